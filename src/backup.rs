@@ -0,0 +1,190 @@
+//! Backup planning for single titles.
+//!
+//! [`Disc::backup_plan`] lists every file a playlist needs to be played back:
+//! the playlist itself and the stream and clip information files of all the
+//! clips it references, along with their sizes.
+//!
+//! [`Disc::backup_plan`]: ../disc/struct.Disc.html#method.backup_plan
+
+use crate::disc::Disc;
+use crate::types::{Clip, Mpls};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// The kind of a [`BackupFile`].
+///
+/// [`BackupFile`]: struct.BackupFile.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FileKind {
+    /// The movie playlist, `PLAYLIST/<number>.mpls`.
+    Playlist,
+    /// A clip information file, `CLIPINF/<name>.clpi`.
+    ClipInfo,
+    /// A transport stream, `STREAM/<name>.m2ts`.
+    Stream,
+    /// An interleaved stereoscopic stream, `STREAM/SSIF/<name>.ssif`.
+    Ssif,
+}
+
+/// A file that is part of a [`BackupPlan`].
+///
+/// [`BackupPlan`]: struct.BackupPlan.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupFile {
+    pub kind: FileKind,
+    /// The clip the file belongs to, or `None` for the playlist.
+    pub clip: Option<String>,
+    /// The path of the file on disc.
+    pub path: PathBuf,
+    /// The path of the file relative to the `BDMV` directory, e.g.
+    /// `STREAM/00055.m2ts`.
+    pub relative_path: PathBuf,
+    /// The file size in bytes.
+    pub size: u64,
+}
+
+/// All files needed to back up a single playlist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPlan {
+    /// The playlist number, e.g. 800 for `00800.mpls`.
+    pub playlist: u16,
+    /// The angle the plan is restricted to, if any.
+    pub angle: Option<u8>,
+    /// The clips the playlist references, sorted.
+    pub clips: Vec<String>,
+    /// The files that exist, grouped by clip; the playlist comes first.
+    pub files: Vec<BackupFile>,
+    /// The stream and clip information files that are referenced but don't
+    /// exist. A clip's stream counts as present if either its `.m2ts` or its
+    /// `.ssif` file exists.
+    pub missing: Vec<PathBuf>,
+}
+
+impl BackupPlan {
+    /// Gets the total size of all files in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Returns whether all referenced files exist.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+// the clips a playlist needs, either for all angles or for one of them
+fn clips(mpls: &Mpls, angle: Option<u8>) -> Vec<&Clip> {
+    let angle = match angle {
+        Some(a) => a,
+        None => return mpls.clips(),
+    };
+    let mut clips: Vec<&Clip> = match mpls.angles().iter().find(|a| a.index == angle) {
+        Some(a) => a.segments(),
+        None => mpls.play_list.play_items.iter().map(|p| &p.clip).collect(),
+    };
+    // sub play items' multi-clip entries are their clips for the other angles
+    for sub_path in mpls.play_list.sub_paths.iter() {
+        for item in sub_path.play_items.iter() {
+            let clip = match angle {
+                0 => &item.clip,
+                n => item
+                    .multi_clip_entries
+                    .get(n as usize - 1)
+                    .unwrap_or(&item.clip),
+            };
+            clips.push(clip);
+        }
+    }
+    clips
+}
+
+fn backup_file(
+    disc: &Disc,
+    kind: FileKind,
+    clip: Option<&str>,
+    path: PathBuf,
+) -> Option<BackupFile> {
+    let size = disc.file_size(&path).ok()?;
+    let relative_path = path.strip_prefix(&disc.root).unwrap_or(&path).to_path_buf();
+    Some(BackupFile {
+        kind,
+        clip: clip.map(String::from),
+        path,
+        relative_path,
+        size,
+    })
+}
+
+impl Disc {
+    /// Lists all files needed to back up a playlist.
+    ///
+    /// These are the playlist itself and, for every clip of all angles, sub
+    /// paths and multi-clip entries, its clip information file, its transport
+    /// stream and, on 3D discs, its interleaved stereoscopic stream. If an
+    /// `angle` is given, only the clips of that angle are included.
+    ///
+    /// Returns `None` if the disc has no such playlist.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), mpls::MplsError> {
+    /// use mpls::Disc;
+    ///
+    /// let disc = Disc::open("/mnt/bluray")?;
+    /// let plan = disc.backup_plan(800, None).expect("no such playlist");
+    /// for file in plan.files.iter() {
+    ///     println!("{} ({} bytes)", file.relative_path.display(), file.size);
+    /// }
+    /// println!("{} bytes in total", plan.total_size());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn backup_plan(&self, playlist: u16, angle: Option<u8>) -> Option<BackupPlan> {
+        let mpls = self.playlist(playlist)?;
+        let root = &self.root;
+        let name = format!("{:05}", playlist);
+
+        let mut files = Vec::new();
+        let mut missing = Vec::new();
+        match self.find_file(&root.join("PLAYLIST"), &name, "mpls") {
+            Some(path) => files.extend(backup_file(self, FileKind::Playlist, None, path)),
+            None => missing.push(root.join("PLAYLIST").join(format!("{}.mpls", name))),
+        }
+
+        let clips: BTreeSet<&str> = clips(mpls, angle)
+            .into_iter()
+            .map(|c| c.file_name.as_str())
+            .collect();
+        for clip in clips.iter() {
+            let clip_files = self.clip_files(&Clip {
+                file_name: clip.to_string(),
+                codec_id: "M2TS".into(),
+                stc_id: 0,
+            });
+            if clip_files.clip_info.is_none() {
+                missing.push(root.join("CLIPINF").join(format!("{}.clpi", clip)));
+            }
+            if clip_files.stream.is_none() && clip_files.ssif.is_none() {
+                missing.push(root.join("STREAM").join(format!("{}.m2ts", clip)));
+            }
+            let paths = [
+                (FileKind::ClipInfo, clip_files.clip_info),
+                (FileKind::Stream, clip_files.stream),
+                (FileKind::Ssif, clip_files.ssif),
+            ];
+            for (kind, path) in paths.iter().cloned() {
+                if let Some(path) = path {
+                    files.extend(backup_file(self, kind, Some(clip), path));
+                }
+            }
+        }
+
+        Some(BackupPlan {
+            playlist,
+            angle,
+            clips: clips.into_iter().map(String::from).collect(),
+            files,
+            missing,
+        })
+    }
+}
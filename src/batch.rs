@@ -0,0 +1,281 @@
+//! Parallel parsing of large collections of playlists and clip information
+//! files.
+//!
+//! This module requires the `rayon` feature. [`parse_files`] and
+//! [`for_each_file`] parse many MPLS and CLPI files concurrently on a
+//! [rayon] thread pool. Files are memory-mapped instead of being read into a
+//! buffer, and the kind of each file is detected from its header, so the
+//! paths of a whole library can be passed in at once (see [`find_files`]).
+//!
+//! Each worker thread maps and parses one file at a time, so the memory used
+//! for reading files is bounded by the number of threads times
+//! [`BatchOptions::max_file_size`]. [`for_each_file`] hands each result to a
+//! callback as soon as it is ready, so that scans don't have to keep all
+//! parsed files in memory.
+//!
+//! [`parse_files`]: fn.parse_files.html
+//! [`for_each_file`]: fn.for_each_file.html
+//! [`find_files`]: fn.find_files.html
+//! [`BatchOptions::max_file_size`]: struct.BatchOptions.html#structfield.max_file_size
+//! [rayon]: https://docs.rs/rayon
+
+use crate::clpi::parser::parse_clpi;
+use crate::clpi::Clpi;
+use crate::parser::parse_mpls;
+use crate::types::Mpls;
+use crate::MplsError;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A parsed playlist or clip information file.
+#[derive(Debug, Clone)]
+pub enum Parsed {
+    Mpls(Mpls),
+    Clpi(Clpi),
+}
+
+impl Parsed {
+    /// Gets the playlist, if the file was one.
+    pub fn mpls(&self) -> Option<&Mpls> {
+        match self {
+            Parsed::Mpls(mpls) => Some(mpls),
+            Parsed::Clpi(_) => None,
+        }
+    }
+
+    /// Gets the clip information, if the file was a clip information file.
+    pub fn clpi(&self) -> Option<&Clpi> {
+        match self {
+            Parsed::Mpls(_) => None,
+            Parsed::Clpi(clpi) => Some(clpi),
+        }
+    }
+}
+
+/// The result of parsing a single file.
+#[derive(Debug)]
+pub struct FileResult {
+    pub path: PathBuf,
+    /// The file size in bytes, or 0 if the file couldn't be opened.
+    pub size: u64,
+    /// The time it took to map and parse the file.
+    pub elapsed: Duration,
+    pub result: Result<Parsed, MplsError>,
+}
+
+/// Options for [`parse_files`] and [`for_each_file`].
+///
+/// [`parse_files`]: fn.parse_files.html
+/// [`for_each_file`]: fn.for_each_file.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// The number of worker threads, or `None` to use rayon's global thread
+    /// pool.
+    pub threads: Option<usize>,
+    /// The size of the largest file that is parsed, in bytes. Larger files
+    /// fail with an error without being read. Defaults to 16 MiB, far more
+    /// than any playlist or clip information file needs.
+    pub max_file_size: u64,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            threads: None,
+            max_file_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+fn invalid_data(msg: String) -> MplsError {
+    MplsError::IoError(io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+// parses a file's bytes as MPLS or CLPI, depending on its type indicator
+fn parse_bytes(bytes: &[u8]) -> Result<Parsed, MplsError> {
+    match bytes.get(..4) {
+        Some(b"MPLS") => parse_mpls(bytes)
+            .map(|(_, mpls)| Parsed::Mpls(mpls))
+            .map_err(|_| MplsError::ParseError),
+        Some(b"HDMV") => parse_clpi(bytes)
+            .map(|(_, clpi)| Parsed::Clpi(clpi))
+            .map_err(|_| MplsError::ClpiParseError),
+        _ => Err(invalid_data(
+            "not a playlist or clip information file".into(),
+        )),
+    }
+}
+
+fn map_and_parse(path: &Path, max_file_size: u64, size: &mut u64) -> Result<Parsed, MplsError> {
+    let file = File::open(path)?;
+    *size = file.metadata()?.len();
+    if *size > max_file_size {
+        return Err(invalid_data(format!(
+            "{} is larger than {} bytes",
+            path.display(),
+            max_file_size
+        )));
+    }
+    if *size == 0 {
+        // empty files can't be mapped on all platforms
+        return parse_bytes(&[]);
+    }
+    // Safety: the mapping is only read while the file is open. If another
+    // process truncates the file in the meantime, reading the mapping may
+    // fail, as with any memory-mapped I/O.
+    let map = unsafe { Mmap::map(&file)? };
+    parse_bytes(&map)
+}
+
+/// Memory-maps and parses a single playlist or clip information file.
+///
+/// Whether the file is a playlist or clip information is detected from its
+/// type indicator (`MPLS` or `HDMV`), not its extension. Files larger than
+/// `max_file_size` bytes fail without being read.
+pub fn parse_file<P: AsRef<Path>>(path: P, max_file_size: u64) -> FileResult {
+    let path = path.as_ref();
+    let start = Instant::now();
+    let mut size = 0;
+    let result = map_and_parse(path, max_file_size, &mut size);
+    FileResult {
+        path: path.to_path_buf(),
+        size,
+        elapsed: start.elapsed(),
+        result,
+    }
+}
+
+fn thread_pool(options: &BatchOptions) -> Result<Option<ThreadPool>, MplsError> {
+    match options.threads {
+        None => Ok(None),
+        Some(n) => rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map(Some)
+            .map_err(|e| MplsError::IoError(io::Error::other(e))),
+    }
+}
+
+/// Parses many playlist and clip information files concurrently, see
+/// [`parse_file`].
+///
+/// The results are returned in the order of `paths`. Fails only if the
+/// thread pool can't be created; errors of individual files are part of
+/// their results.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> Result<(), mpls::MplsError> {
+/// use mpls::batch::{self, BatchOptions};
+///
+/// let paths = batch::find_files("/library")?;
+/// for file in batch::parse_files(&paths, &BatchOptions::default())? {
+///     match file.result {
+///         Ok(_) => println!("{}: {:?}", file.path.display(), file.elapsed),
+///         Err(e) => println!("{}: {}", file.path.display(), e),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`parse_file`]: fn.parse_file.html
+pub fn parse_files<P>(paths: &[P], options: &BatchOptions) -> Result<Vec<FileResult>, MplsError>
+where
+    P: AsRef<Path> + Sync,
+{
+    let parse = || {
+        paths
+            .par_iter()
+            .map(|p| parse_file(p, options.max_file_size))
+            .collect()
+    };
+    Ok(match thread_pool(options)? {
+        Some(pool) => pool.install(parse),
+        None => parse(),
+    })
+}
+
+/// Parses many playlist and clip information files concurrently, passing
+/// each result to `f` as soon as it's ready, in no particular order.
+///
+/// Unlike [`parse_files`], this doesn't keep the parsed files in memory, so
+/// it's suited for scans that only extract some information from each file.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> Result<(), mpls::MplsError> {
+/// use mpls::batch::{self, BatchOptions};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// let paths = batch::find_files("/library")?;
+/// let failed = AtomicUsize::new(0);
+/// batch::for_each_file(&paths, &BatchOptions::default(), |file| {
+///     if file.result.is_err() {
+///         failed.fetch_add(1, Ordering::Relaxed);
+///     }
+/// })?;
+/// println!("{} of {} files failed to parse", failed.into_inner(), paths.len());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`parse_files`]: fn.parse_files.html
+pub fn for_each_file<P, F>(paths: &[P], options: &BatchOptions, f: F) -> Result<(), MplsError>
+where
+    P: AsRef<Path> + Sync,
+    F: Fn(FileResult) + Sync + Send,
+{
+    let parse = || {
+        paths
+            .par_iter()
+            .for_each(|p| f(parse_file(p, options.max_file_size)))
+    };
+    match thread_pool(options)? {
+        Some(pool) => pool.install(parse),
+        None => parse(),
+    }
+    Ok(())
+}
+
+fn is_candidate(path: &Path) -> bool {
+    match path.extension().and_then(OsStr::to_str) {
+        Some(ext) => ext.eq_ignore_ascii_case("mpls") || ext.eq_ignore_ascii_case("clpi"),
+        None => false,
+    }
+}
+
+/// Recursively finds all `.mpls` and `.clpi` files below a directory, e.g.
+/// a whole library of disc backups.
+///
+/// The paths are sorted. Files and directories that can't be read are
+/// skipped, except for `root` itself.
+pub fn find_files<P: AsRef<Path>>(root: P) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.as_ref().to_path_buf()];
+    let mut first = true;
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if first => return Err(e),
+            Err(_) => continue,
+        };
+        first = false;
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => dirs.push(path),
+                Ok(t) if t.is_file() && is_candidate(&path) => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,14 @@
+//! A BD-J object file (`.bdjo`) parser.
+//!
+//! BD-J titles run Java applications instead of HDMV movie objects. Each
+//! BD-J title of the index table names an object in `BDMV/BDJO` that, among
+//! other things, lists the playlists the title's applications may play. The
+//! entry point is the [`Bdjo`] struct, which can be obtained through its
+//! [`from`] method.
+//!
+//! [`Bdjo`]: struct.Bdjo.html
+//! [`from`]: struct.Bdjo.html#method.from
+mod parser;
+mod types;
+
+pub use types::*;
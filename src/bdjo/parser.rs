@@ -0,0 +1,151 @@
+use super::types::{AccessiblePlaylists, AppCacheEntry, Bdjo, TerminalInfo};
+use crate::parser::{str_len, str_len_owned, version};
+use nom::{
+    count, do_parse,
+    error::ErrorKind,
+    map,
+    multi::{length_data, length_value},
+    number::complete::{be_u16, be_u32, be_u8},
+    take, Err, IResult,
+};
+
+// matches the ASCII/UTF-8 string "BDJO"
+fn header_tag(input: &[u8]) -> IResult<&[u8], &str> {
+    let (rest, s) = str_len(4, input)?;
+    if s == "BDJO" {
+        Ok((rest, s))
+    } else {
+        Err(Err::Error((input, ErrorKind::Tag)))
+    }
+}
+
+// a five character file name, e.g. "00000"
+fn file_name(input: &[u8]) -> IResult<&[u8], String> {
+    str_len_owned(5, input)
+}
+
+fn language_code(input: &[u8]) -> IResult<&[u8], String> {
+    str_len_owned(3, input)
+}
+
+fn terminal_info(input: &[u8]) -> IResult<&[u8], TerminalInfo> {
+    fn parser(input: &[u8]) -> IResult<&[u8], TerminalInfo> {
+        do_parse!(
+            input,
+            default_font_file_name: file_name
+                >> flags: be_u8
+                >> (TerminalInfo {
+                    default_font_file_name,
+                    initial_havi_configuration_id: flags >> 4,
+                    menu_call_mask: flags & 0x08 != 0,
+                    title_search_mask: flags & 0x04 != 0,
+                })
+        )
+    }
+
+    // the terminal info is followed by reserved bytes
+    let (input, data) = length_data(be_u32)(input)?;
+    let (_, info) = parser(data)?;
+    Ok((input, info))
+}
+
+fn app_cache_info(input: &[u8]) -> IResult<&[u8], Vec<AppCacheEntry>> {
+    fn entry(input: &[u8]) -> IResult<&[u8], AppCacheEntry> {
+        do_parse!(
+            input,
+            entry_type: be_u8
+                >> name: file_name
+                >> language: language_code
+                >> take!(3usize)
+                >> (AppCacheEntry {
+                    entry_type,
+                    name,
+                    language,
+                })
+        )
+    }
+    fn parser(input: &[u8]) -> IResult<&[u8], Vec<AppCacheEntry>> {
+        do_parse!(
+            input,
+            n_entries: be_u8
+                >> take!(1usize)
+                >> entries: count!(entry, n_entries as usize)
+                >> (entries)
+        )
+    }
+
+    let (input, data) = length_data(be_u32)(input)?;
+    let (_, entries) = parser(data)?;
+    Ok((input, entries))
+}
+
+fn accessible_playlists(input: &[u8]) -> IResult<&[u8], AccessiblePlaylists> {
+    fn playlist(input: &[u8]) -> IResult<&[u8], String> {
+        do_parse!(input, name: file_name >> take!(1usize) >> (name))
+    }
+    fn parser(input: &[u8]) -> IResult<&[u8], AccessiblePlaylists> {
+        do_parse!(
+            input,
+            flags: be_u32
+                >> n_playlists: map!(be_u16, |n| n >> 5)
+                >> playlists: count!(playlist, n_playlists as usize)
+                >> (AccessiblePlaylists {
+                    access_to_all: flags & 0x8000_0000 != 0,
+                    autostart_first_playlist: flags & 0x4000_0000 != 0,
+                    playlists,
+                })
+        )
+    }
+
+    length_value(be_u32, parser)(input)
+}
+
+pub fn parse_bdjo(input: &[u8]) -> IResult<&[u8], Bdjo> {
+    let (input, (version, terminal_info, app_cache_info, accessible_playlists)) = do_parse!(
+        input,
+        header_tag
+            >> version: version
+            // extension data address and reserved bytes
+            >> take!(20usize)
+            >> terminal_info: terminal_info
+            >> app_cache_info: app_cache_info
+            >> accessible_playlists: accessible_playlists
+            >> ((version, terminal_info, app_cache_info, accessible_playlists))
+    )?;
+    let (input, application_management_table) = length_data(be_u32)(input)?;
+
+    Ok((
+        input,
+        Bdjo {
+            version: version.into(),
+            terminal_info,
+            app_cache_info,
+            accessible_playlists,
+            application_management_table: application_management_table.to_vec(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn header_tag() {
+        let data = [0x42, 0x44, 0x4a, 0x4f, 0x30];
+        let sl = &data[..];
+        assert_eq!(super::header_tag(sl), Ok((&sl[4..], "BDJO")));
+    }
+
+    #[test]
+    fn accessible_playlists() {
+        // autostart, two playlists
+        let data = [
+            0x00, 0x00, 0x00, 0x12, 0x40, 0x00, 0x00, 0x00, 0x00, 0x40, 0x30, 0x30, 0x38, 0x30,
+            0x30, 0x00, 0x30, 0x30, 0x30, 0x30, 0x31, 0x00,
+        ];
+        let (rest, playlists) = super::accessible_playlists(&data[..]).unwrap();
+        assert!(rest.is_empty());
+        assert!(!playlists.access_to_all);
+        assert!(playlists.autostart_first_playlist);
+        assert_eq!(playlists.playlists, &["00800", "00001"]);
+    }
+}
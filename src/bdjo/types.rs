@@ -0,0 +1,105 @@
+use super::parser::parse_bdjo;
+use crate::MplsError;
+use std::io::Read;
+
+/// A BD-J object, `BDMV/BDJO/<name>.bdjo`.
+#[derive(Debug, Clone)]
+pub struct Bdjo {
+    /// The file format version, e.g. "0200".
+    pub version: String,
+    pub terminal_info: TerminalInfo,
+    /// The files that are loaded into the application cache.
+    pub app_cache_info: Vec<AppCacheEntry>,
+    pub accessible_playlists: AccessiblePlaylists,
+    /// The raw application management table, which describes the Java
+    /// applications of the title. Its contents aren't decoded.
+    pub application_management_table: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TerminalInfo {
+    pub default_font_file_name: String,
+    pub initial_havi_configuration_id: u8,
+    /// Whether the menu call user operation is disabled.
+    pub menu_call_mask: bool,
+    /// Whether the title search user operation is disabled.
+    pub title_search_mask: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AppCacheEntry {
+    /// 1 for a JAR file, 2 for a directory.
+    pub entry_type: u8,
+    /// The name of the file or directory in `BDMV/JAR`.
+    pub name: String,
+    pub language: String,
+}
+
+/// The playlists a BD-J title's applications may play.
+#[derive(Debug, Clone)]
+pub struct AccessiblePlaylists {
+    /// Whether all playlists of the disc are accessible, regardless of
+    /// [`playlists`].
+    ///
+    /// [`playlists`]: #structfield.playlists
+    pub access_to_all: bool,
+    /// Whether the first of the [`playlists`] is played automatically when
+    /// the title starts.
+    ///
+    /// [`playlists`]: #structfield.playlists
+    pub autostart_first_playlist: bool,
+    /// The playlist file names, e.g. "00800" for `00800.mpls`.
+    pub playlists: Vec<String>,
+}
+
+impl Bdjo {
+    /// Attempts to parse a BD-J object from the given reader.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::fs::File;
+    /// use mpls::Bdjo;
+    ///
+    /// let file = File::open("00000.bdjo")?;
+    /// let bdjo = Bdjo::from(&file).expect("failed to parse BDJO file.");
+    /// println!("accessible playlists: {:?}", bdjo.playlists());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from<R: Read>(mut reader: R) -> Result<Bdjo, MplsError> {
+        let bytes = {
+            let mut buffer = Vec::new();
+            reader.read_to_end(&mut buffer)?;
+            buffer
+        };
+
+        parse_bdjo(&bytes)
+            .map_err(|_| MplsError::BdjoParseError)
+            .map(|(_, b)| b)
+    }
+
+    /// Gets the numbers of the accessible playlists, in the order they are
+    /// listed in.
+    ///
+    /// This doesn't take [`AccessiblePlaylists::access_to_all`] into account.
+    ///
+    /// [`AccessiblePlaylists::access_to_all`]: struct.AccessiblePlaylists.html#structfield.access_to_all
+    pub fn playlists(&self) -> Vec<u16> {
+        self.accessible_playlists
+            .playlists
+            .iter()
+            .filter_map(|p| p.parse().ok())
+            .collect()
+    }
+
+    /// Gets the number of the playlist that is played automatically when the
+    /// title starts, if any.
+    pub fn autostart_playlist(&self) -> Option<u16> {
+        if self.accessible_playlists.autostart_first_playlist {
+            self.accessible_playlists.playlists.first()?.parse().ok()
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,665 @@
+//! Chapter extraction and export.
+//!
+//! Chapters are derived from a playlist's entry point marks (see
+//! [`Mpls::chapters`]) and are placed on the playlist timeline, i.e. their
+//! start and end times are relative to the beginning of the playlist rather
+//! than to the clip the mark points into.
+//!
+//! This module can write chapters in a few common text formats:
+//!
+//! * Matroska chapter XML, as consumed by `mkvmerge` ([`write_matroska_xml`]),
+//! * OGM `CHAPTERxx=` text ([`write_ogm`]),
+//! * FFmpeg metadata files ([`write_ffmetadata`]) and
+//! * HandBrake-style chapter name CSV ([`write_csv`]).
+//!
+//! All formats that carry times use the same rounding: time stamps are rounded
+//! to the nearest millisecond (see [`TimeStamp::millis`]), so a chapter starts
+//! at the exact same millisecond regardless of the output format.
+//!
+//! Chapters can also be read back from Matroska XML ([`read_matroska_xml`])
+//! and OGM ([`read_ogm`]) files, and written into a playlist's marks through
+//! [`Mpls::set_chapters`] or [`Mpls::merge_chapters`].
+//!
+//! [`Mpls::chapters`]: ../types/struct.Mpls.html#method.chapters
+//! [`write_matroska_xml`]: fn.write_matroska_xml.html
+//! [`write_ogm`]: fn.write_ogm.html
+//! [`write_ffmetadata`]: fn.write_ffmetadata.html
+//! [`write_csv`]: fn.write_csv.html
+//! [`TimeStamp::millis`]: ../types/struct.TimeStamp.html#method.millis
+//! [`read_matroska_xml`]: fn.read_matroska_xml.html
+//! [`read_ogm`]: fn.read_ogm.html
+//! [`Mpls::set_chapters`]: ../types/struct.Mpls.html#method.set_chapters
+//! [`Mpls::merge_chapters`]: ../types/struct.Mpls.html#method.merge_chapters
+
+use crate::types::{MarkType, Mpls, PlayListMark, TimeStamp};
+use crate::MplsError;
+use std::io::{self, Write};
+
+/// A chapter on the playlist timeline.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    /// The chapter's start time, relative to the start of the playlist.
+    pub start: TimeStamp,
+    /// The chapter's end time, relative to the start of the playlist.
+    pub end: TimeStamp,
+    /// The chapter's names. Blu-ray playlists don't carry chapter names, so
+    /// this is empty for chapters obtained through [`Mpls::chapters`].
+    ///
+    /// [`Mpls::chapters`]: ../types/struct.Mpls.html#method.chapters
+    pub names: Vec<ChapterName>,
+}
+
+/// A chapter name, optionally tagged with a language.
+#[derive(Debug, Clone)]
+pub struct ChapterName {
+    /// The name itself.
+    pub name: String,
+    /// The name's ISO 639-2 language code (e.g. "eng"), if known.
+    pub language: Option<String>,
+}
+
+impl Chapter {
+    /// Returns the chapter's first name, or `None` if it doesn't have one.
+    pub fn name(&self) -> Option<&str> {
+        self.names.first().map(|n| n.name.as_ref())
+    }
+
+    /// Returns the chapter's duration.
+    pub fn duration(&self) -> TimeStamp {
+        TimeStamp(self.end.0.saturating_sub(self.start.0))
+    }
+}
+
+impl Mpls {
+    /// Gets the playlist's chapters.
+    ///
+    /// Every entry point mark is turned into a chapter, ordered by start time.
+    /// Marks that share the same playlist time are collapsed into a single
+    /// chapter. Each chapter ends where the next one starts, and the last
+    /// chapter ends at the end of the playlist.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::Mpls;
+    ///
+    /// # let mpls = {
+    /// #     let bytes = include_bytes!("../assets/simple.mpls");
+    /// #     Mpls::from(&bytes[..]).unwrap()
+    /// # };
+    /// let chapters = mpls.chapters();
+    /// assert_eq!(chapters.len(), 21);
+    /// assert_eq!(chapters[0].start.0, 0);
+    /// assert_eq!(chapters[20].end, mpls.duration());
+    /// ```
+    pub fn chapters(&self) -> Vec<Chapter> {
+        let mut starts: Vec<TimeStamp> = self
+            .marks
+            .iter()
+            .filter(|m| matches!(m.mark_type, MarkType::EntryPoint))
+            .filter_map(|m| self.playlist_time(m.play_item, m.time_stamp))
+            .collect();
+        starts.sort();
+        starts.dedup();
+
+        let duration = self.duration();
+        let mut chapters = Vec::with_capacity(starts.len());
+        for (i, start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(duration);
+            chapters.push(Chapter {
+                start: *start,
+                end,
+                names: Vec::new(),
+            });
+        }
+        chapters
+    }
+
+    /// Replaces the playlist's chapters with the given ones.
+    ///
+    /// All entry point marks are removed and a new entry point mark is created
+    /// for every chapter's start time. Link points are kept. Chapters that
+    /// start at or beyond the end of the playlist are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::{Chapter, Mpls, TimeStamp};
+    ///
+    /// # let mut mpls = {
+    /// #     let bytes = include_bytes!("../assets/simple.mpls");
+    /// #     Mpls::from(&bytes[..]).unwrap()
+    /// # };
+    /// let chapter = |t| Chapter { start: TimeStamp(t), end: TimeStamp(t), names: Vec::new() };
+    /// mpls.set_chapters(&[chapter(0), chapter(45_000 * 60)]);
+    ///
+    /// let chapters = mpls.chapters();
+    /// assert_eq!(chapters.len(), 2);
+    /// assert_eq!(chapters[1].start, TimeStamp(45_000 * 60));
+    /// ```
+    pub fn set_chapters(&mut self, chapters: &[Chapter]) {
+        self.marks
+            .retain(|m| !matches!(m.mark_type, MarkType::EntryPoint));
+        self.merge_chapters(chapters);
+    }
+
+    /// Adds the given chapters to the playlist's existing chapters.
+    ///
+    /// A new entry point mark is created for every chapter that doesn't start
+    /// at the same time as an existing chapter. Chapters that start at or
+    /// beyond the end of the playlist are ignored.
+    pub fn merge_chapters(&mut self, chapters: &[Chapter]) {
+        let existing: Vec<TimeStamp> = self.chapters().iter().map(|c| c.start).collect();
+        for chapter in chapters.iter() {
+            if existing.contains(&chapter.start) {
+                continue;
+            }
+            if let Some((play_item, time_stamp)) = self.clip_time(chapter.start) {
+                self.marks.push(PlayListMark {
+                    mark_type: MarkType::EntryPoint,
+                    play_item,
+                    time_stamp,
+                    entry_es_pid: 0xFFFF,
+                    duration: None,
+                });
+            }
+        }
+        self.marks.sort_by_key(|m| (m.play_item.0, m.time_stamp));
+        self.marks
+            .dedup_by_key(|m| (m.play_item.0, m.time_stamp, m.mark_type as u8));
+    }
+}
+
+fn default_name(index: usize) -> String {
+    format!("Chapter {:02}", index + 1)
+}
+
+fn chapter_name(chapter: &Chapter, index: usize) -> String {
+    chapter
+        .name()
+        .map(String::from)
+        .unwrap_or_else(|| default_name(index))
+}
+
+// formats milliseconds as HH:MM:SS.mmm
+pub(crate) fn format_millis(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1_000) % 60,
+        ms % 1_000
+    )
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Writes chapters as a Matroska chapter XML file.
+///
+/// Every chapter name becomes a `ChapterDisplay` element, with a
+/// `ChapterLanguage` if the name has a language. Chapters without names get a
+/// generic "Chapter NN" name. Times are written with nanosecond precision, but
+/// are rounded to the nearest millisecond first.
+///
+/// # Examples
+/// ```
+/// use mpls::Mpls;
+/// use mpls::chapters::write_matroska_xml;
+///
+/// # let mpls = {
+/// #     let bytes = include_bytes!("../assets/tiny.mpls");
+/// #     Mpls::from(&bytes[..]).unwrap()
+/// # };
+/// let mut xml = Vec::new();
+/// write_matroska_xml(&mpls.chapters(), &mut xml).unwrap();
+/// let xml = String::from_utf8(xml).unwrap();
+/// assert!(xml.contains("<ChapterTimeStart>00:00:00.000000000</ChapterTimeStart>"));
+/// ```
+pub fn write_matroska_xml<W: Write>(chapters: &[Chapter], mut writer: W) -> io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<!DOCTYPE Chapters SYSTEM "matroskachapters.dtd">"#
+    )?;
+    writeln!(writer, "<Chapters>")?;
+    writeln!(writer, "  <EditionEntry>")?;
+    for (i, chapter) in chapters.iter().enumerate() {
+        writeln!(writer, "    <ChapterAtom>")?;
+        writeln!(
+            writer,
+            "      <ChapterTimeStart>{}000000</ChapterTimeStart>",
+            format_millis(chapter.start.millis())
+        )?;
+        writeln!(
+            writer,
+            "      <ChapterTimeEnd>{}000000</ChapterTimeEnd>",
+            format_millis(chapter.end.millis())
+        )?;
+        if chapter.names.is_empty() {
+            writeln!(writer, "      <ChapterDisplay>")?;
+            writeln!(
+                writer,
+                "        <ChapterString>{}</ChapterString>",
+                default_name(i)
+            )?;
+            writeln!(writer, "      </ChapterDisplay>")?;
+        }
+        for name in chapter.names.iter() {
+            writeln!(writer, "      <ChapterDisplay>")?;
+            writeln!(
+                writer,
+                "        <ChapterString>{}</ChapterString>",
+                escape_xml(&name.name)
+            )?;
+            if let Some(lang) = &name.language {
+                writeln!(
+                    writer,
+                    "        <ChapterLanguage>{}</ChapterLanguage>",
+                    escape_xml(lang)
+                )?;
+            }
+            writeln!(writer, "      </ChapterDisplay>")?;
+        }
+        writeln!(writer, "    </ChapterAtom>")?;
+    }
+    writeln!(writer, "  </EditionEntry>")?;
+    writeln!(writer, "</Chapters>")
+}
+
+/// Writes chapters in the OGM chapter text format.
+///
+/// Each chapter produces a `CHAPTERxx=HH:MM:SS.mmm` line followed by a
+/// `CHAPTERxxNAME=` line. Only the first name of each chapter is written.
+///
+/// # Examples
+/// ```
+/// use mpls::Mpls;
+/// use mpls::chapters::write_ogm;
+///
+/// # let mpls = {
+/// #     let bytes = include_bytes!("../assets/tiny.mpls");
+/// #     Mpls::from(&bytes[..]).unwrap()
+/// # };
+/// let mut ogm = Vec::new();
+/// write_ogm(&mpls.chapters(), &mut ogm).unwrap();
+/// assert_eq!(
+///     String::from_utf8(ogm).unwrap(),
+///     "CHAPTER01=00:00:00.000\nCHAPTER01NAME=Chapter 01\n"
+/// );
+/// ```
+pub fn write_ogm<W: Write>(chapters: &[Chapter], mut writer: W) -> io::Result<()> {
+    for (i, chapter) in chapters.iter().enumerate() {
+        writeln!(
+            writer,
+            "CHAPTER{:02}={}",
+            i + 1,
+            format_millis(chapter.start.millis())
+        )?;
+        writeln!(
+            writer,
+            "CHAPTER{:02}NAME={}",
+            i + 1,
+            chapter_name(chapter, i)
+        )?;
+    }
+    Ok(())
+}
+
+fn escape_ffmetadata(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if let '=' | ';' | '#' | '\\' | '\n' = c {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Writes chapters as an FFmpeg metadata file (`;FFMETADATA1`).
+///
+/// Chapters use a time base of 1/1000, i.e. start and end times are written in
+/// milliseconds. Only the first name of each chapter is written.
+///
+/// # Examples
+/// ```
+/// use mpls::Mpls;
+/// use mpls::chapters::write_ffmetadata;
+///
+/// # let mpls = {
+/// #     let bytes = include_bytes!("../assets/tiny.mpls");
+/// #     Mpls::from(&bytes[..]).unwrap()
+/// # };
+/// let mut meta = Vec::new();
+/// write_ffmetadata(&mpls.chapters(), &mut meta).unwrap();
+/// assert!(String::from_utf8(meta).unwrap().starts_with(";FFMETADATA1\n"));
+/// ```
+pub fn write_ffmetadata<W: Write>(chapters: &[Chapter], mut writer: W) -> io::Result<()> {
+    writeln!(writer, ";FFMETADATA1")?;
+    for (i, chapter) in chapters.iter().enumerate() {
+        writeln!(writer)?;
+        writeln!(writer, "[CHAPTER]")?;
+        writeln!(writer, "TIMEBASE=1/1000")?;
+        writeln!(writer, "START={}", chapter.start.millis())?;
+        writeln!(writer, "END={}", chapter.end.millis())?;
+        writeln!(
+            writer,
+            "title={}",
+            escape_ffmetadata(&chapter_name(chapter, i))
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes chapter names as a HandBrake-style CSV file.
+///
+/// Each line consists of the 1-based chapter number and the chapter's first
+/// name, separated by a comma. Commas within names are escaped with a
+/// backslash. This format doesn't carry any times.
+///
+/// # Examples
+/// ```
+/// use mpls::Mpls;
+/// use mpls::chapters::write_csv;
+///
+/// # let mpls = {
+/// #     let bytes = include_bytes!("../assets/tiny.mpls");
+/// #     Mpls::from(&bytes[..]).unwrap()
+/// # };
+/// let mut csv = Vec::new();
+/// write_csv(&mpls.chapters(), &mut csv).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "1,Chapter 01\n");
+/// ```
+pub fn write_csv<W: Write>(chapters: &[Chapter], mut writer: W) -> io::Result<()> {
+    for (i, chapter) in chapters.iter().enumerate() {
+        writeln!(
+            writer,
+            "{},{}",
+            i + 1,
+            chapter_name(chapter, i).replace(',', "\\,")
+        )?;
+    }
+    Ok(())
+}
+
+// parses HH:MM:SS with an optional fractional second part of arbitrary precision
+fn parse_time(s: &str) -> Result<TimeStamp, MplsError> {
+    let err = || MplsError::ChapterParseError(format!("invalid time \"{}\"", s));
+    let mut parts = s.trim().split(':');
+    let (h, m, sec) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(h), Some(m), Some(s), None) => (h, m, s),
+        _ => return Err(err()),
+    };
+    let (sec, frac) = match sec.find('.') {
+        Some(i) => (&sec[..i], &sec[i + 1..]),
+        None => (sec, ""),
+    };
+    let h: u64 = h.parse().map_err(|_| err())?;
+    let m: u64 = m.parse().map_err(|_| err())?;
+    let sec: u64 = sec.parse().map_err(|_| err())?;
+    if frac.len() > 9 || !frac.chars().all(|c| c.is_ascii_digit()) {
+        return Err(err());
+    }
+    let nanos: u64 = format!("{:0<9}", frac).parse().map_err(|_| err())?;
+    // 45 kHz ticks, rounded to the nearest tick, failing on overflow as the
+    // fields may be arbitrarily large
+    let ticks = h
+        .checked_mul(60)
+        .and_then(|n| n.checked_add(m))
+        .and_then(|n| n.checked_mul(60))
+        .and_then(|n| n.checked_add(sec))
+        .and_then(|n| n.checked_mul(1_000_000_000))
+        .and_then(|n| n.checked_add(nanos))
+        .and_then(|n| n.checked_mul(9))
+        .and_then(|n| n.checked_add(100_000))
+        .map(|n| n / 200_000)
+        .ok_or_else(err)?;
+    if ticks > u32::MAX as u64 {
+        return Err(err());
+    }
+    Ok(TimeStamp(ticks as u32))
+}
+
+fn unescape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            e if e.starts_with("#x") => u32::from_str_radix(&e[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            e if e.starts_with('#') => e[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn fill_in_ends(chapters: &mut [Chapter], explicit_ends: &[bool]) {
+    for i in 0..chapters.len() {
+        if !explicit_ends[i] {
+            chapters[i].end = chapters
+                .get(i + 1)
+                .map(|c| c.start)
+                .unwrap_or(chapters[i].start);
+        }
+    }
+}
+
+/// Reads chapters from a Matroska chapter XML file.
+///
+/// Only the first `EditionEntry` is read. Nested chapter atoms are flattened
+/// into a single list, in document order. Chapters without a
+/// `ChapterTimeEnd` end where the next chapter starts (or, for the last
+/// chapter, where it starts).
+///
+/// # Examples
+/// ```
+/// use mpls::TimeStamp;
+/// use mpls::chapters::read_matroska_xml;
+///
+/// let xml = r#"<Chapters><EditionEntry><ChapterAtom>
+///     <ChapterTimeStart>00:01:00.500000000</ChapterTimeStart>
+///     <ChapterDisplay>
+///       <ChapterString>Opening</ChapterString>
+///       <ChapterLanguage>eng</ChapterLanguage>
+///     </ChapterDisplay>
+///   </ChapterAtom></EditionEntry></Chapters>"#;
+/// let chapters = read_matroska_xml(xml).unwrap();
+/// assert_eq!(chapters[0].start, TimeStamp(2_722_500));
+/// assert_eq!(chapters[0].name(), Some("Opening"));
+/// ```
+pub fn read_matroska_xml(xml: &str) -> Result<Vec<Chapter>, MplsError> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut explicit_ends: Vec<bool> = Vec::new();
+    let mut atoms: Vec<usize> = Vec::new();
+    let mut editions = 0;
+    let mut text = String::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("<!--") {
+            let end = rest
+                .find("-->")
+                .ok_or_else(|| MplsError::ChapterParseError("unterminated comment".into()))?;
+            rest = &rest[end + 3..];
+            continue;
+        }
+        let end = rest
+            .find('>')
+            .ok_or_else(|| MplsError::ChapterParseError("unterminated tag".into()))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') || tag.ends_with('/') {
+            continue;
+        }
+
+        let (closing, name) = match tag.strip_prefix('/') {
+            Some(name) => (true, name.trim()),
+            None => (false, tag.split_whitespace().next().unwrap_or("")),
+        };
+        let value = unescape_xml(text.trim());
+        text.clear();
+
+        if name == "EditionEntry" {
+            if !closing {
+                editions += 1;
+            }
+            continue;
+        }
+        if editions > 1 {
+            continue;
+        }
+
+        match (name, closing) {
+            ("ChapterAtom", false) => {
+                atoms.push(chapters.len());
+                chapters.push(Chapter {
+                    start: TimeStamp(0),
+                    end: TimeStamp(0),
+                    names: Vec::new(),
+                });
+                explicit_ends.push(false);
+            }
+            ("ChapterAtom", true) => {
+                atoms.pop();
+            }
+            _ => {
+                let idx = match atoms.last() {
+                    Some(idx) => *idx,
+                    None => continue,
+                };
+                let chapter = &mut chapters[idx];
+                match (name, closing) {
+                    ("ChapterTimeStart", true) => chapter.start = parse_time(&value)?,
+                    ("ChapterTimeEnd", true) => {
+                        chapter.end = parse_time(&value)?;
+                        explicit_ends[idx] = true;
+                    }
+                    ("ChapterDisplay", false) => chapter.names.push(ChapterName {
+                        name: String::new(),
+                        language: None,
+                    }),
+                    ("ChapterString", true) => {
+                        if let Some(n) = chapter.names.last_mut() {
+                            n.name = value;
+                        }
+                    }
+                    ("ChapterLanguage", true) => {
+                        if let Some(n) = chapter.names.last_mut() {
+                            n.language = Some(value);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fill_in_ends(&mut chapters, &explicit_ends);
+    Ok(chapters)
+}
+
+/// Reads chapters from an OGM chapter text file.
+///
+/// Every `CHAPTERxx=HH:MM:SS.mmm` line starts a chapter, and an optional
+/// `CHAPTERxxNAME=` line names it. Chapters end where the next chapter starts
+/// (or, for the last chapter, where it starts).
+///
+/// # Examples
+/// ```
+/// use mpls::TimeStamp;
+/// use mpls::chapters::read_ogm;
+///
+/// let ogm = "CHAPTER01=00:00:00.000\nCHAPTER01NAME=Intro\nCHAPTER02=00:00:01.000\n";
+/// let chapters = read_ogm(ogm).unwrap();
+/// assert_eq!(chapters.len(), 2);
+/// assert_eq!(chapters[0].end, TimeStamp(45_000));
+/// assert_eq!(chapters[1].name(), None);
+/// ```
+pub fn read_ogm(text: &str) -> Result<Vec<Chapter>, MplsError> {
+    let mut entries: Vec<(String, Chapter)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = match line.find('=') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => {
+                return Err(MplsError::ChapterParseError(format!(
+                    "invalid line \"{}\"",
+                    line
+                )))
+            }
+        };
+        if !key.starts_with("CHAPTER") {
+            continue;
+        }
+        if let Some(id) = key.strip_suffix("NAME") {
+            match entries.iter_mut().find(|(i, _)| i == id) {
+                Some((_, chapter)) => chapter.names.push(ChapterName {
+                    name: value.into(),
+                    language: None,
+                }),
+                None => {
+                    return Err(MplsError::ChapterParseError(format!(
+                        "name for unknown chapter \"{}\"",
+                        id
+                    )))
+                }
+            }
+        } else {
+            let start = parse_time(value)?;
+            entries.push((
+                key.into(),
+                Chapter {
+                    start,
+                    end: start,
+                    names: Vec::new(),
+                },
+            ));
+        }
+    }
+
+    let mut chapters: Vec<Chapter> = entries.into_iter().map(|(_, c)| c).collect();
+    let explicit_ends = vec![false; chapters.len()];
+    fill_in_ends(&mut chapters, &explicit_ends);
+    Ok(chapters)
+}
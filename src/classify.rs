@@ -0,0 +1,308 @@
+//! Playlist classification.
+//!
+//! Every playlist can be labelled with a [`Category`] based on a handful of
+//! [`PlaylistFeatures`]: its duration, playback type, user operation mask,
+//! still modes, random access flags, stream lineup and, if the rest of the
+//! disc is known, how it relates to the other playlists. Each category gets a
+//! score from simple rules whose limits are set by [`Thresholds`], and the
+//! category with the highest score wins.
+//!
+//! The features and scores are part of the result, so that the thresholds can
+//! be tuned against a catalogue of known discs.
+//!
+//! [`Category`]: enum.Category.html
+//! [`PlaylistFeatures`]: struct.PlaylistFeatures.html
+//! [`Thresholds`]: struct.Thresholds.html
+
+use crate::disc::Disc;
+use crate::episodes::find_play_alls;
+use crate::types::{Mpls, PlaybackType, TimeStamp};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What a playlist is used for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    /// A feature film or a cut of it.
+    Feature,
+    /// A playlist that plays other playlists back to back, e.g. all episodes
+    /// of a TV series disc.
+    PlayAll,
+    /// The background video or still image of a menu.
+    MenuLoop,
+    /// A trailer, a logo or a warning screen.
+    Trailer,
+    /// Bonus material and anything else.
+    Extra,
+}
+
+/// The limits of the classification rules.
+#[derive(Debug, Clone)]
+pub struct Thresholds {
+    /// Features are at least this long.
+    pub feature_min_duration: TimeStamp,
+    /// Features are at least this fraction of the longest playlist's
+    /// duration, if that's known.
+    pub feature_min_ratio: f64,
+    /// Trailers are at least this long.
+    pub trailer_min_duration: TimeStamp,
+    /// Trailers are at most this long.
+    pub trailer_max_duration: TimeStamp,
+    /// Menu loops are at most this long.
+    pub menu_max_duration: TimeStamp,
+    /// Playlists that mask at least this many user operations are considered
+    /// to restrict the viewer, as warnings and forced trailers do.
+    pub restricted_min_masked_operations: u32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            feature_min_duration: TimeStamp(60 * 60 * 45_000),
+            feature_min_ratio: 0.75,
+            trailer_min_duration: TimeStamp(30 * 45_000),
+            trailer_max_duration: TimeStamp(4 * 60 * 45_000),
+            menu_max_duration: TimeStamp(3 * 60 * 45_000),
+            restricted_min_masked_operations: 12,
+        }
+    }
+}
+
+/// The properties of a playlist that the classification is based on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistFeatures {
+    pub duration: TimeStamp,
+    pub play_items: usize,
+    pub chapters: usize,
+    pub playback_type: PlaybackType,
+    /// The number of user operations masked by the playlist.
+    pub masked_operations: u32,
+    /// The number of play items with a still mode.
+    pub still_play_items: usize,
+    /// Whether any play item prohibits random access (e.g. skipping).
+    pub random_access_restricted: bool,
+    /// The number of primary video, audio, subtitle and interactive graphics
+    /// streams of the first play item.
+    pub video_streams: usize,
+    pub audio_streams: usize,
+    pub subtitle_streams: usize,
+    pub interactive_graphics_streams: usize,
+    /// The playlist's duration relative to the longest playlist on the disc.
+    pub longest_ratio: Option<f64>,
+    /// Whether the playlist plays other playlists back to back.
+    pub is_play_all: Option<bool>,
+    /// The fraction of the playlist's clips that other playlists play too.
+    pub shared_clip_ratio: Option<f64>,
+}
+
+/// The result of classifying a playlist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub category: Category,
+    /// The winning category's share of all positive scores, between 0 and 1.
+    pub confidence: f64,
+    /// The score of every category.
+    pub scores: Vec<(Category, f64)>,
+    pub features: PlaylistFeatures,
+}
+
+impl PlaylistFeatures {
+    /// Gets the features of a playlist on its own, without disc context.
+    pub fn from_mpls(mpls: &Mpls) -> PlaylistFeatures {
+        let play_items = &mpls.play_list.play_items;
+        let (video, audio, subtitles, ig) = play_items.first().map_or((0, 0, 0, 0), |p| {
+            let table = &p.stream_number_table;
+            (
+                table.primary_video_streams.len(),
+                table.primary_audio_streams.len(),
+                table.primary_pgs_streams.len(),
+                table.primary_igs_streams.len(),
+            )
+        });
+        PlaylistFeatures {
+            duration: mpls.duration(),
+            play_items: play_items.len(),
+            chapters: mpls.chapters().len(),
+            playback_type: mpls.app_info_play_list.playback_type,
+            masked_operations: mpls.app_info_play_list.user_opt_mask.count_ones(),
+            still_play_items: play_items.iter().filter(|p| p.still_mode != 0).count(),
+            random_access_restricted: play_items.iter().any(|p| p.random_access_flag),
+            video_streams: video,
+            audio_streams: audio,
+            subtitle_streams: subtitles,
+            interactive_graphics_streams: ig,
+            longest_ratio: None,
+            is_play_all: None,
+            shared_clip_ratio: None,
+        }
+    }
+}
+
+impl Thresholds {
+    /// Classifies a playlist by its features.
+    pub fn classify(&self, features: &PlaylistFeatures) -> Classification {
+        let f = features;
+        let t = self;
+        let restricted = f.masked_operations >= t.restricted_min_masked_operations;
+        let short = f.duration <= t.menu_max_duration;
+
+        let mut feature: f64 = 0.0;
+        if f.duration >= t.feature_min_duration {
+            feature += 3.0;
+        }
+        match f.longest_ratio {
+            Some(r) if r >= t.feature_min_ratio => feature += 2.0,
+            Some(r) if r < 0.5 => feature -= 2.0,
+            _ => {}
+        }
+        if f.chapters >= 4 {
+            feature += 1.0;
+        }
+        if f.audio_streams >= 2 || f.subtitle_streams >= 2 {
+            feature += 1.0;
+        }
+        if restricted {
+            feature -= 2.0;
+        }
+        if f.random_access_restricted {
+            feature -= 1.0;
+        }
+
+        let play_all: f64 = if f.is_play_all == Some(true) {
+            6.0
+        } else {
+            0.0
+        };
+
+        let mut menu: f64 = 0.0;
+        if f.still_play_items > 0 {
+            menu += 3.0;
+        }
+        if f.interactive_graphics_streams > 0 && short {
+            menu += 2.0;
+        }
+        if let PlaybackType::Random | PlaybackType::Shuffle = f.playback_type {
+            menu += 1.0;
+        }
+        if short && f.audio_streams <= 1 {
+            menu += 1.0;
+        }
+        if short && f.subtitle_streams == 0 {
+            menu += 1.0;
+        }
+
+        let mut trailer: f64 = 0.0;
+        if f.duration >= t.trailer_min_duration && f.duration <= t.trailer_max_duration {
+            trailer += 2.0;
+            if restricted || f.random_access_restricted {
+                trailer += 1.0;
+            }
+            if f.chapters <= 1 {
+                trailer += 1.0;
+            }
+            if f.interactive_graphics_streams == 0 {
+                trailer += 0.5;
+            }
+            if f.still_play_items > 0 {
+                trailer -= 2.0;
+            }
+        }
+
+        let mut extra: f64 = 0.5;
+        if f.duration > t.trailer_max_duration && f.duration < t.feature_min_duration {
+            extra += 2.0;
+        }
+        if f.shared_clip_ratio == Some(0.0) {
+            extra += 1.0;
+        }
+
+        let scores = vec![
+            (Category::Feature, feature),
+            (Category::PlayAll, play_all),
+            (Category::MenuLoop, menu),
+            (Category::Trailer, trailer),
+            (Category::Extra, extra),
+        ];
+        // the first of several equal scores wins
+        let (category, best) = scores
+            .iter()
+            .fold(scores[0], |best, s| if s.1 > best.1 { *s } else { best });
+        let total: f64 = scores.iter().map(|s| s.1.max(0.0)).sum();
+        let confidence = if total > 0.0 { best / total } else { 0.0 };
+
+        Classification {
+            category,
+            confidence,
+            scores,
+            features: features.clone(),
+        }
+    }
+}
+
+impl Mpls {
+    /// Classifies this playlist on its own, see the [`classify`] module.
+    ///
+    /// Use [`Disc::classify`] to take the other playlists of the disc into
+    /// account.
+    ///
+    /// [`classify`]: ../classify/index.html
+    /// [`Disc::classify`]: ../disc/struct.Disc.html#method.classify
+    pub fn classify(&self, thresholds: &Thresholds) -> Classification {
+        thresholds.classify(&PlaylistFeatures::from_mpls(self))
+    }
+}
+
+impl Disc {
+    /// Classifies all playlists of the disc, see the [`classify`] module.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), mpls::MplsError> {
+    /// use mpls::classify::Thresholds;
+    /// use mpls::Disc;
+    ///
+    /// let disc = Disc::open("/mnt/bluray")?;
+    /// for (number, c) in disc.classify(&Thresholds::default()) {
+    ///     println!("{:05}.mpls: {:?} ({:.0}%)", number, c.category, c.confidence * 100.0);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`classify`]: ../classify/index.html
+    pub fn classify(&self, thresholds: &Thresholds) -> BTreeMap<u16, Classification> {
+        let longest = self
+            .playlists
+            .values()
+            .map(Mpls::duration)
+            .max()
+            .unwrap_or_default();
+        let play_alls: BTreeSet<u16> = find_play_alls(&self.playlists)
+            .iter()
+            .map(|p| p.playlist)
+            .collect();
+        // the number of playlists each clip appears in
+        let mut clip_usage: BTreeMap<&str, usize> = BTreeMap::new();
+        for mpls in self.playlists.values() {
+            let clips: BTreeSet<&str> = mpls.clips().iter().map(|c| c.file_name.as_str()).collect();
+            for clip in clips {
+                *clip_usage.entry(clip).or_default() += 1;
+            }
+        }
+
+        let mut result = BTreeMap::new();
+        for (number, mpls) in self.playlists.iter() {
+            let mut features = PlaylistFeatures::from_mpls(mpls);
+            if longest.0 > 0 {
+                features.longest_ratio = Some(features.duration.0 as f64 / longest.0 as f64);
+            }
+            features.is_play_all = Some(play_alls.contains(number));
+            let clips: BTreeSet<&str> = mpls.clips().iter().map(|c| c.file_name.as_str()).collect();
+            if !clips.is_empty() {
+                let shared = clips.iter().filter(|c| clip_usage[*c] > 1).count();
+                features.shared_clip_ratio = Some(shared as f64 / clips.len() as f64);
+            }
+            result.insert(*number, thresholds.classify(&features));
+        }
+        result
+    }
+}
@@ -0,0 +1,207 @@
+//! Clip reuse across playlists.
+//!
+//! Discs with several cuts of a movie, alternate endings or seamless
+//! branching let many playlists share the same clips. A [`ClipGraph`] links
+//! every playlist to the clips its play items play, so that these structures
+//! can be queried, or exported to [Graphviz] with [`ClipGraph::to_dot`].
+//!
+//! [`ClipGraph`]: struct.ClipGraph.html
+//! [`ClipGraph::to_dot`]: struct.ClipGraph.html#method.to_dot
+//! [Graphviz]: https://graphviz.org/
+
+use crate::disc::Disc;
+use crate::types::{Mpls, TimeStamp};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// An edge of a [`ClipGraph`]: a play item of a playlist that plays (part of)
+/// a clip.
+///
+/// [`ClipGraph`]: struct.ClipGraph.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipUse {
+    /// The playlist number, e.g. 800 for `00800.mpls`.
+    pub playlist: u16,
+    /// The clip name, e.g. "00055".
+    pub clip: String,
+    /// The index of the play item within the playlist.
+    pub play_item: usize,
+    /// The angle the clip belongs to, 0 being the default angle.
+    pub angle: u8,
+    pub in_time: TimeStamp,
+    pub out_time: TimeStamp,
+}
+
+/// A graph of playlists and the clips they play.
+///
+/// Only the main path is taken into account, including all angles of
+/// multi-angle play items.
+#[derive(Debug, Clone, Default)]
+pub struct ClipGraph {
+    playlists: BTreeSet<u16>,
+    uses: Vec<ClipUse>,
+}
+
+impl ClipGraph {
+    /// Builds the graph of the given playlists.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::clip_graph::ClipGraph;
+    /// use mpls::Mpls;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let bytes = include_bytes!("../assets/multi-angle.mpls");
+    /// let mut playlists = BTreeMap::new();
+    /// playlists.insert(800, Mpls::from(&bytes[..]).unwrap());
+    ///
+    /// let graph = ClipGraph::new(&playlists);
+    /// assert_eq!(graph.playlists_using("00083"), &[800]);
+    /// ```
+    pub fn new(playlists: &BTreeMap<u16, Mpls>) -> ClipGraph {
+        let mut uses = Vec::new();
+        for (number, mpls) in playlists.iter() {
+            for (i, item) in mpls.play_list.play_items.iter().enumerate() {
+                let clips = std::iter::once(&item.clip).chain(item.angles.iter());
+                for (angle, clip) in clips.enumerate() {
+                    uses.push(ClipUse {
+                        playlist: *number,
+                        clip: clip.file_name.clone(),
+                        play_item: i,
+                        angle: angle as u8,
+                        in_time: item.in_time,
+                        out_time: item.out_time,
+                    });
+                }
+            }
+        }
+        ClipGraph {
+            playlists: playlists.keys().copied().collect(),
+            uses,
+        }
+    }
+
+    /// Gets all playlist numbers, in ascending order.
+    pub fn playlists(&self) -> Vec<u16> {
+        self.playlists.iter().copied().collect()
+    }
+
+    /// Gets the names of all clips that any playlist plays, sorted.
+    pub fn clips(&self) -> Vec<&str> {
+        let clips: BTreeSet<&str> = self.uses.iter().map(|u| u.clip.as_str()).collect();
+        clips.into_iter().collect()
+    }
+
+    /// Gets all edges, ordered by playlist, play item and angle.
+    pub fn uses(&self) -> &[ClipUse] {
+        &self.uses
+    }
+
+    /// Gets the edges of a clip, i.e. every place a playlist plays it.
+    pub fn uses_of_clip<'a>(&'a self, clip: &'a str) -> impl Iterator<Item = &'a ClipUse> + 'a {
+        self.uses.iter().filter(move |u| u.clip == clip)
+    }
+
+    /// Gets the edges of a playlist.
+    pub fn uses_of_playlist(&self, playlist: u16) -> impl Iterator<Item = &ClipUse> + '_ {
+        self.uses.iter().filter(move |u| u.playlist == playlist)
+    }
+
+    /// Gets the playlists that play the given clip, in ascending order.
+    pub fn playlists_using(&self, clip: &str) -> Vec<u16> {
+        let playlists: BTreeSet<u16> = self.uses_of_clip(clip).map(|u| u.playlist).collect();
+        playlists.into_iter().collect()
+    }
+
+    /// Gets the names of the clips the given playlist plays, sorted.
+    pub fn clips_of(&self, playlist: u16) -> Vec<&str> {
+        let clips: BTreeSet<&str> = self
+            .uses_of_playlist(playlist)
+            .map(|u| u.clip.as_str())
+            .collect();
+        clips.into_iter().collect()
+    }
+
+    /// Gets the names of the clips that both playlists play, sorted.
+    pub fn shared_clips(&self, a: u16, b: u16) -> Vec<&str> {
+        let b: BTreeSet<&str> = self.clips_of(b).into_iter().collect();
+        self.clips_of(a)
+            .into_iter()
+            .filter(|c| b.contains(c))
+            .collect()
+    }
+
+    /// Gets the clips that are played by more than one playlist, with the
+    /// playlists that play them.
+    pub fn shared(&self) -> BTreeMap<&str, Vec<u16>> {
+        self.clips()
+            .into_iter()
+            .map(|c| (c, self.playlists_using(c)))
+            .filter(|(_, p)| p.len() > 1)
+            .collect()
+    }
+
+    /// Renders the graph in the Graphviz DOT language.
+    ///
+    /// Playlists are drawn as boxes and clips as ellipses. Each edge is
+    /// labelled with the play item index, the angle (if it isn't the default
+    /// one) and the clip time range in seconds.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph clips {\n    rankdir=LR;\n");
+        for playlist in self.playlists.iter() {
+            writeln!(
+                dot,
+                "    \"{0:05}.mpls\" [shape=box, label=\"{0:05}.mpls\"];",
+                playlist
+            )
+            .unwrap();
+        }
+        for clip in self.clips() {
+            writeln!(
+                dot,
+                "    \"{0}.m2ts\" [shape=ellipse, label=\"{0}.m2ts\"];",
+                clip
+            )
+            .unwrap();
+        }
+        for u in self.uses.iter() {
+            let angle = match u.angle {
+                0 => String::new(),
+                n => format!(" angle {}", n),
+            };
+            writeln!(
+                dot,
+                "    \"{:05}.mpls\" -> \"{}.m2ts\" [label=\"#{}{} {:.3}-{:.3}\"];",
+                u.playlist,
+                u.clip,
+                u.play_item,
+                angle,
+                u.in_time.seconds(),
+                u.out_time.seconds()
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl Disc {
+    /// Builds the [`ClipGraph`] of all playlists on the disc.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use mpls::Disc;
+    ///
+    /// let disc = Disc::open("/mnt/bluray")?;
+    /// std::fs::write("clips.dot", disc.clip_graph().to_dot())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ClipGraph`]: ../clip_graph/struct.ClipGraph.html
+    pub fn clip_graph(&self) -> ClipGraph {
+        ClipGraph::new(&self.playlists)
+    }
+}
@@ -0,0 +1,12 @@
+//! A clip information file (CLPI) parser.
+//!
+//! The entry point is the [`Clpi`] struct, which can be obtained through its
+//! [`from`] method, just like [`Mpls`].
+//!
+//! [`Clpi`]: struct.Clpi.html
+//! [`from`]: struct.Clpi.html#method.from
+//! [`Mpls`]: ../types/struct.Mpls.html
+pub(crate) mod parser;
+mod types;
+
+pub use types::*;
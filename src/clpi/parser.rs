@@ -0,0 +1,336 @@
+use super::types::{
+    AtcDelta, AtcSequence, ClipInfo, ClipStream, Clpi, EpCoarse, EpFine, EpMap, EpMapStream,
+    Program, ProgramInfo, SequenceInfo, StcSequence,
+};
+use crate::parser::{
+    addr, clip_codec_id, clip_file_name, extension_data, str_len, stream_attrs, time_stamp, version,
+};
+use nom::{
+    bytes::complete::take,
+    combinator::map,
+    cond, count, do_parse,
+    error::ErrorKind,
+    length_data, map,
+    multi::{count, length_data, length_value},
+    number::complete::{be_u16, be_u32, be_u8},
+    take, Err, IResult,
+};
+
+// matches the ASCII/UTF-8 string "HDMV"
+fn header_tag(input: &[u8]) -> IResult<&[u8], &str> {
+    let (rest, s) = str_len(4, input)?;
+    if s == "HDMV" {
+        Ok((rest, s))
+    } else {
+        Err(Err::Error((input, ErrorKind::Tag)))
+    }
+}
+
+// returns the input starting at the given absolute address
+fn at(input: &[u8], addr: u32) -> IResult<&[u8], &[u8]> {
+    if (addr as usize) <= input.len() {
+        Ok((input, &input[addr as usize..]))
+    } else {
+        Err(Err::Error((input, ErrorKind::Eof)))
+    }
+}
+
+fn clip_info(input: &[u8]) -> IResult<&[u8], ClipInfo> {
+    fn atc_delta(input: &[u8]) -> IResult<&[u8], AtcDelta> {
+        do_parse!(
+            input,
+            atc_delta: be_u32
+                >> following_clip_file_name: clip_file_name
+                >> following_clip_codec_id: clip_codec_id
+                >> take!(1usize)
+                >> (AtcDelta {
+                    atc_delta,
+                    following_clip_file_name: following_clip_file_name.into(),
+                    following_clip_codec_id: following_clip_codec_id.into(),
+                })
+        )
+    }
+    fn atc_deltas(input: &[u8]) -> IResult<&[u8], Vec<AtcDelta>> {
+        let (input, _) = take(1usize)(input)?;
+        let (input, n) = be_u8(input)?;
+        count(atc_delta, n as usize)(input)
+    }
+    fn parser(input: &[u8]) -> IResult<&[u8], ClipInfo> {
+        do_parse!(
+            input,
+            take!(2usize)
+                >> clip_stream_type: be_u8
+                >> application_type: be_u8
+                >> is_atc_delta: map!(be_u32, |n| (n & 0x1) == 1)
+                >> ts_recording_rate: be_u32
+                >> number_of_source_packets: be_u32
+                >> take!(128usize)
+                // TSTypeInfoBlock
+                >> length_data!(be_u16)
+                >> atc_deltas: map!(cond!(is_atc_delta, atc_deltas), |d| d.unwrap_or_default())
+                >> (ClipInfo {
+                    clip_stream_type,
+                    application_type,
+                    ts_recording_rate,
+                    number_of_source_packets,
+                    atc_deltas,
+                })
+        )
+    }
+
+    // the clip info may be followed by font info we don't care about, so we
+    // only parse a prefix of it
+    let (input, data) = length_data(be_u32)(input)?;
+    let (_, info) = parser(data)?;
+    Ok((input, info))
+}
+
+fn sequence_info(input: &[u8]) -> IResult<&[u8], SequenceInfo> {
+    fn stc_sequence(input: &[u8]) -> IResult<&[u8], StcSequence> {
+        do_parse!(
+            input,
+            pcr_pid: be_u16
+                >> spn_stc_start: be_u32
+                >> presentation_start_time: time_stamp
+                >> presentation_end_time: time_stamp
+                >> (StcSequence {
+                    pcr_pid,
+                    spn_stc_start,
+                    presentation_start_time,
+                    presentation_end_time,
+                })
+        )
+    }
+    fn atc_sequence(input: &[u8]) -> IResult<&[u8], AtcSequence> {
+        do_parse!(
+            input,
+            spn_atc_start: be_u32
+                >> n_stc_sequences: be_u8
+                >> offset_stc_id: be_u8
+                >> stc_sequences: count!(stc_sequence, n_stc_sequences as usize)
+                >> (AtcSequence {
+                    spn_atc_start,
+                    offset_stc_id,
+                    stc_sequences,
+                })
+        )
+    }
+    fn parser(input: &[u8]) -> IResult<&[u8], SequenceInfo> {
+        do_parse!(
+            input,
+            take!(1usize)
+                >> n_atc_sequences: be_u8
+                >> atc_sequences: count!(atc_sequence, n_atc_sequences as usize)
+                >> (SequenceInfo { atc_sequences })
+        )
+    }
+
+    length_value(be_u32, parser)(input)
+}
+
+fn program_info(input: &[u8]) -> IResult<&[u8], ProgramInfo> {
+    fn clip_stream(input: &[u8]) -> IResult<&[u8], ClipStream> {
+        do_parse!(
+            input,
+            pid: be_u16 >> attrs: stream_attrs >> (ClipStream { pid, attrs })
+        )
+    }
+    fn program(input: &[u8]) -> IResult<&[u8], Program> {
+        do_parse!(
+            input,
+            spn_program_sequence_start: be_u32
+                >> program_map_pid: be_u16
+                >> n_streams: be_u8
+                >> num_groups: be_u8
+                >> streams: count!(clip_stream, n_streams as usize)
+                >> (Program {
+                    spn_program_sequence_start,
+                    program_map_pid,
+                    num_groups,
+                    streams,
+                })
+        )
+    }
+    fn parser(input: &[u8]) -> IResult<&[u8], ProgramInfo> {
+        do_parse!(
+            input,
+            take!(1usize)
+                >> n_programs: be_u8
+                >> programs: count!(program, n_programs as usize)
+                >> (ProgramInfo { programs })
+        )
+    }
+
+    length_value(be_u32, parser)(input)
+}
+
+struct EpMapStreamHeader {
+    pid: u16,
+    ep_stream_type: u8,
+    n_coarse: u16,
+    n_fine: u32,
+    addr: u32,
+}
+
+fn ep_map_stream_header(input: &[u8]) -> IResult<&[u8], EpMapStreamHeader> {
+    // 10 bits reserved, 4 bits EP_stream_type, 16 bits number of coarse
+    // entries, 18 bits number of fine entries
+    let (input, pid) = be_u16(input)?;
+    let (input, hi) = be_u16(input)?;
+    let (input, lo) = be_u32(input)?;
+    let (input, addr) = be_u32(input)?;
+    let bits = ((hi as u64) << 32) | lo as u64;
+    Ok((
+        input,
+        EpMapStreamHeader {
+            pid,
+            ep_stream_type: ((bits >> 34) & 0x0F) as u8,
+            n_coarse: ((bits >> 18) & 0xFFFF) as u16,
+            n_fine: (bits & 0x3FFFF) as u32,
+            addr,
+        },
+    ))
+}
+
+fn ep_coarse(input: &[u8]) -> IResult<&[u8], EpCoarse> {
+    let (input, n) = be_u32(input)?;
+    let (input, spn_ep) = be_u32(input)?;
+    Ok((
+        input,
+        EpCoarse {
+            ref_to_ep_fine_id: n >> 14,
+            pts_ep: (n & 0x3FFF) as u16,
+            spn_ep,
+        },
+    ))
+}
+
+fn ep_fine(input: &[u8]) -> IResult<&[u8], EpFine> {
+    map(be_u32, |n| EpFine {
+        is_angle_change_point: (n >> 31) == 1,
+        i_end_position_offset: ((n >> 28) & 0x7) as u8,
+        pts_ep: ((n >> 17) & 0x7FF) as u16,
+        spn_ep: n & 0x1FFFF,
+    })(input)
+}
+
+// `ep_map` is the input starting at the EP map, which all addresses within
+// the EP map are relative to
+fn ep_map_stream<'a>(
+    ep_map: &'a [u8],
+    header: &EpMapStreamHeader,
+) -> IResult<&'a [u8], EpMapStream> {
+    let (_, stream) = at(ep_map, header.addr)?;
+    let (coarse_input, fine_addr) = be_u32(stream)?;
+    let (_, coarse) = count(ep_coarse, header.n_coarse as usize)(coarse_input)?;
+    let (_, fine_input) = at(stream, fine_addr)?;
+    let (_, fine) = count(ep_fine, header.n_fine as usize)(fine_input)?;
+    Ok((
+        ep_map,
+        EpMapStream {
+            pid: header.pid,
+            ep_stream_type: header.ep_stream_type,
+            coarse,
+            fine,
+        },
+    ))
+}
+
+fn cpi(input: &[u8]) -> IResult<&[u8], Option<EpMap>> {
+    fn parser(input: &[u8]) -> IResult<&[u8], Option<EpMap>> {
+        let (ep_map, cpi_type) = map(be_u16, |n| (n & 0x0F) as u8)(input)?;
+        let (rest, _) = take(1usize)(ep_map)?;
+        let (rest, n_streams) = be_u8(rest)?;
+        let (_, headers) = count(ep_map_stream_header, n_streams as usize)(rest)?;
+        let mut streams = Vec::with_capacity(headers.len());
+        for header in headers.iter() {
+            let (_, stream) = ep_map_stream(ep_map, header)?;
+            streams.push(stream);
+        }
+        Ok((&[], Some(EpMap { cpi_type, streams })))
+    }
+
+    let (input, len) = be_u32(input)?;
+    if len == 0 {
+        Ok((input, None))
+    } else {
+        let (input, data) = take(len as usize)(input)?;
+        let (_, cpi) = parser(data)?;
+        Ok((input, cpi))
+    }
+}
+
+pub fn parse_clpi(input: &[u8]) -> IResult<&[u8], Clpi> {
+    let (_, (version, addrs)) = do_parse!(
+        input,
+        header_tag >> version: version >> addrs: count!(addr, 5) >> ((version, addrs))
+    )?;
+    let (sequence_info_addr, program_info_addr, cpi_addr, ext_addr) =
+        (addrs[0], addrs[1], addrs[2], addrs[4]);
+
+    // the clip info always directly follows the 40-byte header
+    let (_, clip_info) = clip_info(at(input, 40)?.1)?;
+    let (_, sequence_info) = sequence_info(at(input, sequence_info_addr)?.1)?;
+    let (_, program_info) = program_info(at(input, program_info_addr)?.1)?;
+    let (_, cpi) = cpi(at(input, cpi_addr)?.1)?;
+    let ext = if ext_addr != 0 {
+        extension_data(at(input, ext_addr)?.1)?.1
+    } else {
+        Vec::new()
+    };
+
+    Ok((
+        &[],
+        Clpi {
+            version: version.into(),
+            clip_info,
+            sequence_info,
+            program_info,
+            cpi,
+            ext,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn header_tag() {
+        let data = [0x48, 0x44, 0x4d, 0x56, 0x30];
+        let sl = &data[..];
+        assert_eq!(super::header_tag(sl), Ok((&sl[4..], "HDMV")));
+    }
+
+    #[test]
+    fn ep_map_stream_header() {
+        // PID 0x1011, stream type 1, 3 coarse and 70000 fine entries
+        let data = [
+            0x10, 0x11, 0x00, 0x04, 0x00, 0x0D, 0x11, 0x70, 0x00, 0x00, 0x00, 0x1A,
+        ];
+        let (_, header) = super::ep_map_stream_header(&data[..]).unwrap();
+        assert_eq!(header.pid, 0x1011);
+        assert_eq!(header.ep_stream_type, 1);
+        assert_eq!(header.n_coarse, 3);
+        assert_eq!(header.n_fine, 70_000);
+        assert_eq!(header.addr, 26);
+    }
+
+    #[test]
+    fn ep_coarse() {
+        let data = [0x00, 0x01, 0x40, 0x05, 0x00, 0x02, 0x00, 0x00];
+        let (_, coarse) = super::ep_coarse(&data[..]).unwrap();
+        assert_eq!(coarse.ref_to_ep_fine_id, 5);
+        assert_eq!(coarse.pts_ep, 5);
+        assert_eq!(coarse.spn_ep, 0x20000);
+    }
+
+    #[test]
+    fn ep_fine() {
+        let data = [0xB0, 0x0B, 0x00, 0x2A];
+        let (_, fine) = super::ep_fine(&data[..]).unwrap();
+        assert!(fine.is_angle_change_point);
+        assert_eq!(fine.i_end_position_offset, 3);
+        assert_eq!(fine.pts_ep, 5);
+        assert_eq!(fine.spn_ep, 0x1002A);
+    }
+}
@@ -0,0 +1,301 @@
+use super::parser::parse_clpi;
+use crate::types::{
+    ExtensionDataEntry, PlayItem, Ref, StreamAttributes, StreamEntryRef, StreamRef, TimeStamp,
+};
+use crate::MplsError;
+use std::io::Read;
+use std::ops::Range;
+
+/// The size of a source packet in bytes: a 188-byte transport stream packet
+/// preceded by a 4-byte arrival time stamp.
+pub const SOURCE_PACKET_SIZE: u64 = 192;
+
+/// The clip information file.
+///
+/// Every clip (e.g. `STREAM/00055.m2ts`) has a clip information file of the
+/// same name (e.g. `CLIPINF/00055.clpi`) that describes the clip's streams,
+/// its timeline and the entry points that allow seeking within it.
+#[derive(Debug, Clone)]
+pub struct Clpi {
+    /// The file format version, e.g. "0200" or "0300".
+    pub version: String,
+    pub clip_info: ClipInfo,
+    pub sequence_info: SequenceInfo,
+    pub program_info: ProgramInfo,
+    pub cpi: Option<EpMap>,
+    pub ext: Vec<ExtensionDataEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClipInfo {
+    pub clip_stream_type: u8,
+    pub application_type: u8,
+    /// The maximum bitrate of the transport stream, in bytes per second.
+    pub ts_recording_rate: u32,
+    /// The number of 192-byte source packets in the transport stream.
+    pub number_of_source_packets: u32,
+    pub atc_deltas: Vec<AtcDelta>,
+}
+
+/// The Arrival Time Clock offset to a clip that directly follows this one.
+#[derive(Debug, Clone)]
+pub struct AtcDelta {
+    pub atc_delta: u32,
+    pub following_clip_file_name: String,
+    pub following_clip_codec_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SequenceInfo {
+    pub atc_sequences: Vec<AtcSequence>,
+}
+
+/// A part of the transport stream with a continuous Arrival Time Clock.
+#[derive(Debug, Clone)]
+pub struct AtcSequence {
+    /// The source packet number this sequence starts at.
+    pub spn_atc_start: u32,
+    /// The id of the first STC sequence in this ATC sequence.
+    pub offset_stc_id: u8,
+    pub stc_sequences: Vec<StcSequence>,
+}
+
+/// A part of the transport stream with a continuous System Time Clock.
+///
+/// Playlist time stamps are relative to one of a clip's STC sequences (see
+/// [`Clip::stc_id`]).
+///
+/// [`Clip::stc_id`]: ../types/struct.Clip.html#structfield.stc_id
+#[derive(Debug, Clone)]
+pub struct StcSequence {
+    pub pcr_pid: u16,
+    /// The source packet number this sequence starts at.
+    pub spn_stc_start: u32,
+    pub presentation_start_time: TimeStamp,
+    pub presentation_end_time: TimeStamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgramInfo {
+    pub programs: Vec<Program>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Program {
+    /// The source packet number this program sequence starts at.
+    pub spn_program_sequence_start: u32,
+    pub program_map_pid: u16,
+    pub num_groups: u8,
+    pub streams: Vec<ClipStream>,
+}
+
+/// An elementary stream within a clip, identified by its transport stream
+/// packet id (PID).
+#[derive(Debug, Clone)]
+pub struct ClipStream {
+    pub pid: u16,
+    pub attrs: StreamAttributes,
+}
+
+/// The entry point map, which maps presentation time stamps to source packet
+/// numbers within a clip.
+#[derive(Debug, Clone)]
+pub struct EpMap {
+    pub cpi_type: u8,
+    pub streams: Vec<EpMapStream>,
+}
+
+/// The entry points of a single elementary stream (usually the video stream).
+///
+/// Entry points are split into a coarse and a fine table to save space: the
+/// coarse entries hold the high bits of the time stamps and source packet
+/// numbers, and each of them refers to the first of a run of fine entries
+/// holding the low bits.
+#[derive(Debug, Clone)]
+pub struct EpMapStream {
+    pub pid: u16,
+    pub ep_stream_type: u8,
+    pub coarse: Vec<EpCoarse>,
+    pub fine: Vec<EpFine>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct EpCoarse {
+    pub ref_to_ep_fine_id: u32,
+    /// Bits 32 to 19 of the 90 kHz PTS.
+    pub pts_ep: u16,
+    pub spn_ep: u32,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct EpFine {
+    pub is_angle_change_point: bool,
+    pub i_end_position_offset: u8,
+    /// Bits 19 to 9 of the 90 kHz PTS.
+    pub pts_ep: u16,
+    /// The lower 17 bits of the source packet number.
+    pub spn_ep: u32,
+}
+
+/// A decoded entry point, i.e. the position of an I-frame within a clip.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    /// The presentation time stamp of the I-frame, on the clip's timeline.
+    pub pts: TimeStamp,
+    /// The number of the source packet the I-frame starts in.
+    pub spn: u32,
+    pub is_angle_change_point: bool,
+}
+
+impl EntryPoint {
+    /// Returns the byte offset of this entry point within the clip's `.m2ts`
+    /// file.
+    pub fn byte_offset(&self) -> u64 {
+        self.spn as u64 * SOURCE_PACKET_SIZE
+    }
+}
+
+impl EpMapStream {
+    /// Combines the coarse and fine entries into a list of entry points, in
+    /// the order they are stored in (i.e. ascending source packet numbers).
+    pub fn entry_points(&self) -> Vec<EntryPoint> {
+        let mut points = Vec::with_capacity(self.fine.len());
+        for (i, coarse) in self.coarse.iter().enumerate() {
+            let start = coarse.ref_to_ep_fine_id as usize;
+            let end = self
+                .coarse
+                .get(i + 1)
+                .map_or(self.fine.len(), |c| c.ref_to_ep_fine_id as usize);
+            for fine in self.fine.get(start..end).unwrap_or(&[]) {
+                // the coarse and fine PTS overlap by one bit, and both are in
+                // units of the 90 kHz clock, so we drop one bit to get 45 kHz
+                let pts = ((coarse.pts_ep as u32 & !1) << 18) + ((fine.pts_ep as u32) << 8);
+                points.push(EntryPoint {
+                    pts: TimeStamp(pts),
+                    spn: (coarse.spn_ep & !0x1FFFF) + fine.spn_ep,
+                    is_angle_change_point: fine.is_angle_change_point,
+                });
+            }
+        }
+        points
+    }
+}
+
+impl Clpi {
+    /// Attempts to parse a clip information file from the given reader.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::fs::File;
+    /// use mpls::Clpi;
+    ///
+    /// let file = File::open("00055.clpi")?;
+    /// let clpi = Clpi::from(&file).expect("failed to parse CLPI file.");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from<R: Read>(mut reader: R) -> Result<Clpi, MplsError> {
+        let bytes = {
+            let mut buffer = Vec::new();
+            reader.read_to_end(&mut buffer)?;
+            buffer
+        };
+
+        parse_clpi(&bytes)
+            .map_err(|_| MplsError::ClpiParseError)
+            .map(|(_, c)| c)
+    }
+
+    /// Gets all of the clip's STC sequences, across all ATC sequences.
+    pub fn stc_sequences(&self) -> Vec<&StcSequence> {
+        self.sequence_info
+            .atc_sequences
+            .iter()
+            .flat_map(|a| a.stc_sequences.iter())
+            .collect()
+    }
+
+    /// Gets the entry points of the elementary stream with the given PID.
+    pub fn ep_map_stream(&self, pid: u16) -> Option<&EpMapStream> {
+        self.cpi.as_ref()?.streams.iter().find(|s| s.pid == pid)
+    }
+
+    /// Finds the nearest I-frame at or before the given time stamp of a play
+    /// item, so that the clip's stream can be cut at that point without
+    /// decoding it.
+    ///
+    /// `time` is relative to the clip's timeline, just like the play item's
+    /// `in_time` and `out_time`, and this clip information file has to be the
+    /// one of the play item's clip. The entry points are looked up for the
+    /// play item's primary video stream and restricted to the STC sequence the
+    /// play item refers to. Returns `None` if there is no entry point at or
+    /// before `time`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), mpls::MplsError> {
+    /// use std::fs::File;
+    /// use mpls::{Clpi, Mpls};
+    ///
+    /// let mpls = Mpls::from(File::open("00800.mpls")?)?;
+    /// let play_item = &mpls.play_list.play_items[0];
+    /// let clpi = Clpi::from(File::open(format!("{}.clpi", play_item.clip.file_name))?)?;
+    /// if let Some(entry) = clpi.seek(play_item, play_item.in_time) {
+    ///     println!("start cutting at byte {}", entry.byte_offset());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn seek(&self, play_item: &PlayItem, time: TimeStamp) -> Option<EntryPoint> {
+        let video_pid = play_item
+            .stream_number_table
+            .primary_video_streams
+            .iter()
+            .find_map(|s| match s.entry.refs {
+                StreamEntryRef::PlayItem(Ref::Stream(StreamRef(pid))) => Some(pid),
+                _ => None,
+            });
+        let stream = match video_pid {
+            Some(pid) => self.ep_map_stream(pid)?,
+            None => self.cpi.as_ref()?.streams.first()?,
+        };
+        let spns = self
+            .stc_spn_range(play_item.clip.stc_id)
+            .unwrap_or(0..u32::MAX);
+
+        stream
+            .entry_points()
+            .into_iter()
+            .filter(|e| spns.contains(&e.spn))
+            .take_while(|e| e.pts <= time)
+            .last()
+    }
+
+    // the source packets covered by the STC sequence with the given id
+    fn stc_spn_range(&self, stc_id: u8) -> Option<Range<u32>> {
+        let starts: Vec<(u32, u32)> = self
+            .sequence_info
+            .atc_sequences
+            .iter()
+            .flat_map(|a| {
+                a.stc_sequences
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, s)| (a.offset_stc_id as u32 + i as u32, s.spn_stc_start))
+            })
+            .collect();
+        let i = starts.iter().position(|(id, _)| *id == stc_id as u32)?;
+        let end = starts.get(i + 1).map_or(u32::MAX, |(_, spn)| *spn);
+        Some(starts[i].1..end)
+    }
+
+    /// Gets all of the clip's elementary streams, across all programs.
+    pub fn streams(&self) -> Vec<&ClipStream> {
+        self.program_info
+            .programs
+            .iter()
+            .flat_map(|p| p.streams.iter())
+            .collect()
+    }
+}
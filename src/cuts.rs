@@ -0,0 +1,227 @@
+//! Comparison of different cuts of a movie.
+//!
+//! Discs with several cuts (e.g. theatrical and extended) usually share most
+//! clips between the cuts' playlists and branch in a few places.
+//! [`Mpls::compare_cut`] aligns the play items of two playlists and reports
+//! which parts both cuts share and which are specific to either of them.
+//!
+//! [`Mpls::compare_cut`]: ../types/struct.Mpls.html#method.compare_cut
+
+use crate::chapters::format_millis;
+use crate::main_feature::play_item_key;
+use crate::types::{Mpls, TimeStamp};
+use std::fmt::Display;
+use std::ops::Range;
+
+/// Whether a [`CutSegment`] is part of one or both cuts.
+///
+/// [`CutSegment`]: struct.CutSegment.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SegmentKind {
+    /// Both cuts play the segment.
+    Shared,
+    /// Only the first cut plays the segment.
+    Removed,
+    /// Only the second cut plays the segment.
+    Added,
+}
+
+/// Where a [`CutSegment`] is within one of the cuts.
+///
+/// For segments that aren't part of the cut, the ranges are empty and point
+/// at the place the segment would be.
+///
+/// [`CutSegment`]: struct.CutSegment.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentPosition {
+    /// The segment's play items.
+    pub play_items: Range<usize>,
+    /// The segment's time range, relative to the start of the playlist.
+    pub range: Range<TimeStamp>,
+}
+
+/// A run of consecutive play items that is shared by both cuts or specific to
+/// one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CutSegment {
+    pub kind: SegmentKind,
+    pub first: SegmentPosition,
+    pub second: SegmentPosition,
+    pub duration: TimeStamp,
+}
+
+/// The result of [`Mpls::compare_cut`].
+///
+/// [`Mpls::compare_cut`]: ../types/struct.Mpls.html#method.compare_cut
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CutComparison {
+    /// All segments of both cuts, in playback order.
+    pub segments: Vec<CutSegment>,
+    /// The running time both cuts share.
+    pub shared: TimeStamp,
+    /// The running time only the second cut has.
+    pub added: TimeStamp,
+    /// The running time only the first cut has.
+    pub removed: TimeStamp,
+}
+
+impl CutComparison {
+    /// Returns whether both cuts play the same play items.
+    pub fn is_identical(&self) -> bool {
+        self.segments.iter().all(|s| s.kind == SegmentKind::Shared)
+    }
+
+    /// Gets the segments that are specific to one of the cuts.
+    pub fn differences(&self) -> impl Iterator<Item = &CutSegment> {
+        self.segments
+            .iter()
+            .filter(|s| s.kind != SegmentKind::Shared)
+    }
+}
+
+impl Display for CutComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let range = |r: &Range<TimeStamp>| {
+            format!(
+                "{}-{}",
+                format_millis(r.start.millis()),
+                format_millis(r.end.millis())
+            )
+        };
+        for s in self.segments.iter() {
+            let (kind, position) = match s.kind {
+                SegmentKind::Shared => ("shared", &s.first),
+                SegmentKind::Removed => ("removed", &s.first),
+                SegmentKind::Added => ("added", &s.second),
+            };
+            writeln!(
+                f,
+                "{:<8} {} ({}, play items {}..{})",
+                kind,
+                range(&position.range),
+                format_millis(s.duration.millis()),
+                position.play_items.start,
+                position.play_items.end
+            )?;
+        }
+        writeln!(
+            f,
+            "shared {}, added {}, removed {}",
+            format_millis(self.shared.millis()),
+            format_millis(self.added.millis()),
+            format_millis(self.removed.millis())
+        )
+    }
+}
+
+// the start time of each play item, plus the playlist's end
+fn start_times(mpls: &Mpls) -> Vec<TimeStamp> {
+    let mut times = Vec::with_capacity(mpls.play_list.play_items.len() + 1);
+    let mut time = 0u32;
+    times.push(TimeStamp(0));
+    for item in mpls.play_list.play_items.iter() {
+        time = time.saturating_add(item.duration().0);
+        times.push(TimeStamp(time));
+    }
+    times
+}
+
+impl Mpls {
+    /// Compares this playlist with another cut of the same movie.
+    ///
+    /// Play items are aligned by their longest common subsequence, comparing
+    /// their clips and in and out times. Consecutive play items of the same
+    /// kind are merged into a single segment. A play item that plays a longer
+    /// or shorter part of a clip than its counterpart counts as removed and
+    /// added.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::Mpls;
+    ///
+    /// let bytes = include_bytes!("../assets/simple.mpls");
+    /// let theatrical = Mpls::from(&bytes[..]).unwrap();
+    /// let mut extended = theatrical.clone();
+    /// let scene = extended.play_list.play_items[0].clone();
+    /// extended.play_list.play_items.insert(1, scene.clone());
+    ///
+    /// let comparison = theatrical.compare_cut(&extended);
+    /// assert_eq!(comparison.segments.len(), 3);
+    /// assert_eq!(comparison.added, scene.duration());
+    /// assert_eq!(comparison.removed.0, 0);
+    /// ```
+    pub fn compare_cut(&self, other: &Mpls) -> CutComparison {
+        let (a, b) = (play_item_key(self), play_item_key(other));
+        let (a_times, b_times) = (start_times(self), start_times(other));
+
+        // lcs[i][j]: the length of the longest common subsequence of a[i..]
+        // and b[j..]
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut segments: Vec<CutSegment> = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            let (kind, next_i, next_j) = if i < a.len() && j < b.len() && a[i] == b[j] {
+                (SegmentKind::Shared, i + 1, j + 1)
+            } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                (SegmentKind::Removed, i + 1, j)
+            } else {
+                (SegmentKind::Added, i, j + 1)
+            };
+
+            match segments.last_mut() {
+                Some(last) if last.kind == kind => {
+                    last.first.play_items.end = next_i;
+                    last.second.play_items.end = next_j;
+                }
+                _ => segments.push(CutSegment {
+                    kind,
+                    first: SegmentPosition {
+                        play_items: i..next_i,
+                        range: TimeStamp(0)..TimeStamp(0),
+                    },
+                    second: SegmentPosition {
+                        play_items: j..next_j,
+                        range: TimeStamp(0)..TimeStamp(0),
+                    },
+                    duration: TimeStamp(0),
+                }),
+            }
+            i = next_i;
+            j = next_j;
+        }
+
+        let (mut shared, mut added, mut removed) = (0, 0, 0);
+        for s in segments.iter_mut() {
+            let first = &s.first.play_items;
+            let second = &s.second.play_items;
+            s.first.range = a_times[first.start]..a_times[first.end];
+            s.second.range = b_times[second.start]..b_times[second.end];
+            s.duration = match s.kind {
+                SegmentKind::Added => TimeStamp(s.second.range.end.0 - s.second.range.start.0),
+                _ => TimeStamp(s.first.range.end.0 - s.first.range.start.0),
+            };
+            match s.kind {
+                SegmentKind::Shared => shared += s.duration.0,
+                SegmentKind::Added => added += s.duration.0,
+                SegmentKind::Removed => removed += s.duration.0,
+            }
+        }
+
+        CutComparison {
+            segments,
+            shared: TimeStamp(shared),
+            added: TimeStamp(added),
+            removed: TimeStamp(removed),
+        }
+    }
+}
@@ -0,0 +1,658 @@
+//! Structural differences between two playlists.
+//!
+//! [`Mpls::diff`] compares two playlists field by field, e.g. a playlist from
+//! a disc reissue with the one from the original release, and lists every
+//! change as a [`Change`]. The resulting [`MplsDiff`] can be rendered in a
+//! format similar to a unified diff.
+//!
+//! [`Mpls::diff`]: ../types/struct.Mpls.html#method.diff
+//! [`Change`]: enum.Change.html
+//! [`MplsDiff`]: struct.MplsDiff.html
+
+use crate::types::{Mpls, PlayItem, PlayListMark, Stream, StreamNumberTable, SubPath};
+use std::fmt::Display;
+
+/// A field whose value differs between the two playlists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// The stream lists of a play item's stream number table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StreamTable {
+    PrimaryVideo,
+    PrimaryAudio,
+    PrimaryPgs,
+    PrimaryIgs,
+    SecondaryAudio,
+    SecondaryVideo,
+    SecondaryPgs,
+    DolbyVision,
+}
+
+impl Display for StreamTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            StreamTable::PrimaryVideo => "primary video",
+            StreamTable::PrimaryAudio => "primary audio",
+            StreamTable::PrimaryPgs => "primary PGS",
+            StreamTable::PrimaryIgs => "primary IGS",
+            StreamTable::SecondaryAudio => "secondary audio",
+            StreamTable::SecondaryVideo => "secondary video",
+            StreamTable::SecondaryPgs => "secondary PGS",
+            StreamTable::DolbyVision => "Dolby Vision",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single difference between two playlists.
+///
+/// Play item indices refer to the old playlist for removed play items and to
+/// the new one otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A field of the playlist header or its `AppInfoPlayList` changed.
+    Header(FieldChange),
+    PlayItemRemoved {
+        index: usize,
+        clip: String,
+    },
+    PlayItemAdded {
+        index: usize,
+        clip: String,
+    },
+    /// A play item that plays the same clip in both playlists changed.
+    PlayItemModified {
+        old_index: usize,
+        index: usize,
+        clip: String,
+        fields: Vec<FieldChange>,
+    },
+    StreamRemoved {
+        play_item: usize,
+        table: StreamTable,
+        index: usize,
+        stream: String,
+    },
+    StreamAdded {
+        play_item: usize,
+        table: StreamTable,
+        index: usize,
+        stream: String,
+    },
+    StreamModified {
+        play_item: usize,
+        table: StreamTable,
+        index: usize,
+        old: String,
+        new: String,
+    },
+    /// A sub path was added (`old` is `None`), removed (`new` is `None`) or
+    /// modified.
+    SubPath {
+        index: usize,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    MarkRemoved {
+        index: usize,
+        mark: String,
+    },
+    MarkAdded {
+        index: usize,
+        mark: String,
+    },
+    /// A mark's position or type changed.
+    MarkMoved {
+        index: usize,
+        old: String,
+        new: String,
+    },
+    /// An extension data entry was added (`old_len` is `None`), removed
+    /// (`new_len` is `None`) or its data changed.
+    Extension {
+        data_type: u16,
+        data_version: u16,
+        old_len: Option<usize>,
+        new_len: Option<usize>,
+    },
+}
+
+impl Change {
+    // the hunk header and the removed and added lines
+    fn hunk(&self) -> (String, Vec<String>, Vec<String>) {
+        fn opt(s: &Option<String>) -> Vec<String> {
+            s.iter().cloned().collect()
+        }
+        match self {
+            Change::Header(c) => (
+                "header".into(),
+                vec![format!("{}: {}", c.field, c.old)],
+                vec![format!("{}: {}", c.field, c.new)],
+            ),
+            Change::PlayItemRemoved { index, clip } => (
+                format!("play item {} removed", index),
+                vec![clip.clone()],
+                vec![],
+            ),
+            Change::PlayItemAdded { index, clip } => (
+                format!("play item {} added", index),
+                vec![],
+                vec![clip.clone()],
+            ),
+            Change::PlayItemModified {
+                old_index,
+                index,
+                clip,
+                fields,
+            } => (
+                format!("play item {} -> {} ({})", old_index, index, clip),
+                fields
+                    .iter()
+                    .map(|c| format!("{}: {}", c.field, c.old))
+                    .collect(),
+                fields
+                    .iter()
+                    .map(|c| format!("{}: {}", c.field, c.new))
+                    .collect(),
+            ),
+            Change::StreamRemoved {
+                play_item,
+                table,
+                index,
+                stream,
+            } => (
+                format!("play item {}, {} stream {}", play_item, table, index),
+                vec![stream.clone()],
+                vec![],
+            ),
+            Change::StreamAdded {
+                play_item,
+                table,
+                index,
+                stream,
+            } => (
+                format!("play item {}, {} stream {}", play_item, table, index),
+                vec![],
+                vec![stream.clone()],
+            ),
+            Change::StreamModified {
+                play_item,
+                table,
+                index,
+                old,
+                new,
+            } => (
+                format!("play item {}, {} stream {}", play_item, table, index),
+                vec![old.clone()],
+                vec![new.clone()],
+            ),
+            Change::SubPath { index, old, new } => {
+                (format!("sub path {}", index), opt(old), opt(new))
+            }
+            Change::MarkRemoved { index, mark } => {
+                (format!("mark {}", index), vec![mark.clone()], vec![])
+            }
+            Change::MarkAdded { index, mark } => {
+                (format!("mark {}", index), vec![], vec![mark.clone()])
+            }
+            Change::MarkMoved { index, old, new } => (
+                format!("mark {}", index),
+                vec![old.clone()],
+                vec![new.clone()],
+            ),
+            Change::Extension {
+                data_type,
+                data_version,
+                old_len,
+                new_len,
+            } => {
+                let len = |l: &Option<usize>| l.iter().map(|l| format!("{} bytes", l)).collect();
+                (
+                    format!("extension data {}:{}", data_type, data_version),
+                    len(old_len),
+                    len(new_len),
+                )
+            }
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (header, old, new) = self.hunk();
+        writeln!(f, "@@ {} @@", header)?;
+        for line in old {
+            writeln!(f, "-{}", line)?;
+        }
+        for line in new {
+            writeln!(f, "+{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of [`Mpls::diff`].
+///
+/// [`Mpls::diff`]: ../types/struct.Mpls.html#method.diff
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MplsDiff {
+    /// All changes, ordered by the section of the playlist they're in.
+    pub changes: Vec<Change>,
+}
+
+impl MplsDiff {
+    /// Returns whether the playlists are structurally identical.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for MplsDiff {
+    /// Renders the changes like a unified diff, with one hunk per change.
+    /// Consecutive changes of the playlist header share a hunk.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "--- a")?;
+        writeln!(f, "+++ b")?;
+        let mut i = 0;
+        while i < self.changes.len() {
+            let (header, mut old, mut new) = self.changes[i].hunk();
+            i += 1;
+            if let Change::Header(_) = self.changes[i - 1] {
+                while let Some(Change::Header(c)) = self.changes.get(i) {
+                    old.push(format!("{}: {}", c.field, c.old));
+                    new.push(format!("{}: {}", c.field, c.new));
+                    i += 1;
+                }
+            }
+            writeln!(f, "@@ {} @@", header)?;
+            for line in old {
+                writeln!(f, "-{}", line)?;
+            }
+            for line in new {
+                writeln!(f, "+{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// pushes a field change if the values' debug representations differ
+fn field<T: std::fmt::Debug>(changes: &mut Vec<FieldChange>, field: &'static str, old: T, new: T) {
+    let (old, new) = (format!("{:?}", old), format!("{:?}", new));
+    if old != new {
+        changes.push(FieldChange { field, old, new });
+    }
+}
+
+fn play_item_fields(old: &PlayItem, new: &PlayItem) -> Vec<FieldChange> {
+    let mut fields = Vec::new();
+    field(
+        &mut fields,
+        "codec_id",
+        &old.clip.codec_id,
+        &new.clip.codec_id,
+    );
+    field(&mut fields, "stc_id", old.clip.stc_id, new.clip.stc_id);
+    field(
+        &mut fields,
+        "connection_condition",
+        old.connection_condition,
+        new.connection_condition,
+    );
+    field(&mut fields, "in_time", old.in_time.0, new.in_time.0);
+    field(&mut fields, "out_time", old.out_time.0, new.out_time.0);
+    field(
+        &mut fields,
+        "user_opt_mask",
+        old.user_opt_mask,
+        new.user_opt_mask,
+    );
+    field(
+        &mut fields,
+        "random_access_flag",
+        old.random_access_flag,
+        new.random_access_flag,
+    );
+    field(&mut fields, "still_mode", old.still_mode, new.still_mode);
+    field(&mut fields, "still_time", old.still_time, new.still_time);
+    let angles =
+        |p: &PlayItem| -> Vec<String> { p.angles.iter().map(|c| c.file_name.clone()).collect() };
+    field(&mut fields, "angles", angles(old), angles(new));
+    field(&mut fields, "angle_info", old.angle_info, new.angle_info);
+    fields
+}
+
+fn stream_tables(table: &StreamNumberTable) -> [(StreamTable, &[Stream]); 8] {
+    [
+        (StreamTable::PrimaryVideo, &table.primary_video_streams),
+        (StreamTable::PrimaryAudio, &table.primary_audio_streams),
+        (StreamTable::PrimaryPgs, &table.primary_pgs_streams),
+        (StreamTable::PrimaryIgs, &table.primary_igs_streams),
+        (StreamTable::SecondaryAudio, &table.secondary_audio_streams),
+        (StreamTable::SecondaryVideo, &table.secondary_video_streams),
+        (StreamTable::SecondaryPgs, &table.secondary_pgs_streams),
+        (StreamTable::DolbyVision, &table.dolby_vision_streams),
+    ]
+}
+
+fn describe_stream(stream: &Stream) -> String {
+    format!(
+        "{:?} (coding type 0x{:02x}, {:?})",
+        stream.attrs.stream_type, stream.attrs.coding_type, stream.entry.refs
+    )
+}
+
+// compares the stream tables stream by stream, aligning the streams of each
+// table so that an inserted or removed stream doesn't change all following ones
+fn stream_changes(old: &PlayItem, new: &PlayItem, play_item: usize, changes: &mut Vec<Change>) {
+    let old_tables = stream_tables(&old.stream_number_table);
+    let new_tables = stream_tables(&new.stream_number_table);
+    for ((table, old), (_, new)) in old_tables.iter().zip(new_tables.iter()) {
+        let table = *table;
+        let old: Vec<String> = old.iter().map(describe_stream).collect();
+        let new: Vec<String> = new.iter().map(describe_stream).collect();
+        for edit in edits(&old, &new, true) {
+            match edit {
+                Edit::Removed(index) => changes.push(Change::StreamRemoved {
+                    play_item,
+                    table,
+                    index,
+                    stream: old[index].clone(),
+                }),
+                Edit::Added(index) => changes.push(Change::StreamAdded {
+                    play_item,
+                    table,
+                    index,
+                    stream: new[index].clone(),
+                }),
+                Edit::Kept(a, index) => {
+                    if old[a] != new[index] {
+                        changes.push(Change::StreamModified {
+                            play_item,
+                            table,
+                            index,
+                            old: old[a].clone(),
+                            new: new[index].clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn describe_play_item(item: &PlayItem) -> String {
+    format!(
+        "{} {}-{}",
+        item.clip.file_name, item.in_time.0, item.out_time.0
+    )
+}
+
+fn describe_sub_path(sub_path: &SubPath) -> String {
+    let clips: Vec<String> = sub_path
+        .play_items
+        .iter()
+        .map(|p| format!("{} {}-{}", p.clip.file_name, p.in_time.0, p.out_time.0))
+        .collect();
+    format!(
+        "type {}{}: {}",
+        sub_path.sub_path_type,
+        if sub_path.is_repeat { ", repeat" } else { "" },
+        clips.join(", ")
+    )
+}
+
+fn describe_mark(mark: &PlayListMark) -> String {
+    format!(
+        "{:?} at play item {}, {}",
+        mark.mark_type, mark.play_item.0, mark.time_stamp.0
+    )
+}
+
+// the data of the extension data entries with the given type and version
+fn ext_data(mpls: &Mpls, key: (u16, u16)) -> Vec<&[u8]> {
+    mpls.ext
+        .iter()
+        .filter(|e| (e.data_type, e.data_version) == key)
+        .map(|e| e.data.as_slice())
+        .collect()
+}
+
+// aligns two lists by the longest common subsequence of their keys,
+// returning the pairs of matching indices in order
+fn align<K: PartialEq>(old: &[K], new: &[K]) -> Vec<(usize, usize)> {
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+// an element that is only in the old list, only in the new list, or at the
+// given indices of both
+enum Edit {
+    Removed(usize),
+    Added(usize),
+    Kept(usize, usize),
+}
+
+// the edits that turn `old` into `new`, aligned by their keys. With
+// `pair_gaps`, the unaligned elements between two aligned ones are paired up
+// in order and kept, so that a changed element isn't reported as removed and
+// added.
+fn edits<K: PartialEq>(old: &[K], new: &[K], pair_gaps: bool) -> Vec<Edit> {
+    let mut pairs = align(old, new);
+    pairs.push((old.len(), new.len()));
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (a, b) in pairs {
+        if pair_gaps {
+            while i < a && j < b {
+                edits.push(Edit::Kept(i, j));
+                i += 1;
+                j += 1;
+            }
+        }
+        edits.extend((i..a).map(Edit::Removed));
+        edits.extend((j..b).map(Edit::Added));
+        if a < old.len() {
+            edits.push(Edit::Kept(a, b));
+        }
+        i = a + 1;
+        j = b + 1;
+    }
+    edits
+}
+
+fn play_item_changes(old: &[PlayItem], new: &[PlayItem], changes: &mut Vec<Change>) {
+    // play items are aligned by their clip names
+    let clips = |items: &[PlayItem]| -> Vec<String> {
+        items.iter().map(|p| p.clip.file_name.clone()).collect()
+    };
+    for edit in edits(&clips(old), &clips(new), false) {
+        match edit {
+            Edit::Removed(index) => changes.push(Change::PlayItemRemoved {
+                index,
+                clip: describe_play_item(&old[index]),
+            }),
+            Edit::Added(index) => changes.push(Change::PlayItemAdded {
+                index,
+                clip: describe_play_item(&new[index]),
+            }),
+            Edit::Kept(a, b) => {
+                let fields = play_item_fields(&old[a], &new[b]);
+                if !fields.is_empty() {
+                    changes.push(Change::PlayItemModified {
+                        old_index: a,
+                        index: b,
+                        clip: new[b].clip.file_name.clone(),
+                        fields,
+                    });
+                }
+                stream_changes(&old[a], &new[b], b, changes);
+            }
+        }
+    }
+}
+
+impl Mpls {
+    /// Compares this playlist (the old one) with another one (the new one).
+    ///
+    /// Play items are matched by their clip names, so that inserted or removed
+    /// play items don't make all following ones show up as modified. Streams,
+    /// sub paths and marks are matched the same way by their contents, and
+    /// extension data entries by their type and version, and then by their
+    /// order.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::Mpls;
+    ///
+    /// let bytes = include_bytes!("../assets/simple.mpls");
+    /// let old = Mpls::from(&bytes[..]).unwrap();
+    /// let mut new = old.clone();
+    /// new.version = "0200".into();
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.changes.len(), 1);
+    /// assert_eq!(
+    ///     diff.to_string(),
+    ///     "--- a\n+++ b\n@@ header @@\n-version: \"0300\"\n+version: \"0200\"\n"
+    /// );
+    /// ```
+    pub fn diff(&self, other: &Mpls) -> MplsDiff {
+        let mut changes = Vec::new();
+
+        let mut header = Vec::new();
+        let (old, new) = (&self.app_info_play_list, &other.app_info_play_list);
+        field(&mut header, "version", &self.version, &other.version);
+        field(
+            &mut header,
+            "playback_type",
+            old.playback_type,
+            new.playback_type,
+        );
+        field(
+            &mut header,
+            "playback_count",
+            old.playback_count,
+            new.playback_count,
+        );
+        field(
+            &mut header,
+            "user_opt_mask",
+            old.user_opt_mask,
+            new.user_opt_mask,
+        );
+        field(&mut header, "flags", old.flags, new.flags);
+        changes.extend(header.into_iter().map(Change::Header));
+
+        play_item_changes(
+            &self.play_list.play_items,
+            &other.play_list.play_items,
+            &mut changes,
+        );
+
+        let old: Vec<String> = self
+            .play_list
+            .sub_paths
+            .iter()
+            .map(describe_sub_path)
+            .collect();
+        let new: Vec<String> = other
+            .play_list
+            .sub_paths
+            .iter()
+            .map(describe_sub_path)
+            .collect();
+        for edit in edits(&old, &new, true) {
+            let (index, old, new) = match edit {
+                Edit::Removed(a) => (a, Some(&old[a]), None),
+                Edit::Added(b) => (b, None, Some(&new[b])),
+                Edit::Kept(a, b) => (b, Some(&old[a]), Some(&new[b])),
+            };
+            if old != new {
+                changes.push(Change::SubPath {
+                    index,
+                    old: old.cloned(),
+                    new: new.cloned(),
+                });
+            }
+        }
+
+        let old: Vec<String> = self.marks.iter().map(describe_mark).collect();
+        let new: Vec<String> = other.marks.iter().map(describe_mark).collect();
+        for edit in edits(&old, &new, true) {
+            match edit {
+                Edit::Removed(index) => changes.push(Change::MarkRemoved {
+                    index,
+                    mark: old[index].clone(),
+                }),
+                Edit::Added(index) => changes.push(Change::MarkAdded {
+                    index,
+                    mark: new[index].clone(),
+                }),
+                Edit::Kept(a, index) => {
+                    if old[a] != new[index] {
+                        changes.push(Change::MarkMoved {
+                            index,
+                            old: old[a].clone(),
+                            new: new[index].clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // entries are matched by their type and version, and entries of the
+        // same type and version by their order
+        let mut keys: Vec<(u16, u16)> = self
+            .ext
+            .iter()
+            .chain(other.ext.iter())
+            .map(|e| (e.data_type, e.data_version))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        for key in keys {
+            let (old, new) = (ext_data(self, key), ext_data(other, key));
+            for i in 0..old.len().max(new.len()) {
+                let (a, b) = (old.get(i), new.get(i));
+                if a != b {
+                    changes.push(Change::Extension {
+                        data_type: key.0,
+                        data_version: key.1,
+                        old_len: a.map(|d| d.len()),
+                        new_len: b.map(|d| d.len()),
+                    });
+                }
+            }
+        }
+
+        MplsDiff { changes }
+    }
+}
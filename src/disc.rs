@@ -0,0 +1,582 @@
+//! Blu-ray disc directory structure.
+//!
+//! A Blu-ray disc (or a backup of one) keeps all of its navigation and stream
+//! files below a `BDMV` directory:
+//!
+//! ```text
+//! BDMV/
+//! ├── index.bdmv             the title table
+//! ├── MovieObject.bdmv       navigation programs of HDMV titles
+//! ├── PLAYLIST/00800.mpls    movie playlists
+//! ├── BDJO/00000.bdjo        BD-J objects
+//! ├── CLIPINF/00055.clpi     clip information
+//! └── STREAM/00055.m2ts      the actual audio/video streams
+//!     └── SSIF/00055.ssif    interleaved stereoscopic (3D) streams
+//! ```
+//!
+//! The [`Disc`] type parses the navigation files and all playlists of such a
+//! directory, finds the playlists its titles can play and resolves the clips
+//! they reference to their stream and clip information files. Discs can also
+//! be read straight from a disc image, see [`Disc::open_iso`].
+//!
+//! [`Disc`]: struct.Disc.html
+//! [`Disc::open_iso`]: struct.Disc.html#method.open_iso
+
+use crate::bdjo::Bdjo;
+use crate::clpi::Clpi;
+use crate::index::{Index, IndexObject};
+use crate::movie_objects::MovieObjects;
+use crate::types::{Clip, Mpls};
+use crate::udf::UdfImage;
+use crate::vm::{Vm, VmConfig};
+use crate::MplsError;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// A Blu-ray disc's `BDMV` directory and all of its playlists.
+///
+/// The disc's files are either read from a directory (see [`open`]) or from a
+/// disc image (see [`open_iso`]). In the latter case, all paths refer to the
+/// files within the image as if it was a directory, e.g.
+/// `movie.iso/BDMV/STREAM/00055.m2ts`.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> Result<(), mpls::MplsError> {
+/// use mpls::Disc;
+///
+/// let disc = Disc::open("/mnt/bluray")?;
+/// for (number, mpls) in disc.playlists.iter() {
+///     println!("{:05}.mpls: {:.0}s", number, mpls.duration().seconds());
+/// }
+/// for (path, err) in disc.errors.iter() {
+///     println!("{}: {}", path.display(), err);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`open`]: #method.open
+/// [`open_iso`]: #method.open_iso
+#[derive(Debug)]
+pub struct Disc {
+    /// The path to the `BDMV` directory.
+    pub root: PathBuf,
+    /// The disc's index table, if it could be read and parsed.
+    pub index: Option<Index>,
+    /// The disc's movie objects, if they could be read and parsed.
+    pub movie_objects: Option<MovieObjects>,
+    /// All successfully parsed BD-J objects, keyed by their file name (e.g.
+    /// "00000" for `00000.bdjo`).
+    pub bdj_objects: BTreeMap<String, Bdjo>,
+    /// All successfully parsed playlists, keyed by their playlist number
+    /// (e.g. 800 for `00800.mpls`).
+    pub playlists: BTreeMap<u16, Mpls>,
+    /// The navigation and playlist files that couldn't be read or parsed.
+    pub errors: Vec<(PathBuf, MplsError)>,
+    source: Source,
+}
+
+// where the files of a disc are read from
+#[derive(Debug)]
+enum Source {
+    Directory,
+    // a disc image at `path`, whose files are addressed by paths below it
+    Image {
+        path: PathBuf,
+        image: Mutex<UdfImage<File>>,
+    },
+}
+
+impl Source {
+    // the locked disc image and the path within it, if `path` is below the
+    // image's path
+    fn image(&self, path: &Path) -> Option<(MutexGuard<'_, UdfImage<File>>, String)> {
+        let (iso, image) = match self {
+            Source::Directory => return None,
+            Source::Image { path, image } => (path, image),
+        };
+        let relative = path.strip_prefix(iso).ok()?;
+        let components: Option<Vec<&str>> = relative
+            .components()
+            .map(|c| match c {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+        let image = image.lock().unwrap_or_else(|e| e.into_inner());
+        Some((image, components?.join("/")))
+    }
+
+    // returns an error for paths outside of the disc image
+    fn image_or_err(&self, path: &Path) -> io::Result<(MutexGuard<'_, UdfImage<File>>, String)> {
+        self.image(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not part of the disc image", path.display()),
+            )
+        })
+    }
+
+    // whether a path of a disc image is a directory, or `None` if it doesn't
+    // exist
+    fn image_is_dir(&self, path: &Path) -> Option<bool> {
+        let (mut image, p) = self.image(path)?;
+        image.file(&p).ok().map(|f| f.is_dir())
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        match self {
+            Source::Directory => path.is_file(),
+            Source::Image { .. } => self.image_is_dir(path) == Some(false),
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        match self {
+            Source::Directory => path.is_dir(),
+            Source::Image { .. } => self.image_is_dir(path) == Some(true),
+        }
+    }
+
+    // the files of a directory in a stable order, or none if it doesn't exist
+    fn files(&self, dir: &Path) -> Result<Vec<PathBuf>, MplsError> {
+        if !self.is_dir(dir) {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<PathBuf> = match self {
+            Source::Directory => fs::read_dir(dir)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect(),
+            Source::Image { .. } => {
+                let (mut image, p) = self.image_or_err(dir)?;
+                image
+                    .read_dir(&p)?
+                    .into_iter()
+                    .map(|e| dir.join(e.name))
+                    .collect()
+            }
+        };
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn size(&self, path: &Path) -> Result<u64, MplsError> {
+        match self {
+            Source::Directory => Ok(fs::metadata(path)?.len()),
+            Source::Image { .. } => {
+                let (mut image, p) = self.image_or_err(path)?;
+                Ok(image.file(&p)?.size())
+            }
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, MplsError> {
+        match self {
+            Source::Directory => Ok(fs::read(path)?),
+            Source::Image { .. } => {
+                let (mut image, p) = self.image_or_err(path)?;
+                image.read(&p)
+            }
+        }
+    }
+
+    fn read_range(&self, path: &Path, range: Range<u64>) -> Result<Vec<u8>, MplsError> {
+        match self {
+            Source::Directory => {
+                let mut file = File::open(path)?;
+                let start = range.start.min(file.metadata()?.len());
+                file.seek(SeekFrom::Start(start))?;
+                let mut buf = Vec::new();
+                file.take(range.end.saturating_sub(start))
+                    .read_to_end(&mut buf)?;
+                Ok(buf)
+            }
+            Source::Image { .. } => {
+                let (mut image, p) = self.image_or_err(path)?;
+                image.read_range(&p, range)
+            }
+        }
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), MplsError> {
+        match self {
+            Source::Directory => {
+                fs::copy(from, to)?;
+            }
+            Source::Image { .. } => {
+                let (mut image, p) = self.image_or_err(from)?;
+                image.copy_to(&p, &mut File::create(to)?)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The files on disc that belong to a [`Clip`].
+///
+/// Each path is only set if the file actually exists.
+///
+/// [`Clip`]: ../types/struct.Clip.html
+#[derive(Debug, Clone)]
+pub struct ClipFiles {
+    /// The transport stream, `STREAM/<name>.m2ts`.
+    pub stream: Option<PathBuf>,
+    /// The interleaved stereoscopic stream, `STREAM/SSIF/<name>.ssif`. Only
+    /// present on 3D discs.
+    pub ssif: Option<PathBuf>,
+    /// The clip information file, `CLIPINF/<name>.clpi`.
+    pub clip_info: Option<PathBuf>,
+}
+
+impl ClipFiles {
+    /// Returns whether the clip has a playable stream and clip information.
+    pub fn is_complete(&self) -> bool {
+        (self.stream.is_some() || self.ssif.is_some()) && self.clip_info.is_some()
+    }
+}
+
+// reads one of the files in the `BDMV` directory that have a copy in the
+// `BACKUP` directory, falling back to that copy if the main one is missing or
+// broken. If neither can be read, the error of the first one is recorded.
+fn read_with_backup<T, F>(
+    source: &Source,
+    root: &Path,
+    name: &str,
+    parse: F,
+    errors: &mut Vec<(PathBuf, MplsError)>,
+) -> Option<T>
+where
+    F: Fn(&[u8]) -> Result<T, MplsError>,
+{
+    let paths: Vec<PathBuf> = [root.to_path_buf(), root.join("BACKUP")]
+        .iter()
+        .flat_map(|dir| vec![dir.join(name), dir.join(name.to_uppercase())])
+        .filter(|p| source.is_file(p))
+        .collect();
+    let mut first_error = None;
+    for path in paths {
+        match source.read(&path).and_then(|b| parse(&b)) {
+            Ok(value) => return Some(value),
+            Err(e) => {
+                first_error.get_or_insert((path, e));
+            }
+        }
+    }
+    errors.extend(first_error);
+    None
+}
+
+// the name of a file with the given extension, without the extension
+fn file_stem<'a>(path: &'a Path, ext: &str) -> Option<&'a str> {
+    let e = path.extension().and_then(OsStr::to_str)?;
+    if !e.eq_ignore_ascii_case(ext) {
+        return None;
+    }
+    path.file_stem().and_then(OsStr::to_str)
+}
+
+fn playlist_number(path: &Path) -> Option<u16> {
+    file_stem(path, "mpls")?.parse().ok()
+}
+
+// an entry of the index table
+enum Entry {
+    FirstPlayback,
+    TopMenu,
+    Title(u16),
+}
+
+impl Disc {
+    /// Opens a disc and parses its index table, movie objects and all of its
+    /// playlists.
+    ///
+    /// `path` may either point to a `BDMV` directory or to a directory
+    /// containing one (e.g. the disc's mount point). Playlists that fail to
+    /// parse don't abort opening the disc; they are collected in [`errors`]
+    /// instead. The same goes for the navigation files; the index table and
+    /// the movie objects are read from the `BACKUP` directory if necessary.
+    ///
+    /// [`errors`]: #structfield.errors
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Disc, MplsError> {
+        let path = path.as_ref();
+        let root = if path.join("BDMV").is_dir() {
+            path.join("BDMV")
+        } else if path.join("PLAYLIST").is_dir() {
+            path.to_path_buf()
+        } else {
+            return Err(MplsError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no BDMV directory found in {}", path.display()),
+            )));
+        };
+        Disc::load(root, Source::Directory)
+    }
+
+    /// Opens a disc image (an ISO file of a disc with a UDF file system, see
+    /// [`UdfImage`]) and parses its index table, movie objects and all of its
+    /// playlists, just like [`open`].
+    ///
+    /// The disc's [`root`] is `<path>/BDMV`. Files in the image can be read
+    /// with [`read_file`] and [`read_range`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), mpls::MplsError> {
+    /// use mpls::Disc;
+    ///
+    /// let disc = Disc::open_iso("/library/movie.iso")?;
+    /// let clip = &disc.playlist(800).expect("no such playlist").play_list.play_items[0].clip;
+    /// let stream = disc.clip_files(clip).stream.expect("no stream");
+    /// // the first aligned unit (32 source packets) of the stream
+    /// let bytes = disc.read_range(&stream, 0..6144)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`UdfImage`]: ../udf/struct.UdfImage.html
+    /// [`open`]: #method.open
+    /// [`root`]: #structfield.root
+    /// [`read_file`]: #method.read_file
+    /// [`read_range`]: #method.read_range
+    pub fn open_iso<P: AsRef<Path>>(path: P) -> Result<Disc, MplsError> {
+        let path = path.as_ref();
+        let image = UdfImage::open(File::open(path)?)?;
+        let source = Source::Image {
+            path: path.to_path_buf(),
+            image: Mutex::new(image),
+        };
+        let root = path.join("BDMV");
+        if !source.is_dir(&root) {
+            return Err(MplsError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no BDMV directory found in {}", path.display()),
+            )));
+        }
+        Disc::load(root, source)
+    }
+
+    fn load(root: PathBuf, source: Source) -> Result<Disc, MplsError> {
+        let mut playlists = BTreeMap::new();
+        let mut errors = Vec::new();
+        let index = read_with_backup(
+            &source,
+            &root,
+            "index.bdmv",
+            |b| Index::from(b),
+            &mut errors,
+        );
+        let movie_objects = read_with_backup(
+            &source,
+            &root,
+            "MovieObject.bdmv",
+            |b| MovieObjects::from(b),
+            &mut errors,
+        );
+        let mut bdj_objects = BTreeMap::new();
+        for path in source.files(&root.join("BDJO"))? {
+            let name = match file_stem(&path, "bdjo") {
+                Some(n) => n.to_string(),
+                None => continue,
+            };
+            match source.read(&path).and_then(|b| Bdjo::from(&b[..])) {
+                Ok(bdjo) => {
+                    bdj_objects.insert(name, bdjo);
+                }
+                Err(e) => errors.push((path, e)),
+            }
+        }
+        if !source.is_dir(&root.join("PLAYLIST")) {
+            return Err(MplsError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no PLAYLIST directory found in {}", root.display()),
+            )));
+        }
+        for path in source.files(&root.join("PLAYLIST"))? {
+            let number = match playlist_number(&path) {
+                Some(n) => n,
+                None => continue,
+            };
+            match source.read(&path).and_then(|b| Mpls::from(&b[..])) {
+                Ok(mpls) => {
+                    playlists.insert(number, mpls);
+                }
+                Err(e) => errors.push((path, e)),
+            }
+        }
+
+        Ok(Disc {
+            root,
+            index,
+            movie_objects,
+            bdj_objects,
+            playlists,
+            errors,
+            source,
+        })
+    }
+
+    /// Reads a whole file of the disc, e.g. a clip information file.
+    pub fn read_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, MplsError> {
+        self.source.read(path.as_ref())
+    }
+
+    /// Reads a byte range of a file of the disc, e.g. a part of a transport
+    /// stream.
+    ///
+    /// The range is clamped to the file's size.
+    pub fn read_range<P: AsRef<Path>>(
+        &self,
+        path: P,
+        range: Range<u64>,
+    ) -> Result<Vec<u8>, MplsError> {
+        self.source.read_range(path.as_ref(), range)
+    }
+
+    // looks up `<name>.<ext>` in `dir`, accepting upper-case extensions as
+    // well, which some disc backups use
+    pub(crate) fn find_file(&self, dir: &Path, name: &str, ext: &str) -> Option<PathBuf> {
+        [ext.to_lowercase(), ext.to_uppercase()]
+            .iter()
+            .map(|e| dir.join(format!("{}.{}", name, e)))
+            .find(|p| self.source.is_file(p))
+    }
+
+    pub(crate) fn file_size(&self, path: &Path) -> Result<u64, MplsError> {
+        self.source.size(path)
+    }
+
+    // copies a file of the disc to the file system
+    pub(crate) fn copy_file(&self, from: &Path, to: &Path) -> Result<(), MplsError> {
+        self.source.copy(from, to)
+    }
+
+    /// Gets the playlist with the given number.
+    pub fn playlist(&self, number: u16) -> Option<&Mpls> {
+        self.playlists.get(&number)
+    }
+
+    /// Resolves a clip to its stream and clip information files.
+    pub fn clip_files(&self, clip: &Clip) -> ClipFiles {
+        let stream_dir = self.root.join("STREAM");
+        ClipFiles {
+            stream: self.find_file(&stream_dir, &clip.file_name, "m2ts"),
+            ssif: self.find_file(&stream_dir.join("SSIF"), &clip.file_name, "ssif"),
+            clip_info: self.find_file(&self.root.join("CLIPINF"), &clip.file_name, "clpi"),
+        }
+    }
+
+    /// Creates an HDMV virtual machine for simulating the disc's titles.
+    ///
+    /// Returns `None` if the disc has no (valid) index table or movie
+    /// objects.
+    pub fn vm(&self, config: VmConfig) -> Option<Vm<'_>> {
+        Some(Vm::new(
+            self.index.as_ref()?,
+            self.movie_objects.as_ref()?,
+            config,
+        ))
+    }
+
+    fn entry_playlists(&self, entry: Entry, config: &VmConfig) -> Option<Vec<u16>> {
+        let index = self.index.as_ref()?;
+        let object = match entry {
+            Entry::FirstPlayback => index.first_playback.as_ref()?,
+            Entry::TopMenu => index.top_menu.as_ref()?,
+            Entry::Title(n) => &index.title(n)?.object,
+        };
+        match object {
+            IndexObject::Hdmv { .. } => {
+                let mut vm = self.vm(config.clone())?;
+                let result = match entry {
+                    Entry::FirstPlayback => vm.run_first_playback(),
+                    Entry::TopMenu => vm.run_top_menu(),
+                    Entry::Title(n) => vm.run_title(n),
+                };
+                Some(result.playlists())
+            }
+            IndexObject::BdJ { name, .. } => {
+                let bdjo = self.bdj_objects.get(name)?;
+                if bdjo.accessible_playlists.access_to_all {
+                    Some(self.playlists.keys().copied().collect())
+                } else {
+                    Some(bdjo.playlists())
+                }
+            }
+        }
+    }
+
+    /// Finds the playlists the given title (starting at 1) plays.
+    ///
+    /// For HDMV titles, the title's movie objects are run in the [`Vm`] with
+    /// the given configuration. For BD-J titles, whose Java applications
+    /// can't be simulated, these are the playlists the title's BD-J object
+    /// makes accessible, with the autostart playlist (if any) first.
+    ///
+    /// Returns `None` if the title or the files needed to find its playlists
+    /// don't exist.
+    ///
+    /// [`Vm`]: ../vm/struct.Vm.html
+    pub fn title_playlists(&self, number: u16, config: &VmConfig) -> Option<Vec<u16>> {
+        self.entry_playlists(Entry::Title(number), config)
+    }
+
+    /// Finds all playlists that can be reached from the index table, i.e.
+    /// from the first playback object, the top menu and all titles. See
+    /// [`title_playlists`].
+    ///
+    /// The result may include playlists that don't exist on the disc.
+    ///
+    /// [`title_playlists`]: #method.title_playlists
+    pub fn reachable_playlists(&self, config: &VmConfig) -> BTreeSet<u16> {
+        let n_titles = self.index.as_ref().map_or(0, |i| i.titles.len() as u16);
+        let entries = vec![Entry::FirstPlayback, Entry::TopMenu]
+            .into_iter()
+            .chain((1..=n_titles).map(Entry::Title));
+        entries
+            .filter_map(|e| self.entry_playlists(e, config))
+            .flatten()
+            .collect()
+    }
+
+    /// Reads and parses the clip information file of a clip.
+    pub fn clip_info(&self, clip: &Clip) -> Result<Clpi, MplsError> {
+        match self.clip_files(clip).clip_info {
+            Some(path) => Clpi::from(&self.read_file(path)?[..]),
+            None => Err(MplsError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no clip information file found for {}", clip.file_name),
+            ))),
+        }
+    }
+
+    /// Finds all stream and clip information files that are referenced by a
+    /// playlist but don't exist.
+    ///
+    /// A clip's stream counts as present if either its `.m2ts` or its `.ssif`
+    /// file exists. The returned paths are sorted and free of duplicates.
+    pub fn missing_files(&self) -> Vec<PathBuf> {
+        let mut missing = Vec::new();
+        for mpls in self.playlists.values() {
+            for clip in mpls.clips() {
+                let files = self.clip_files(clip);
+                if files.stream.is_none() && files.ssif.is_none() {
+                    let name = format!("{}.m2ts", clip.file_name);
+                    missing.push(self.root.join("STREAM").join(name));
+                }
+                if files.clip_info.is_none() {
+                    let name = format!("{}.clpi", clip.file_name);
+                    missing.push(self.root.join("CLIPINF").join(name));
+                }
+            }
+        }
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+}
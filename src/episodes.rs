@@ -0,0 +1,177 @@
+//! Play-all and episode detection for TV series discs.
+//!
+//! TV series discs usually have one playlist per episode and a "play all"
+//! playlist that plays all of them back to back. [`find_play_alls`] finds
+//! playlists whose play items are exactly the play items of other playlists,
+//! concatenated, and splits them into their episodes.
+//!
+//! [`find_play_alls`]: fn.find_play_alls.html
+
+use crate::disc::Disc;
+use crate::main_feature::play_item_key;
+use crate::types::{Mpls, TimeStamp};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+/// An episode of a [`PlayAll`].
+///
+/// [`PlayAll`]: struct.PlayAll.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Episode {
+    /// The episode's own playlist number, e.g. 801 for `00801.mpls`.
+    pub playlist: u16,
+    pub duration: TimeStamp,
+    /// The episode's play items within the play-all playlist.
+    pub play_items: Range<usize>,
+    /// The episode's time range, relative to the start of the play-all
+    /// playlist.
+    pub range: Range<TimeStamp>,
+}
+
+/// A playlist that plays several other playlists back to back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayAll {
+    /// The play-all playlist number.
+    pub playlist: u16,
+    /// The episodes, in the order the play-all plays them.
+    pub episodes: Vec<Episode>,
+}
+
+type PlayItemKey<'a> = Vec<(&'a str, u32, u32)>;
+
+// splits `key` into as few of the candidates as possible, returning the
+// candidates' indices, or `None` if it can't be split into at least two
+fn split(key: &[(&str, u32, u32)], candidates: &[(u16, PlayItemKey<'_>)]) -> Option<Vec<usize>> {
+    // best[i]: the fewest candidates that make up key[..i], and the last one
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; key.len() + 1];
+    best[0] = Some((0, usize::MAX));
+    for i in 0..key.len() {
+        let count = match best[i] {
+            Some((count, _)) => count,
+            None => continue,
+        };
+        for (c, (_, candidate)) in candidates.iter().enumerate() {
+            let end = i + candidate.len();
+            // a candidate as long as `key` can only be `key` itself
+            if candidate.is_empty()
+                || candidate.len() >= key.len()
+                || end > key.len()
+                || key[i..end] != candidate[..]
+            {
+                continue;
+            }
+            let better = match best[end] {
+                Some((n, _)) => count + 1 < n,
+                None => true,
+            };
+            if better {
+                best[end] = Some((count + 1, c));
+            }
+        }
+    }
+
+    let (count, _) = best[key.len()]?;
+    if count < 2 {
+        return None;
+    }
+    let mut parts = Vec::with_capacity(count);
+    let mut end = key.len();
+    while end > 0 {
+        let (_, c) = best[end]?;
+        parts.push(c);
+        end -= candidates[c].1.len();
+    }
+    parts.reverse();
+    Some(parts)
+}
+
+/// Finds the playlists that are made up of two or more other playlists.
+///
+/// Play items are compared by their clip and in and out times. Of several
+/// identical episode playlists, the one with the lowest number is used, and
+/// playlists that are play-alls themselves (e.g. one per season) are never
+/// used as episodes. If there are several ways to split a playlist, the one
+/// with the fewest episodes wins.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> Result<(), mpls::MplsError> {
+/// use mpls::Disc;
+///
+/// let disc = Disc::open("/mnt/bluray")?;
+/// for play_all in disc.play_alls() {
+///     println!("{:05}.mpls plays:", play_all.playlist);
+///     for episode in play_all.episodes {
+///         println!("  {:05}.mpls ({:.0}s)", episode.playlist, episode.duration.seconds());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn find_play_alls(playlists: &BTreeMap<u16, Mpls>) -> Vec<PlayAll> {
+    // distinct play item sequences, each with its lowest playlist number
+    let mut seen = BTreeSet::new();
+    let mut candidates: Vec<(u16, PlayItemKey<'_>)> = Vec::new();
+    for (number, mpls) in playlists.iter() {
+        let key = play_item_key(mpls);
+        if !key.is_empty() && seen.insert(key.clone()) {
+            candidates.push((*number, key));
+        }
+    }
+
+    let is_play_all: Vec<bool> = candidates
+        .iter()
+        .map(|(_, key)| split(key, &candidates).is_some())
+        .collect();
+    let episodes: Vec<(u16, PlayItemKey<'_>)> = candidates
+        .iter()
+        .zip(is_play_all.iter())
+        .filter(|(_, p)| !**p)
+        .map(|(c, _)| c.clone())
+        .collect();
+
+    let mut play_alls = Vec::new();
+    for ((number, key), is_play_all) in candidates.iter().zip(is_play_all) {
+        if !is_play_all {
+            continue;
+        }
+        let parts = match split(key, &episodes) {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        let play_items = &playlists[number].play_list.play_items;
+        let mut start = 0;
+        let mut time = TimeStamp(0);
+        let mut result = Vec::with_capacity(parts.len());
+        for part in parts {
+            let (episode, episode_key) = &episodes[part];
+            let end = start + episode_key.len();
+            let duration =
+                TimeStamp::saturating_sum(play_items[start..end].iter().map(|p| p.duration()));
+            let end_time = TimeStamp(time.0.saturating_add(duration.0));
+            result.push(Episode {
+                playlist: *episode,
+                duration,
+                play_items: start..end,
+                range: time..end_time,
+            });
+            start = end;
+            time = end_time;
+        }
+        play_alls.push(PlayAll {
+            playlist: *number,
+            episodes: result,
+        });
+    }
+    play_alls
+}
+
+impl Disc {
+    /// Finds the disc's play-all playlists, see [`find_play_alls`].
+    ///
+    /// [`find_play_alls`]: ../episodes/fn.find_play_alls.html
+    pub fn play_alls(&self) -> Vec<PlayAll> {
+        find_play_alls(&self.playlists)
+    }
+}
@@ -1,6 +1,6 @@
 use std::{error::Error, fmt::Display};
 
-/// The error type of the [`Mpls::from`] method.
+/// The error type of this crate, e.g. of the [`Mpls::from`] method.
 ///
 /// [`Mpls::from`]: ../types/struct.Mpls.html#method.from
 #[derive(Debug)]
@@ -9,6 +9,18 @@ pub enum MplsError {
     IoError(std::io::Error),
     /// Failed to parse the byte stream as valid MPLS.
     ParseError,
+    /// Failed to parse a chapter file.
+    ChapterParseError(String),
+    /// Failed to parse the byte stream as valid clip information (CLPI).
+    ClpiParseError,
+    /// Failed to parse the byte stream as a valid index table.
+    IndexParseError,
+    /// Failed to parse the byte stream as valid movie objects.
+    MovieObjectParseError,
+    /// Failed to parse the byte stream as a valid BD-J object.
+    BdjoParseError,
+    /// Failed to read a UDF file system from a disc image.
+    UdfParseError,
 }
 
 impl Error for MplsError {}
@@ -18,6 +30,20 @@ impl Display for MplsError {
         match self {
             MplsError::IoError(e) => write!(f, "{}", e),
             MplsError::ParseError => write!(f, "failed to parse byte stream as valid MPLS"),
+            MplsError::ChapterParseError(e) => write!(f, "failed to parse chapters: {}", e),
+            MplsError::ClpiParseError => {
+                write!(f, "failed to parse byte stream as valid clip information")
+            }
+            MplsError::IndexParseError => {
+                write!(f, "failed to parse byte stream as valid index table")
+            }
+            MplsError::MovieObjectParseError => {
+                write!(f, "failed to parse byte stream as valid movie objects")
+            }
+            MplsError::BdjoParseError => {
+                write!(f, "failed to parse byte stream as valid BD-J object")
+            }
+            MplsError::UdfParseError => write!(f, "failed to read UDF file system"),
         }
     }
 }
@@ -0,0 +1,194 @@
+//! Export of single playlists as self-contained `BDMV` directories.
+//!
+//! [`Disc::export_playlist`] copies everything a playlist needs (see
+//! [`Disc::backup_plan`]) into a new `BDMV` directory, renumbers the playlist
+//! to `00000.mpls` and synthesizes an index table and movie objects that play
+//! it as title 1, so that the result can be played back on its own.
+//!
+//! [`Disc::export_playlist`]: ../disc/struct.Disc.html#method.export_playlist
+//! [`Disc::backup_plan`]: ../disc/struct.Disc.html#method.backup_plan
+
+use crate::backup::FileKind;
+use crate::disc::Disc;
+use crate::index::{AccessType, AppInfoBdmv, Index, IndexObject, PlaybackType, Title};
+use crate::movie_objects::{Command, Instruction, MovieObject, MovieObjects, Operand};
+use crate::types::Mpls;
+use crate::MplsError;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Restricts a playlist to one of its angles, replacing every multi-angle
+/// play item's clip with the angle's clip and dropping all other angles.
+///
+/// Sub play items with multi-clip entries are restricted the same way.
+pub fn restrict_to_angle(mpls: &Mpls, angle: u8) -> Mpls {
+    let mut mpls = mpls.clone();
+    let n = angle as usize;
+    for item in mpls.play_list.play_items.iter_mut() {
+        if n > 0 && n <= item.angles.len() {
+            item.clip = item.angles[n - 1].clone();
+        }
+        item.angles.clear();
+        item.angle_info = None;
+    }
+    for sub_path in mpls.play_list.sub_paths.iter_mut() {
+        for item in sub_path.play_items.iter_mut() {
+            if n > 0 && n <= item.multi_clip_entries.len() {
+                item.clip = item.multi_clip_entries[n - 1].clone();
+            }
+            item.multi_clip_entries.clear();
+        }
+    }
+    mpls
+}
+
+// an index table whose first playback and only title run movie object 0
+fn index(disc: &Disc) -> Index {
+    let object = IndexObject::Hdmv {
+        playback_type: PlaybackType::Movie,
+        movie_object: 0,
+    };
+    let (version, app_info) = match disc.index.as_ref() {
+        Some(index) => (index.version.clone(), index.app_info.clone()),
+        None => (
+            "0200".into(),
+            AppInfoBdmv {
+                initial_output_mode_preference_3d: false,
+                content_exist_3d: false,
+                initial_dynamic_range_type: 0,
+                video_format: 0,
+                frame_rate: 0,
+                user_data: vec![0; 32],
+            },
+        ),
+    };
+    Index {
+        version,
+        app_info,
+        first_playback: Some(object.clone()),
+        top_menu: None,
+        titles: vec![Title {
+            object,
+            access_type: AccessType {
+                title_search_prohibited: false,
+                hidden: false,
+            },
+        }],
+        ext: Vec::new(),
+    }
+}
+
+// a single movie object that plays playlist 0
+fn movie_objects() -> MovieObjects {
+    MovieObjects {
+        version: "0200".into(),
+        objects: vec![MovieObject {
+            resume_intention_flag: false,
+            menu_call_mask: true,
+            title_search_mask: false,
+            commands: vec![Command::from(Instruction::PlayPl(Operand::Immediate(0)))],
+        }],
+        ext: Vec::new(),
+    }
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+impl Disc {
+    /// Writes a new `BDMV` directory to `dest` (i.e. `dest/BDMV`) that only
+    /// contains the given playlist, renumbered to `00000.mpls`.
+    ///
+    /// The playlist's clip information and stream files are copied, and an
+    /// index table and a movie object file are synthesized that play the
+    /// playlist on disc insertion and as title 1. The navigation files, the
+    /// playlist and the clip information are also written to the `BACKUP`
+    /// directory, as on real discs. If an `angle` is given, the playlist is
+    /// restricted to that angle (see [`restrict_to_angle`]) and only its
+    /// clips are copied.
+    ///
+    /// Fails if the playlist doesn't exist, if any of its files are missing
+    /// (see [`BackupPlan::missing`]) or if `dest/BDMV` already exists.
+    /// Returns the paths of all written files.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), mpls::MplsError> {
+    /// use mpls::Disc;
+    ///
+    /// let disc = Disc::open("/mnt/bluray")?;
+    /// disc.export_playlist(800, None, "/backups/movie")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`restrict_to_angle`]: ../export/fn.restrict_to_angle.html
+    /// [`BackupPlan::missing`]: ../backup/struct.BackupPlan.html#structfield.missing
+    pub fn export_playlist<P: AsRef<Path>>(
+        &self,
+        playlist: u16,
+        angle: Option<u8>,
+        dest: P,
+    ) -> Result<Vec<PathBuf>, MplsError> {
+        let not_found =
+            |msg: String| MplsError::IoError(io::Error::new(io::ErrorKind::NotFound, msg));
+        let plan = self
+            .backup_plan(playlist, angle)
+            .ok_or_else(|| not_found(format!("no playlist {:05}.mpls", playlist)))?;
+        if let Some(path) = plan.missing.first() {
+            return Err(not_found(format!("{} doesn't exist", path.display())));
+        }
+        let root = dest.as_ref().join("BDMV");
+        if root.exists() {
+            return Err(MplsError::IoError(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", root.display()),
+            )));
+        }
+
+        let mpls = &self.playlists[&playlist];
+        let mpls = match angle {
+            Some(angle) => restrict_to_angle(mpls, angle),
+            None => mpls.clone(),
+        };
+        let mut written = Vec::new();
+        let mut write = |relative: &str, contents: &[u8], backup: bool| -> io::Result<()> {
+            let path = root.join(relative);
+            write_file(&path, contents)?;
+            written.push(path);
+            if backup {
+                let path = root.join("BACKUP").join(relative);
+                write_file(&path, contents)?;
+                written.push(path);
+            }
+            Ok(())
+        };
+        write("index.bdmv", &index(self).to_bytes(), true)?;
+        write("MovieObject.bdmv", &movie_objects().to_bytes(), true)?;
+        write("PLAYLIST/00000.mpls", &mpls.to_bytes()?, true)?;
+
+        for file in plan.files.iter() {
+            let targets = match file.kind {
+                FileKind::Playlist => continue,
+                FileKind::ClipInfo => vec![
+                    root.join(&file.relative_path),
+                    root.join("BACKUP").join(&file.relative_path),
+                ],
+                FileKind::Stream | FileKind::Ssif => vec![root.join(&file.relative_path)],
+            };
+            for target in targets {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                self.copy_file(&file.path, &target)?;
+                written.push(target);
+            }
+        }
+        Ok(written)
+    }
+}
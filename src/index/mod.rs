@@ -0,0 +1,14 @@
+//! An index table file (`index.bdmv`) parser.
+//!
+//! The index table lists the disc's titles and the objects (HDMV movie
+//! objects or BD-J objects) that are run when a title is selected. The entry
+//! point is the [`Index`] struct, which can be obtained through its [`from`]
+//! method.
+//!
+//! [`Index`]: struct.Index.html
+//! [`from`]: struct.Index.html#method.from
+mod parser;
+mod types;
+mod writer;
+
+pub use types::*;
@@ -0,0 +1,205 @@
+use super::types::{AccessType, AppInfoBdmv, Index, IndexObject, PlaybackType, Title};
+use crate::parser::{addr, extension_data, str_len, str_len_owned, version};
+use nom::{
+    bytes::complete::take,
+    count, do_parse,
+    error::ErrorKind,
+    multi::length_value,
+    number::complete::{be_u16, be_u32, be_u8},
+    take, Err, IResult,
+};
+
+// matches the ASCII/UTF-8 string "INDX"
+fn header_tag(input: &[u8]) -> IResult<&[u8], &str> {
+    let (rest, s) = str_len(4, input)?;
+    if s == "INDX" {
+        Ok((rest, s))
+    } else {
+        Err(Err::Error((input, ErrorKind::Tag)))
+    }
+}
+
+fn app_info(input: &[u8]) -> IResult<&[u8], AppInfoBdmv> {
+    fn parser(input: &[u8]) -> IResult<&[u8], AppInfoBdmv> {
+        do_parse!(
+            input,
+            flags: be_u8
+                >> video: be_u8
+                >> user_data: take!(32usize)
+                >> (AppInfoBdmv {
+                    initial_output_mode_preference_3d: flags & 0x40 != 0,
+                    content_exist_3d: flags & 0x20 != 0,
+                    initial_dynamic_range_type: flags & 0x0F,
+                    video_format: video >> 4,
+                    frame_rate: video & 0x0F,
+                    user_data: user_data.to_vec(),
+                })
+        )
+    }
+
+    length_value(be_u32, parser)(input)
+}
+
+fn playback_type(bits: u8) -> PlaybackType {
+    // HDMV objects use the values 0 and 1, BD-J objects 2 and 3
+    match bits {
+        0 | 2 => PlaybackType::Movie,
+        1 | 3 => PlaybackType::Interactive,
+        _ => PlaybackType::Unknown,
+    }
+}
+
+// the 8 bytes describing an object, once its type is known
+fn index_object(object_type: u8, input: &[u8]) -> IResult<&[u8], Option<IndexObject>> {
+    let (input, data) = take(8usize)(input)?;
+    let (rest, bits) = be_u16(data)?;
+    let playback_type = playback_type((bits >> 14) as u8);
+    let object = match object_type {
+        1 => {
+            let (_, movie_object) = be_u16(rest)?;
+            Some(IndexObject::Hdmv {
+                playback_type,
+                movie_object,
+            })
+        }
+        2 => {
+            let (_, name) = str_len_owned(5, rest)?;
+            Some(IndexObject::BdJ {
+                playback_type,
+                name,
+            })
+        }
+        _ => None,
+    };
+    Ok((input, object))
+}
+
+// first playback and top menu, which may be absent
+fn optional_object(input: &[u8]) -> IResult<&[u8], Option<IndexObject>> {
+    let (input, bits) = be_u32(input)?;
+    index_object((bits >> 30) as u8, input)
+}
+
+fn title(input: &[u8]) -> IResult<&[u8], Title> {
+    let (rest, bits) = be_u32(input)?;
+    let access_type = AccessType {
+        title_search_prohibited: bits & (1 << 28) != 0,
+        hidden: bits & (1 << 29) != 0,
+    };
+    match index_object((bits >> 30) as u8, rest)? {
+        (rest, Some(object)) => Ok((
+            rest,
+            Title {
+                object,
+                access_type,
+            },
+        )),
+        (_, None) => Err(Err::Error((input, ErrorKind::Switch))),
+    }
+}
+
+type Indexes = (Option<IndexObject>, Option<IndexObject>, Vec<Title>);
+
+fn indexes(input: &[u8]) -> IResult<&[u8], Indexes> {
+    fn parser(input: &[u8]) -> IResult<&[u8], Indexes> {
+        do_parse!(
+            input,
+            first_playback: optional_object
+                >> top_menu: optional_object
+                >> n_titles: be_u16
+                >> titles: count!(title, n_titles as usize)
+                >> ((first_playback, top_menu, titles))
+        )
+    }
+
+    length_value(be_u32, parser)(input)
+}
+
+pub fn parse_index(input: &[u8]) -> IResult<&[u8], Index> {
+    let (_, (version, indexes_addr, ext_addr, app_info)) = do_parse!(
+        input,
+        header_tag
+            >> version: version
+            >> indexes_addr: addr
+            >> ext_addr: addr
+            >> take!(24usize)
+            >> app_info: app_info
+            >> ((version, indexes_addr, ext_addr, app_info))
+    )?;
+
+    if indexes_addr as usize > input.len() {
+        return Err(Err::Error((input, ErrorKind::Eof)));
+    }
+    let (_, (first_playback, top_menu, titles)) = indexes(&input[indexes_addr as usize..])?;
+    let ext = if ext_addr != 0 && (ext_addr as usize) < input.len() {
+        extension_data(&input[ext_addr as usize..])?.1
+    } else {
+        Vec::new()
+    };
+
+    Ok((
+        &[],
+        Index {
+            version: version.into(),
+            app_info,
+            first_playback,
+            top_menu,
+            titles,
+            ext,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::types::{IndexObject, PlaybackType};
+
+    #[test]
+    fn header_tag() {
+        let data = [0x49, 0x4e, 0x44, 0x58, 0x30];
+        let sl = &data[..];
+        assert_eq!(super::header_tag(sl), Ok((&sl[4..], "INDX")));
+    }
+
+    #[test]
+    fn hdmv_title() {
+        // HDMV, title search prohibited, interactive, movie object 3
+        let data = [
+            0x50, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let (rest, title) = super::title(&data[..]).unwrap();
+        assert!(rest.is_empty());
+        assert!(title.access_type.title_search_prohibited);
+        assert!(!title.access_type.hidden);
+        assert_eq!(
+            title.object,
+            IndexObject::Hdmv {
+                playback_type: PlaybackType::Interactive,
+                movie_object: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn bdj_title() {
+        // BD-J, hidden, movie, object 00001
+        let data = [
+            0xA0, 0x00, 0x00, 0x00, 0x80, 0x00, 0x30, 0x30, 0x30, 0x30, 0x31, 0x00,
+        ];
+        let (_, title) = super::title(&data[..]).unwrap();
+        assert!(title.access_type.hidden);
+        assert_eq!(
+            title.object,
+            IndexObject::BdJ {
+                playback_type: PlaybackType::Movie,
+                name: "00001".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn no_first_playback() {
+        let data = [0u8; 12];
+        assert_eq!(super::optional_object(&data[..]), Ok((&[][..], None)));
+    }
+}
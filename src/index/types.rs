@@ -0,0 +1,126 @@
+use super::parser::parse_index;
+use super::writer::write_index;
+use crate::types::ExtensionDataEntry;
+use crate::MplsError;
+use std::io::Read;
+
+/// The index table of a disc, `BDMV/index.bdmv`.
+#[derive(Debug, Clone)]
+pub struct Index {
+    /// The file format version, e.g. "0200" or "0300".
+    pub version: String,
+    pub app_info: AppInfoBdmv,
+    /// The object that is run when the disc is inserted.
+    pub first_playback: Option<IndexObject>,
+    /// The object that is run when the top menu is requested.
+    pub top_menu: Option<IndexObject>,
+    /// The disc's titles. Title numbers start at 1, so the first entry is
+    /// title 1.
+    pub titles: Vec<Title>,
+    pub ext: Vec<ExtensionDataEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AppInfoBdmv {
+    pub initial_output_mode_preference_3d: bool,
+    pub content_exist_3d: bool,
+    pub initial_dynamic_range_type: u8,
+    pub video_format: u8,
+    pub frame_rate: u8,
+    /// 32 bytes of content provider data.
+    pub user_data: Vec<u8>,
+}
+
+/// A numbered title of the index table.
+#[derive(Debug, Clone)]
+pub struct Title {
+    pub object: IndexObject,
+    pub access_type: AccessType,
+}
+
+/// The object that is run for an entry of the index table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexObject {
+    /// An HDMV movie object, referenced by its index in `MovieObject.bdmv`.
+    Hdmv {
+        playback_type: PlaybackType,
+        movie_object: u16,
+    },
+    /// A BD-J object, referenced by its file name in `BDMV/BDJO` (e.g.
+    /// "00000" for `00000.bdjo`).
+    BdJ {
+        playback_type: PlaybackType,
+        name: String,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlaybackType {
+    Movie,
+    Interactive,
+    Unknown,
+}
+
+/// Whether a title may be selected directly by the user.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AccessType {
+    /// Title search, i.e. jumping to this title by number, is prohibited.
+    pub title_search_prohibited: bool,
+    /// The title number isn't displayed to the user.
+    pub hidden: bool,
+}
+
+impl IndexObject {
+    pub fn playback_type(&self) -> PlaybackType {
+        match self {
+            IndexObject::Hdmv { playback_type, .. } => *playback_type,
+            IndexObject::BdJ { playback_type, .. } => *playback_type,
+        }
+    }
+
+    /// Returns whether this is a BD-J object.
+    pub fn is_bdj(&self) -> bool {
+        matches!(self, IndexObject::BdJ { .. })
+    }
+}
+
+impl Index {
+    /// Attempts to parse an index table from the given reader.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::fs::File;
+    /// use mpls::Index;
+    ///
+    /// let file = File::open("index.bdmv")?;
+    /// let index = Index::from(&file).expect("failed to parse index.bdmv.");
+    /// for (i, title) in index.titles.iter().enumerate() {
+    ///     println!("title {}: {:?}", i + 1, title.object);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from<R: Read>(mut reader: R) -> Result<Index, MplsError> {
+        let bytes = {
+            let mut buffer = Vec::new();
+            reader.read_to_end(&mut buffer)?;
+            buffer
+        };
+
+        parse_index(&bytes)
+            .map_err(|_| MplsError::IndexParseError)
+            .map(|(_, i)| i)
+    }
+
+    /// Serializes the index table into the `index.bdmv` binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        write_index(self)
+    }
+
+    /// Gets the title with the given number, starting at 1.
+    pub fn title(&self, number: u16) -> Option<&Title> {
+        let i = (number as usize).checked_sub(1)?;
+        self.titles.get(i)
+    }
+}
@@ -0,0 +1,85 @@
+use super::types::{AppInfoBdmv, Index, IndexObject, PlaybackType, Title};
+use crate::writer::{extension_data, str_len, u32_len};
+
+fn app_info(buf: &mut Vec<u8>, info: &AppInfoBdmv) {
+    let mut body = vec![
+        ((info.initial_output_mode_preference_3d as u8) << 6)
+            | ((info.content_exist_3d as u8) << 5)
+            | (info.initial_dynamic_range_type & 0x0F),
+        (info.video_format << 4) | (info.frame_rate & 0x0F),
+    ];
+    let mut user_data = info.user_data.clone();
+    user_data.resize(32, 0);
+    body.extend(user_data);
+    u32_len(buf, body);
+}
+
+// the 12 bytes of an object, with the access type bits of titles
+fn index_object(buf: &mut Vec<u8>, object: Option<&IndexObject>, access_type: u32) {
+    fn playback_type(p: PlaybackType) -> u16 {
+        match p {
+            PlaybackType::Interactive => 1,
+            _ => 0,
+        }
+    }
+
+    let mut body = Vec::with_capacity(12);
+    match object {
+        None => body.extend_from_slice(&[0; 6]),
+        Some(IndexObject::Hdmv {
+            playback_type: p,
+            movie_object,
+        }) => {
+            body.extend_from_slice(&((1 << 30) | access_type).to_be_bytes());
+            body.extend_from_slice(&(playback_type(*p) << 14).to_be_bytes());
+            body.extend_from_slice(&movie_object.to_be_bytes());
+        }
+        Some(IndexObject::BdJ {
+            playback_type: p,
+            name,
+        }) => {
+            body.extend_from_slice(&((2 << 30) | access_type).to_be_bytes());
+            // BD-J objects use the values 2 and 3
+            body.extend_from_slice(&((2 + playback_type(*p)) << 14).to_be_bytes());
+            str_len(&mut body, name, 5);
+        }
+    }
+    body.resize(12, 0);
+    buf.extend(body);
+}
+
+fn title(buf: &mut Vec<u8>, title: &Title) {
+    let access_type = ((title.access_type.hidden as u32) << 29)
+        | ((title.access_type.title_search_prohibited as u32) << 28);
+    index_object(buf, Some(&title.object), access_type);
+}
+
+pub fn write_index(index: &Index) -> Vec<u8> {
+    let mut sections = Vec::new();
+    app_info(&mut sections, &index.app_info);
+    let indexes_addr = 40 + sections.len() as u32;
+    let mut indexes = Vec::new();
+    index_object(&mut indexes, index.first_playback.as_ref(), 0);
+    index_object(&mut indexes, index.top_menu.as_ref(), 0);
+    indexes.extend_from_slice(&(index.titles.len() as u16).to_be_bytes());
+    for t in index.titles.iter() {
+        title(&mut indexes, t);
+    }
+    u32_len(&mut sections, indexes);
+    let ext_addr = if index.ext.is_empty() {
+        0
+    } else {
+        let addr = 40 + sections.len() as u32;
+        extension_data(&mut sections, &index.ext);
+        addr
+    };
+
+    let mut buf = Vec::with_capacity(40 + sections.len());
+    buf.extend_from_slice(b"INDX");
+    str_len(&mut buf, &index.version, 4);
+    buf.extend_from_slice(&indexes_addr.to_be_bytes());
+    buf.extend_from_slice(&ext_addr.to_be_bytes());
+    buf.extend_from_slice(&[0; 24]);
+    buf.extend(sections);
+    buf
+}
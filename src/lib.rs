@@ -1,4 +1,4 @@
-#![doc(html_root_url = "https://docs.rs/mpls/0.2.0")]
+#![doc(html_root_url = "https://docs.rs/mpls/0.3.0")]
 
 //! A movie playlist file (MPLS) parser.
 //!
@@ -52,9 +52,37 @@
 //! # Ok(())
 //! # }
 //! ```
+pub mod backup;
+#[cfg(feature = "rayon")]
+pub mod batch;
+pub mod bdjo;
+pub mod chapters;
+pub mod classify;
+pub mod clip_graph;
+pub mod clpi;
+pub mod cuts;
+pub mod diff;
+pub mod disc;
+pub mod episodes;
 pub mod error;
+pub mod export;
+pub mod index;
+pub mod main_feature;
+pub mod movie_objects;
+pub mod obfuscation;
 mod parser;
+pub mod summary;
 pub mod types;
+pub mod udf;
+pub mod view;
+pub mod vm;
+mod writer;
 
+pub use bdjo::Bdjo;
+pub use chapters::{Chapter, ChapterName};
+pub use clpi::{Clpi, EntryPoint};
+pub use disc::{ClipFiles, Disc};
 pub use error::MplsError;
+pub use index::Index;
+pub use movie_objects::MovieObjects;
 pub use types::*;
@@ -0,0 +1,337 @@
+//! Main feature detection.
+//!
+//! [`Disc::main_feature`] ranks all playlists of a disc by how likely they
+//! are to be the main feature. Each playlist's score is the sum of a few
+//! independent criteria (see [`Criterion`]), and every candidate carries a
+//! breakdown of the points it got for each of them, so that the ranking can be
+//! explained and tuned.
+//!
+//! [`Disc::main_feature`]: ../disc/struct.Disc.html#method.main_feature
+//! [`Criterion`]: enum.Criterion.html
+
+use crate::disc::Disc;
+use crate::types::{Mpls, TimeStamp};
+use crate::vm::VmConfig;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+/// Playlists shorter than this are unlikely to be a feature.
+const MIN_FEATURE_DURATION: TimeStamp = TimeStamp(15 * 60 * 45_000);
+/// Playlists whose clips are also played in at least this many different
+/// orders by other playlists are assumed to be decoys.
+const MIN_DECOY_GROUP: usize = 3;
+
+/// A criterion that contributes to a playlist's score.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Criterion {
+    /// The playlist's duration, relative to the longest one on the disc.
+    Duration,
+    /// Whether the playlist plays any of its clips more than once.
+    UniqueClips,
+    /// The number of chapters.
+    Chapters,
+    /// The number of audio and subtitle tracks.
+    Tracks,
+    /// Whether the playlist duplicates another one or belongs to a group of
+    /// likely decoys.
+    Duplicates,
+    /// Whether a title of the index table plays the playlist.
+    Reachability,
+}
+
+/// The points a playlist got for a single criterion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreComponent {
+    pub criterion: Criterion,
+    pub points: f64,
+    /// A human-readable explanation of the points.
+    pub reason: String,
+}
+
+/// A playlist ranked by [`Disc::main_feature`].
+///
+/// [`Disc::main_feature`]: ../disc/struct.Disc.html#method.main_feature
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureCandidate {
+    /// The playlist number, e.g. 800 for `00800.mpls`.
+    pub playlist: u16,
+    /// The total score, i.e. the sum of all components' points.
+    pub score: f64,
+    pub breakdown: Vec<ScoreComponent>,
+}
+
+impl Display for FeatureCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:05}.mpls: {:.1}", self.playlist, self.score)?;
+        for c in self.breakdown.iter() {
+            writeln!(f, "  {:+6.1} {:?}: {}", c.points, c.criterion, c.reason)?;
+        }
+        Ok(())
+    }
+}
+
+// the play items' clips and in/out times, in playlist order
+pub(crate) fn play_item_key(mpls: &Mpls) -> Vec<(&str, u32, u32)> {
+    mpls.play_list
+        .play_items
+        .iter()
+        .map(|p| (p.clip.file_name.as_str(), p.in_time.0, p.out_time.0))
+        .collect()
+}
+
+fn duration(mpls: &Mpls, longest: TimeStamp) -> ScoreComponent {
+    let duration = mpls.duration();
+    let ratio = if longest.0 > 0 {
+        duration.0 as f64 / longest.0 as f64
+    } else {
+        0.0
+    };
+    let mut points = 40.0 * ratio;
+    let mut reason = format!(
+        "{:.1} minutes, {:.0}% of the longest playlist",
+        duration.seconds() / 60.0,
+        ratio * 100.0
+    );
+    if duration < MIN_FEATURE_DURATION {
+        points -= 20.0;
+        reason.push_str(", too short for a feature");
+    }
+    ScoreComponent {
+        criterion: Criterion::Duration,
+        points,
+        reason,
+    }
+}
+
+fn unique_clips(mpls: &Mpls) -> ScoreComponent {
+    let play_items = &mpls.play_list.play_items;
+    let unique: BTreeSet<&str> = play_items
+        .iter()
+        .map(|p| p.clip.file_name.as_str())
+        .collect();
+    let repeated = play_items.len() - unique.len();
+    if repeated == 0 {
+        ScoreComponent {
+            criterion: Criterion::UniqueClips,
+            points: 5.0,
+            reason: format!("all {} play items use a different clip", play_items.len()),
+        }
+    } else {
+        ScoreComponent {
+            criterion: Criterion::UniqueClips,
+            points: -15.0 * repeated as f64 / play_items.len() as f64,
+            reason: format!(
+                "{} of {} play items repeat an earlier clip",
+                repeated,
+                play_items.len()
+            ),
+        }
+    }
+}
+
+fn chapters(mpls: &Mpls) -> ScoreComponent {
+    let n = mpls.chapters().len();
+    ScoreComponent {
+        criterion: Criterion::Chapters,
+        points: n.min(16) as f64 * 0.5,
+        reason: format!("{} chapters", n),
+    }
+}
+
+fn tracks(mpls: &Mpls) -> ScoreComponent {
+    let (audio, subtitles) = mpls.play_list.play_items.first().map_or((0, 0), |p| {
+        let table = &p.stream_number_table;
+        (
+            table.primary_audio_streams.len(),
+            table.primary_pgs_streams.len(),
+        )
+    });
+    ScoreComponent {
+        criterion: Criterion::Tracks,
+        points: audio.min(8) as f64 + subtitles.min(16) as f64 * 0.5,
+        reason: format!("{} audio and {} subtitle tracks", audio, subtitles),
+    }
+}
+
+impl Disc {
+    // the titles that play each playlist
+    fn playlist_titles(&self, config: &VmConfig) -> Option<BTreeMap<u16, Vec<u16>>> {
+        let index = self.index.as_ref()?;
+        let mut titles: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+        for number in 1..=index.titles.len() as u16 {
+            for playlist in self.title_playlists(number, config).unwrap_or_default() {
+                titles.entry(playlist).or_default().push(number);
+            }
+        }
+        Some(titles)
+    }
+
+    /// Ranks the disc's playlists by how likely they are to be the main
+    /// feature, best candidate first.
+    ///
+    /// A playlist's score is the sum of the points it gets for each
+    /// criterion:
+    ///
+    /// * whether a title plays the playlist, if the disc has an index table
+    ///   (titles are simulated with the default [`VmConfig`]): +25 if one
+    ///   does, -15 otherwise,
+    /// * the duration: up to +40 in proportion to the longest playlist, minus
+    ///   20 if it's too short for a feature,
+    /// * whether the playlist is an exact copy of a playlist with a lower
+    ///   number (-5), or one of many playlists made up of the same clips (as
+    ///   some discs use to hide the main feature among decoys) whose clip
+    ///   order isn't the likely original one (-10, see
+    ///   [`Disc::decoy_groups`]),
+    /// * whether any clip is played more than once: +5 if none is, otherwise
+    ///   up to -15 in proportion to the play items that repeat a clip,
+    /// * the number of audio and subtitle tracks: 1 point per audio track and
+    ///   half a point per subtitle track, at most +16, and
+    /// * the number of chapters: half a point per chapter, at most +8.
+    ///
+    /// Each candidate's [`breakdown`] lists the points of every criterion.
+    ///
+    /// Candidates with the same score are ordered by playlist number.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), mpls::MplsError> {
+    /// use mpls::Disc;
+    ///
+    /// let disc = Disc::open("/mnt/bluray")?;
+    /// for candidate in disc.main_feature().iter().take(3) {
+    ///     println!("{}", candidate);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`VmConfig`]: ../vm/struct.VmConfig.html
+    /// [`Disc::decoy_groups`]: #method.decoy_groups
+    /// [`breakdown`]: ../main_feature/struct.FeatureCandidate.html#structfield.breakdown
+    pub fn main_feature(&self) -> Vec<FeatureCandidate> {
+        let longest = self
+            .playlists
+            .values()
+            .map(Mpls::duration)
+            .max()
+            .unwrap_or_default();
+        let titles = self.playlist_titles(&VmConfig::default());
+
+        // playlists grouped by their set of play items, regardless of order
+        let mut groups: BTreeMap<Vec<(&str, u32, u32)>, Vec<u16>> = BTreeMap::new();
+        for (number, mpls) in self.playlists.iter() {
+            let mut key = play_item_key(mpls);
+            key.sort_unstable();
+            groups.entry(key).or_default().push(*number);
+        }
+
+        let orders = |group: &[u16]| {
+            let orders: BTreeSet<_> = group
+                .iter()
+                .map(|n| play_item_key(&self.playlists[n]))
+                .collect();
+            orders.len()
+        };
+
+        // the likely originals of each playlist's decoy group, which are
+        // only analyzed if there are decoys at all
+        let mut originals: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+        if groups.values().any(|g| orders(g) >= MIN_DECOY_GROUP) {
+            for group in self.decoy_groups() {
+                let likely = group.likely_originals();
+                for p in group.playlists.iter() {
+                    originals.insert(p.playlist, likely.clone());
+                }
+            }
+        }
+        // the members of a group that aren't penalized as decoys: those
+        // whose clip order is likely the original one, or, if that's
+        // unknown, those that a title plays
+        let likely_originals = |group: &[u16]| {
+            let likely: Vec<u16> = group
+                .iter()
+                .copied()
+                .filter(|n| originals.get(n).is_some_and(|o| o.contains(n)))
+                .collect();
+            if !likely.is_empty() {
+                return likely;
+            }
+            group
+                .iter()
+                .copied()
+                .filter(|n| titles.as_ref().is_some_and(|t| t.contains_key(n)))
+                .collect()
+        };
+
+        let mut candidates: Vec<FeatureCandidate> = Vec::new();
+        for (number, mpls) in self.playlists.iter() {
+            let mut breakdown = vec![
+                duration(mpls, longest),
+                unique_clips(mpls),
+                chapters(mpls),
+                tracks(mpls),
+            ];
+
+            let key = play_item_key(mpls);
+            let mut sorted_key = key.clone();
+            sorted_key.sort_unstable();
+            let group = &groups[&sorted_key];
+            let original = group
+                .iter()
+                .take_while(|n| *n < number)
+                .find(|n| play_item_key(&self.playlists[*n]) == key);
+            let mut points = 0.0;
+            let mut reasons = Vec::new();
+            if let Some(original) = original {
+                points -= 5.0;
+                reasons.push(format!("identical to {:05}.mpls", original));
+            }
+            if orders(group) >= MIN_DECOY_GROUP {
+                let group_reason = format!(
+                    "{} playlists with the same clips in {} different orders",
+                    group.len(),
+                    orders(group)
+                );
+                if likely_originals(group).contains(number) {
+                    reasons.push(format!("likely original among {}", group_reason));
+                } else {
+                    points -= 10.0;
+                    reasons.push(format!("one of {}", group_reason));
+                }
+            }
+            if reasons.is_empty() {
+                reasons.push("no duplicates".into());
+            }
+            breakdown.push(ScoreComponent {
+                criterion: Criterion::Duplicates,
+                points,
+                reason: reasons.join(", "),
+            });
+
+            if let Some(titles) = titles.as_ref() {
+                breakdown.push(match titles.get(number) {
+                    Some(t) => ScoreComponent {
+                        criterion: Criterion::Reachability,
+                        points: 25.0,
+                        reason: format!("played by title {}", t[0]),
+                    },
+                    None => ScoreComponent {
+                        criterion: Criterion::Reachability,
+                        points: -15.0,
+                        reason: "not played by any title".into(),
+                    },
+                });
+            }
+
+            candidates.push(FeatureCandidate {
+                playlist: *number,
+                score: breakdown.iter().map(|c| c.points).sum(),
+                breakdown,
+            });
+        }
+
+        // the sort is stable, so equal scores stay ordered by playlist number
+        candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        candidates
+    }
+}
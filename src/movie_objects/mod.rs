@@ -0,0 +1,16 @@
+//! A movie object file (`MovieObject.bdmv`) parser.
+//!
+//! Movie objects are small programs of HDMV navigation commands that are run
+//! by the player when a title of the index table is selected. The entry point
+//! is the [`MovieObjects`] struct, which can be obtained through its [`from`]
+//! method. Each command can be decoded into an [`Instruction`], whose
+//! `Display` implementation produces a human-readable disassembly.
+//!
+//! [`MovieObjects`]: struct.MovieObjects.html
+//! [`from`]: struct.MovieObjects.html#method.from
+//! [`Instruction`]: enum.Instruction.html
+mod parser;
+mod types;
+mod writer;
+
+pub use types::*;
@@ -0,0 +1,108 @@
+use super::types::{Command, MovieObject, MovieObjects};
+use crate::parser::{addr, extension_data, str_len, version};
+use nom::{
+    count, do_parse,
+    error::ErrorKind,
+    multi::length_value,
+    number::complete::{be_u16, be_u32},
+    take, Err, IResult,
+};
+
+// matches the ASCII/UTF-8 string "MOBJ"
+fn header_tag(input: &[u8]) -> IResult<&[u8], &str> {
+    let (rest, s) = str_len(4, input)?;
+    if s == "MOBJ" {
+        Ok((rest, s))
+    } else {
+        Err(Err::Error((input, ErrorKind::Tag)))
+    }
+}
+
+fn command(input: &[u8]) -> IResult<&[u8], Command> {
+    do_parse!(
+        input,
+        opcode: be_u32 >> dst: be_u32 >> src: be_u32 >> (Command { opcode, dst, src })
+    )
+}
+
+fn movie_object(input: &[u8]) -> IResult<&[u8], MovieObject> {
+    do_parse!(
+        input,
+        flags: be_u16
+            >> n_commands: be_u16
+            >> commands: count!(command, n_commands as usize)
+            >> (MovieObject {
+                resume_intention_flag: flags & 0x8000 != 0,
+                menu_call_mask: flags & 0x4000 != 0,
+                title_search_mask: flags & 0x2000 != 0,
+                commands,
+            })
+    )
+}
+
+fn movie_objects(input: &[u8]) -> IResult<&[u8], Vec<MovieObject>> {
+    fn parser(input: &[u8]) -> IResult<&[u8], Vec<MovieObject>> {
+        do_parse!(
+            input,
+            take!(4usize)
+                >> n_objects: be_u16
+                >> objects: count!(movie_object, n_objects as usize)
+                >> (objects)
+        )
+    }
+
+    length_value(be_u32, parser)(input)
+}
+
+pub fn parse_movie_objects(input: &[u8]) -> IResult<&[u8], MovieObjects> {
+    let (_, (version, ext_addr, objects)) = do_parse!(
+        input,
+        header_tag
+            >> version: version
+            >> ext_addr: addr
+            >> take!(28usize)
+            >> objects: movie_objects
+            >> ((version, ext_addr, objects))
+    )?;
+    let ext = if ext_addr != 0 && (ext_addr as usize) < input.len() {
+        extension_data(&input[ext_addr as usize..])?.1
+    } else {
+        Vec::new()
+    };
+
+    Ok((
+        &[],
+        MovieObjects {
+            version: version.into(),
+            objects,
+            ext,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn header_tag() {
+        let data = [0x4d, 0x4f, 0x42, 0x4a, 0x30];
+        let sl = &data[..];
+        assert_eq!(super::header_tag(sl), Ok((&sl[4..], "MOBJ")));
+    }
+
+    #[test]
+    fn movie_object() {
+        // resume, menu call masked, a single PlayPL 800
+        let data = [
+            0xC0, 0x00, 0x00, 0x01, 0x22, 0x80, 0x00, 0x00, 0x00, 0x00, 0x03, 0x20, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        let (rest, object) = super::movie_object(&data[..]).unwrap();
+        assert!(rest.is_empty());
+        assert!(object.resume_intention_flag);
+        assert!(object.menu_call_mask);
+        assert!(!object.title_search_mask);
+        assert_eq!(object.commands.len(), 1);
+        assert_eq!(object.commands[0].opcode, 0x2280_0000);
+        assert_eq!(object.commands[0].dst, 800);
+    }
+}
@@ -0,0 +1,447 @@
+use super::parser::parse_movie_objects;
+use super::writer::write_movie_objects;
+use crate::types::ExtensionDataEntry;
+use crate::MplsError;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::io::Read;
+
+/// The movie objects of a disc, `BDMV/MovieObject.bdmv`.
+#[derive(Debug, Clone)]
+pub struct MovieObjects {
+    /// The file format version, e.g. "0200".
+    pub version: String,
+    /// The movie objects, referenced by their index from the index table and
+    /// from `JumpObject`/`CallObject` commands.
+    pub objects: Vec<MovieObject>,
+    pub ext: Vec<ExtensionDataEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MovieObject {
+    /// Whether the object may be resumed after a menu call.
+    pub resume_intention_flag: bool,
+    /// Whether the menu call user operation is disabled.
+    pub menu_call_mask: bool,
+    /// Whether the title search user operation is disabled.
+    pub title_search_mask: bool,
+    pub commands: Vec<Command>,
+}
+
+/// A single, still encoded, 12-byte HDMV navigation command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Command {
+    /// The operation code: operand count, command group, sub-group and
+    /// options.
+    pub opcode: u32,
+    /// The destination operand.
+    pub dst: u32,
+    /// The source operand.
+    pub src: u32,
+}
+
+/// An operand of a navigation command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Operand {
+    /// An immediate value.
+    Immediate(u32),
+    /// One of the 4096 general purpose registers.
+    Gpr(u16),
+    /// One of the 128 player status registers.
+    Psr(u8),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    /// Bit compare: true if all bits set in the second operand are also set
+    /// in the first.
+    Bc,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SetOp {
+    Move,
+    Swap,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    /// Random number between 1 and the source operand.
+    Rnd,
+    And,
+    Or,
+    Xor,
+    BitSet,
+    BitClear,
+    ShiftLeft,
+    ShiftRight,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SetSystemOp {
+    SetStream,
+    SetNvTimer,
+    SetButtonPage,
+    EnableButton,
+    DisableButton,
+    SetSecondaryStream,
+    PopUpMenuOff,
+    StillOn,
+    StillOff,
+    SetOutputMode,
+    SetStreamSs,
+}
+
+/// A decoded navigation command.
+///
+/// Operands that aren't used by an instruction are dropped. `SetSystem`
+/// instructions keep both of their operands as is, since their meaning
+/// depends on the operation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    Nop,
+    /// Continues with the command at the given index of the same object.
+    Goto(Operand),
+    /// Stops executing the current object.
+    Break,
+    JumpObject(Operand),
+    JumpTitle(Operand),
+    CallObject(Operand),
+    CallTitle(Operand),
+    /// Returns to the object that made the last `CallObject`/`CallTitle`.
+    Resume,
+    /// Plays the given playlist from its start.
+    PlayPl(Operand),
+    /// Plays the given playlist, starting at a play item.
+    PlayPlAtPlayItem(Operand, Operand),
+    /// Plays the given playlist, starting at a playlist mark.
+    PlayPlAtMark(Operand, Operand),
+    TerminatePl,
+    LinkPlayItem(Operand),
+    LinkMark(Operand),
+    /// Executes the next command only if the comparison holds.
+    Compare(CompareOp, Operand, Operand),
+    /// Sets the first (register) operand, e.g. `Set(Move, dst, src)` is
+    /// `dst = src`.
+    Set(SetOp, Operand, Operand),
+    SetSystem(SetSystemOp, Operand, Operand),
+    /// A command that couldn't be decoded.
+    Unknown(Command),
+}
+
+impl Command {
+    fn operand(&self, value: u32, immediate: bool) -> Operand {
+        if immediate {
+            Operand::Immediate(value)
+        } else if value & 0x8000_0000 != 0 {
+            Operand::Psr((value & 0x7F) as u8)
+        } else {
+            Operand::Gpr((value & 0xFFF) as u16)
+        }
+    }
+
+    /// Decodes the command.
+    pub fn instruction(&self) -> Instruction {
+        let [b0, b1, b2, b3] = self.opcode.to_be_bytes();
+        let group = (b0 >> 3) & 0x3;
+        let sub_group = b0 & 0x7;
+        let dst = self.operand(self.dst, b1 & 0x80 != 0);
+        let src = self.operand(self.src, b1 & 0x40 != 0);
+        let branch_opt = b1 & 0xF;
+        let cmp_opt = b2 & 0xF;
+        let set_opt = b3 & 0x1F;
+
+        use Instruction::*;
+        match (group, sub_group) {
+            // branch
+            (0, 0) => match branch_opt {
+                0 => Nop,
+                1 => Goto(dst),
+                2 => Break,
+                _ => Unknown(*self),
+            },
+            (0, 1) => match branch_opt {
+                0 => JumpObject(dst),
+                1 => JumpTitle(dst),
+                2 => CallObject(dst),
+                3 => CallTitle(dst),
+                4 => Resume,
+                _ => Unknown(*self),
+            },
+            (0, 2) => match branch_opt {
+                0 => PlayPl(dst),
+                1 => PlayPlAtPlayItem(dst, src),
+                2 => PlayPlAtMark(dst, src),
+                3 => TerminatePl,
+                4 => LinkPlayItem(dst),
+                5 => LinkMark(dst),
+                _ => Unknown(*self),
+            },
+            // compare
+            (1, _) => {
+                let op = match cmp_opt {
+                    1 => CompareOp::Bc,
+                    2 => CompareOp::Eq,
+                    3 => CompareOp::Ne,
+                    4 => CompareOp::Ge,
+                    5 => CompareOp::Gt,
+                    6 => CompareOp::Le,
+                    7 => CompareOp::Lt,
+                    _ => return Unknown(*self),
+                };
+                Compare(op, dst, src)
+            }
+            // set
+            (2, 0) => {
+                let op = match set_opt {
+                    1 => SetOp::Move,
+                    2 => SetOp::Swap,
+                    3 => SetOp::Add,
+                    4 => SetOp::Sub,
+                    5 => SetOp::Mul,
+                    6 => SetOp::Div,
+                    7 => SetOp::Mod,
+                    8 => SetOp::Rnd,
+                    9 => SetOp::And,
+                    10 => SetOp::Or,
+                    11 => SetOp::Xor,
+                    12 => SetOp::BitSet,
+                    13 => SetOp::BitClear,
+                    14 => SetOp::ShiftLeft,
+                    15 => SetOp::ShiftRight,
+                    _ => return Unknown(*self),
+                };
+                Set(op, dst, src)
+            }
+            (2, 1) => {
+                let op = match set_opt {
+                    1 => SetSystemOp::SetStream,
+                    2 => SetSystemOp::SetNvTimer,
+                    3 => SetSystemOp::SetButtonPage,
+                    4 => SetSystemOp::EnableButton,
+                    5 => SetSystemOp::DisableButton,
+                    6 => SetSystemOp::SetSecondaryStream,
+                    7 => SetSystemOp::PopUpMenuOff,
+                    8 => SetSystemOp::StillOn,
+                    9 => SetSystemOp::StillOff,
+                    10 => SetSystemOp::SetOutputMode,
+                    11 => SetSystemOp::SetStreamSs,
+                    _ => return Unknown(*self),
+                };
+                SetSystem(op, dst, src)
+            }
+            _ => Unknown(*self),
+        }
+    }
+}
+
+impl Operand {
+    // the encoded value and whether it's an immediate value
+    fn encode(&self) -> (u32, bool) {
+        match self {
+            Operand::Immediate(n) => (*n, true),
+            Operand::Gpr(n) => (*n as u32, false),
+            Operand::Psr(n) => (0x8000_0000 | *n as u32, false),
+        }
+    }
+}
+
+impl From<Instruction> for Command {
+    /// Encodes an instruction.
+    fn from(instruction: Instruction) -> Command {
+        use Instruction::*;
+        // (group, sub-group, option, operands)
+        let (group, sub_group, opt, operands): (u8, u8, u8, Vec<Operand>) = match instruction {
+            Nop => (0, 0, 0, vec![]),
+            Goto(a) => (0, 0, 1, vec![a]),
+            Break => (0, 0, 2, vec![]),
+            JumpObject(a) => (0, 1, 0, vec![a]),
+            JumpTitle(a) => (0, 1, 1, vec![a]),
+            CallObject(a) => (0, 1, 2, vec![a]),
+            CallTitle(a) => (0, 1, 3, vec![a]),
+            Resume => (0, 1, 4, vec![]),
+            PlayPl(a) => (0, 2, 0, vec![a]),
+            PlayPlAtPlayItem(a, b) => (0, 2, 1, vec![a, b]),
+            PlayPlAtMark(a, b) => (0, 2, 2, vec![a, b]),
+            TerminatePl => (0, 2, 3, vec![]),
+            LinkPlayItem(a) => (0, 2, 4, vec![a]),
+            LinkMark(a) => (0, 2, 5, vec![a]),
+            // the options are numbered in declaration order, starting at 1
+            Compare(op, a, b) => (1, 0, op as u8 + 1, vec![a, b]),
+            Set(op, a, b) => (2, 0, op as u8 + 1, vec![a, b]),
+            SetSystem(op, a, b) => (2, 1, op as u8 + 1, vec![a, b]),
+            Unknown(command) => return command,
+        };
+
+        let mut bytes = [
+            (operands.len() as u8) << 5 | group << 3 | sub_group,
+            0,
+            0,
+            0,
+        ];
+        match group {
+            0 => bytes[1] = opt,
+            1 => bytes[2] = opt,
+            _ => bytes[3] = opt,
+        }
+        let mut values = [0; 2];
+        for (i, operand) in operands.iter().enumerate() {
+            let (value, immediate) = operand.encode();
+            values[i] = value;
+            if immediate {
+                bytes[1] |= 0x80 >> i;
+            }
+        }
+        Command {
+            opcode: u32::from_be_bytes(bytes),
+            dst: values[0],
+            src: values[1],
+        }
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.instruction())
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Immediate(n) => write!(f, "{}", n),
+            Operand::Gpr(n) => write!(f, "GPR[{}]", n),
+            Operand::Psr(n) => write!(f, "PSR[{}]", n),
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Instruction::*;
+        match self {
+            Nop => write!(f, "Nop"),
+            Goto(a) => write!(f, "GoTo {}", a),
+            Break => write!(f, "Break"),
+            JumpObject(a) => write!(f, "JumpObject {}", a),
+            JumpTitle(a) => write!(f, "JumpTitle {}", a),
+            CallObject(a) => write!(f, "CallObject {}", a),
+            CallTitle(a) => write!(f, "CallTitle {}", a),
+            Resume => write!(f, "Resume"),
+            PlayPl(a) => write!(f, "PlayPL {}", a),
+            PlayPlAtPlayItem(a, b) => write!(f, "PlayPLatPI {}, {}", a, b),
+            PlayPlAtMark(a, b) => write!(f, "PlayPLatMK {}, {}", a, b),
+            TerminatePl => write!(f, "TerminatePL"),
+            LinkPlayItem(a) => write!(f, "LinkPI {}", a),
+            LinkMark(a) => write!(f, "LinkMK {}", a),
+            Compare(op, a, b) => write!(f, "{:?} {}, {}", op, a, b),
+            Set(op, a, b) => write!(f, "{:?} {}, {}", op, a, b),
+            SetSystem(op, a, b) => match (a, b) {
+                (Operand::Immediate(a), Operand::Immediate(b)) => {
+                    write!(f, "{:?} {:#010X}, {:#010X}", op, a, b)
+                }
+                _ => write!(f, "{:?} {}, {}", op, a, b),
+            },
+            Unknown(c) => write!(f, "Unknown {:08X} {:08X} {:08X}", c.opcode, c.dst, c.src),
+        }
+    }
+}
+
+impl MovieObject {
+    /// Lists the numbers of the playlists this object can play, in
+    /// ascending order.
+    ///
+    /// Besides playlists that are referenced directly, this includes the
+    /// playlists referenced through a general purpose register that the
+    /// object sets to an immediate value. Registers set by other objects
+    /// aren't taken into account; the [`vm`] module simulates those.
+    ///
+    /// [`vm`]: ../vm/index.html
+    pub fn playlists(&self) -> Vec<u16> {
+        let instructions: Vec<Instruction> =
+            self.commands.iter().map(Command::instruction).collect();
+        let mut playlists = Vec::new();
+        for instruction in instructions.iter() {
+            let playlist = match instruction {
+                Instruction::PlayPl(p)
+                | Instruction::PlayPlAtPlayItem(p, _)
+                | Instruction::PlayPlAtMark(p, _) => p,
+                _ => continue,
+            };
+            match playlist {
+                Operand::Immediate(n) => playlists.push(*n),
+                Operand::Gpr(_) => {
+                    for i in instructions.iter() {
+                        if let Instruction::Set(SetOp::Move, dst, Operand::Immediate(n)) = i {
+                            if dst == playlist {
+                                playlists.push(*n);
+                            }
+                        }
+                    }
+                }
+                Operand::Psr(_) => {}
+            }
+        }
+        let mut playlists: Vec<u16> = playlists
+            .into_iter()
+            .filter_map(|n| u16::try_from(n).ok())
+            .collect();
+        playlists.sort_unstable();
+        playlists.dedup();
+        playlists
+    }
+}
+
+impl MovieObjects {
+    /// Attempts to parse the movie objects from the given reader.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::fs::File;
+    /// use mpls::MovieObjects;
+    ///
+    /// let file = File::open("MovieObject.bdmv")?;
+    /// let mobj = MovieObjects::from(&file).expect("failed to parse MovieObject.bdmv.");
+    /// for (i, object) in mobj.objects.iter().enumerate() {
+    ///     println!("movie object {}:", i);
+    ///     for command in object.commands.iter() {
+    ///         println!("    {}", command);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from<R: Read>(mut reader: R) -> Result<MovieObjects, MplsError> {
+        let bytes = {
+            let mut buffer = Vec::new();
+            reader.read_to_end(&mut buffer)?;
+            buffer
+        };
+
+        parse_movie_objects(&bytes)
+            .map_err(|_| MplsError::MovieObjectParseError)
+            .map(|(_, m)| m)
+    }
+
+    /// Serializes the movie objects into the `MovieObject.bdmv` binary
+    /// format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        write_movie_objects(self)
+    }
+
+    /// Lists the numbers of the playlists each movie object can play, see
+    /// [`MovieObject::playlists`].
+    ///
+    /// [`MovieObject::playlists`]: struct.MovieObject.html#method.playlists
+    pub fn playlists(&self) -> Vec<Vec<u16>> {
+        self.objects.iter().map(MovieObject::playlists).collect()
+    }
+}
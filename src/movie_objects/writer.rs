@@ -0,0 +1,40 @@
+use super::types::{MovieObject, MovieObjects};
+use crate::writer::{extension_data, str_len, u32_len};
+
+fn movie_object(buf: &mut Vec<u8>, object: &MovieObject) {
+    let flags = ((object.resume_intention_flag as u16) << 15)
+        | ((object.menu_call_mask as u16) << 14)
+        | ((object.title_search_mask as u16) << 13);
+    buf.extend_from_slice(&flags.to_be_bytes());
+    buf.extend_from_slice(&(object.commands.len() as u16).to_be_bytes());
+    for command in object.commands.iter() {
+        buf.extend_from_slice(&command.opcode.to_be_bytes());
+        buf.extend_from_slice(&command.dst.to_be_bytes());
+        buf.extend_from_slice(&command.src.to_be_bytes());
+    }
+}
+
+pub fn write_movie_objects(objects: &MovieObjects) -> Vec<u8> {
+    let mut sections = Vec::new();
+    let mut body = vec![0; 4];
+    body.extend_from_slice(&(objects.objects.len() as u16).to_be_bytes());
+    for object in objects.objects.iter() {
+        movie_object(&mut body, object);
+    }
+    u32_len(&mut sections, body);
+    let ext_addr = if objects.ext.is_empty() {
+        0
+    } else {
+        let addr = 40 + sections.len() as u32;
+        extension_data(&mut sections, &objects.ext);
+        addr
+    };
+
+    let mut buf = Vec::with_capacity(40 + sections.len());
+    buf.extend_from_slice(b"MOBJ");
+    str_len(&mut buf, &objects.version, 4);
+    buf.extend_from_slice(&ext_addr.to_be_bytes());
+    buf.extend_from_slice(&[0; 28]);
+    buf.extend(sections);
+    buf
+}
@@ -0,0 +1,251 @@
+//! Obfuscated (decoy) playlist detection.
+//!
+//! Some discs hide the main feature among many playlists that play the very
+//! same clips, only in different orders. Only one of these orders makes
+//! sense: in the real playlist, each clip continues where the previous one
+//! ended.
+//!
+//! [`find_decoy_groups`] groups playlists that play the same clips (as a
+//! multiset, i.e. regardless of order) and checks each playlist's order in two
+//! ways: whether the clip numbers are increasing, which is how most discs
+//! number their clips but isn't a reliable sign on its own, and, if the clip
+//! information files are available, whether each play item continues where
+//! the previous one ended on the clips' timelines.
+//!
+//! [`find_decoy_groups`]: fn.find_decoy_groups.html
+
+use crate::clpi::Clpi;
+use crate::disc::Disc;
+use crate::types::{Clip, Mpls, TimeStamp};
+use std::collections::BTreeMap;
+
+/// The largest gap between one play item's out time and the next one's in
+/// time for the two to count as continuous.
+pub const STC_CONTINUITY_TOLERANCE: TimeStamp = TimeStamp(45_000);
+
+/// A group of playlists that all play the same clips.
+#[derive(Debug, Clone)]
+pub struct DecoyGroup {
+    /// The names of the clips, sorted, including repetitions.
+    pub clips: Vec<String>,
+    /// The playlists of the group, ordered by playlist number.
+    pub playlists: Vec<PlaylistOrder>,
+}
+
+/// How a playlist of a [`DecoyGroup`] orders its clips.
+///
+/// [`DecoyGroup`]: struct.DecoyGroup.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistOrder {
+    /// The playlist number, e.g. 800 for `00800.mpls`.
+    pub playlist: u16,
+    /// The indices of the segments whose clip number is lower than that of
+    /// the previous segment.
+    pub order_breaks: Vec<usize>,
+    /// The indices of the segments that don't continue the previous
+    /// segment's clip timeline, or `None` if the clip information of any of
+    /// the clips is missing.
+    pub discontinuities: Option<Vec<usize>>,
+}
+
+impl PlaylistOrder {
+    /// Returns whether the clip numbers increase throughout the playlist.
+    pub fn is_monotonic(&self) -> bool {
+        self.order_breaks.is_empty()
+    }
+
+    /// Returns whether the playlist's clip order is consistent with the
+    /// clips' timelines, or `None` if that's unknown.
+    pub fn is_consistent(&self) -> Option<bool> {
+        self.discontinuities.as_ref().map(Vec::is_empty)
+    }
+}
+
+impl DecoyGroup {
+    /// Gets the playlists whose order is consistent with the clip timelines.
+    ///
+    /// If clip information isn't available, the playlists with increasing
+    /// clip numbers are returned instead.
+    pub fn likely_originals(&self) -> Vec<u16> {
+        let consistent: Vec<u16> = self
+            .playlists
+            .iter()
+            .filter(|p| p.is_consistent() == Some(true))
+            .map(|p| p.playlist)
+            .collect();
+        if !consistent.is_empty() || self.playlists.iter().any(|p| p.is_consistent().is_some()) {
+            return consistent;
+        }
+        self.playlists
+            .iter()
+            .filter(|p| p.is_monotonic())
+            .map(|p| p.playlist)
+            .collect()
+    }
+}
+
+// a play item of the playlist's first angle
+struct Segment<'a> {
+    clip: &'a Clip,
+    in_time: TimeStamp,
+    out_time: TimeStamp,
+}
+
+// the segments of the playlist's first angle
+fn segments(mpls: &Mpls) -> Vec<Segment<'_>> {
+    match mpls.angles().first() {
+        Some(angle) => mpls
+            .play_list
+            .play_items
+            .iter()
+            .map(|p| Segment {
+                clip: p.clip_for_angle(angle),
+                in_time: p.in_time,
+                out_time: p.out_time,
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn order_breaks(segments: &[Segment]) -> Vec<usize> {
+    let numbers: Vec<Option<u32>> = segments
+        .iter()
+        .map(|s| s.clip.file_name.parse().ok())
+        .collect();
+    (1..segments.len())
+        .filter(|i| match (numbers[i - 1], numbers[*i]) {
+            (Some(a), Some(b)) => b < a,
+            _ => segments[*i].clip.file_name < segments[i - 1].clip.file_name,
+        })
+        .collect()
+}
+
+fn is_near(a: TimeStamp, b: TimeStamp) -> bool {
+    a.0.max(b.0) - a.0.min(b.0) <= STC_CONTINUITY_TOLERANCE.0
+}
+
+// whether segment `b` continues where segment `a` ends: either `b`'s in time
+// is `a`'s out time, or `a` plays its clip's STC sequence to the end, `b`
+// plays its clip's from the start, and `a`'s clip names `b`'s as the clip
+// that follows it on the arrival time clock
+fn is_continuous(a: &Segment, a_clpi: &Clpi, b: &Segment, b_clpi: &Clpi) -> bool {
+    let stc_sequence = |clip: &Clip, clpi: &Clpi| {
+        clpi.stc_sequences()
+            .get(clip.stc_id as usize)
+            .map(|s| (s.presentation_start_time, s.presentation_end_time))
+    };
+    let (a_stc, b_stc) = match (stc_sequence(a.clip, a_clpi), stc_sequence(b.clip, b_clpi)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return false,
+    };
+    if is_near(a.out_time, b.in_time) {
+        return true;
+    }
+    is_near(a.out_time, a_stc.1)
+        && is_near(b.in_time, b_stc.0)
+        && a_clpi
+            .clip_info
+            .atc_deltas
+            .iter()
+            .any(|d| d.following_clip_file_name == b.clip.file_name)
+}
+
+fn discontinuities(segments: &[Segment], clip_info: &BTreeMap<String, Clpi>) -> Option<Vec<usize>> {
+    let clpis: Vec<&Clpi> = segments
+        .iter()
+        .map(|s| clip_info.get(&s.clip.file_name))
+        .collect::<Option<_>>()?;
+    Some(
+        (1..segments.len())
+            .filter(|i| !is_continuous(&segments[i - 1], clpis[i - 1], &segments[*i], clpis[*i]))
+            .collect(),
+    )
+}
+
+/// Groups playlists that play the same clips in their first angle and
+/// analyzes their clip orders.
+///
+/// `clip_info` maps clip names (e.g. "00055") to their clip information; it
+/// may be empty. Only groups of at least two playlists are returned, ordered
+/// by their first playlist number.
+pub fn find_decoy_groups(
+    playlists: &BTreeMap<u16, Mpls>,
+    clip_info: &BTreeMap<String, Clpi>,
+) -> Vec<DecoyGroup> {
+    let mut groups: BTreeMap<Vec<String>, Vec<PlaylistOrder>> = BTreeMap::new();
+    for (number, mpls) in playlists.iter() {
+        let segments = segments(mpls);
+        if segments.is_empty() {
+            continue;
+        }
+        let mut clips: Vec<String> = segments.iter().map(|s| s.clip.file_name.clone()).collect();
+        clips.sort();
+        groups.entry(clips).or_default().push(PlaylistOrder {
+            playlist: *number,
+            order_breaks: order_breaks(&segments),
+            discontinuities: discontinuities(&segments, clip_info),
+        });
+    }
+
+    let mut groups: Vec<DecoyGroup> = groups
+        .into_iter()
+        .filter(|(_, playlists)| playlists.len() > 1)
+        .map(|(clips, playlists)| DecoyGroup { clips, playlists })
+        .collect();
+    groups.sort_by_key(|g| g.playlists[0].playlist);
+    groups
+}
+
+impl Disc {
+    /// Finds groups of playlists that play the same clips, see
+    /// [`find_decoy_groups`].
+    ///
+    /// The clip information files of the grouped playlists are read from the
+    /// disc; clips whose clip information can't be read are treated as
+    /// missing.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), mpls::MplsError> {
+    /// use mpls::Disc;
+    ///
+    /// let disc = Disc::open("/mnt/bluray")?;
+    /// for group in disc.decoy_groups() {
+    ///     println!(
+    ///         "{} playlists play the same {} clips, likely originals: {:?}",
+    ///         group.playlists.len(),
+    ///         group.clips.len(),
+    ///         group.likely_originals()
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`find_decoy_groups`]: ../obfuscation/fn.find_decoy_groups.html
+    pub fn decoy_groups(&self) -> Vec<DecoyGroup> {
+        let groups = find_decoy_groups(&self.playlists, &BTreeMap::new());
+        if groups.is_empty() {
+            return groups;
+        }
+
+        let mut clip_info = BTreeMap::new();
+        for group in groups.iter() {
+            for name in group.clips.iter() {
+                if clip_info.contains_key(name) {
+                    continue;
+                }
+                let clip = Clip {
+                    file_name: name.clone(),
+                    codec_id: "M2TS".into(),
+                    stc_id: 0,
+                };
+                if let Ok(clpi) = self.clip_info(&clip) {
+                    clip_info.insert(name.clone(), clpi);
+                }
+            }
+        }
+        find_decoy_groups(&self.playlists, &clip_info)
+    }
+}
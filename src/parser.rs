@@ -1,8 +1,9 @@
 use super::types;
+use crate::MplsError;
 use nom::{
     branch::alt,
     bytes::complete::{tag, take},
-    combinator::map,
+    combinator::{map, map_res},
     cond, count, do_parse,
     error::ErrorKind,
     map,
@@ -20,18 +21,18 @@ use types::{
     SubPathRef, SubPlayItem, TimeStamp, VideoFormat,
 };
 
-fn str_len(len: usize, input: &[u8]) -> IResult<&[u8], &str> {
+pub(crate) fn str_len(len: usize, input: &[u8]) -> IResult<&[u8], &str> {
     let value = take(len);
-    map(value, |v| std::str::from_utf8(v).unwrap())(input)
+    map_res(value, std::str::from_utf8)(input)
 }
 
-fn str_len_owned(len: usize, input: &[u8]) -> IResult<&[u8], String> {
+pub(crate) fn str_len_owned(len: usize, input: &[u8]) -> IResult<&[u8], String> {
     let (input, s) = str_len(len, input)?;
     Ok((input, s.into()))
 }
 
 // matches the ASCII/UTF-8 string "MPLS"
-fn header_tag(input: &[u8]) -> IResult<&[u8], &str> {
+pub(crate) fn header_tag(input: &[u8]) -> IResult<&[u8], &str> {
     let (rest, s) = str_len(4, input)?;
     if s == "MPLS" {
         Ok((rest, s))
@@ -40,33 +41,44 @@ fn header_tag(input: &[u8]) -> IResult<&[u8], &str> {
     }
 }
 
-fn version(input: &[u8]) -> IResult<&[u8], &str> {
+pub(crate) fn version(input: &[u8]) -> IResult<&[u8], &str> {
     str_len(4, input)
 }
 
-fn addr(input: &[u8]) -> IResult<&[u8], u32> {
+pub(crate) fn addr(input: &[u8]) -> IResult<&[u8], u32> {
     let offset = take(4usize);
     map(offset, |o: &[u8]| u32::from_be_bytes(o.try_into().unwrap()))(input)
 }
 
-fn clip_file_name(input: &[u8]) -> IResult<&[u8], &str> {
+// the data of a section with a 32-bit length
+pub(crate) fn section_data(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    do_parse!(input, len: be_u32 >> data: take!(len as usize) >> (data))
+}
+
+// maps any parser error to an MPLS parse error
+pub(crate) fn parse_error<T>(_: T) -> MplsError {
+    MplsError::ParseError
+}
+
+pub(crate) fn clip_file_name(input: &[u8]) -> IResult<&[u8], &str> {
     str_len(5, input)
 }
 
-fn clip_codec_id(input: &[u8]) -> IResult<&[u8], &str> {
+pub(crate) fn clip_codec_id(input: &[u8]) -> IResult<&[u8], &str> {
     str_len(4, input)
 }
 
-fn is_multi_angle(input: &[u8]) -> IResult<&[u8], bool> {
+fn multi_angle_connection(input: &[u8]) -> IResult<&[u8], (bool, u8)> {
     let (input, b) = be_u16(input)?;
-    // 0000 0000 000X .... <-- connection_condition
-    // |-reserved -|^---- the bit we want
+    // 0000 0000 000X CCCC <-- connection_condition
+    // |-reserved -|^---- is_multi_angle
     let is_multi_angle = ((b & 0x1F) >> 4) == 1;
-    Ok((input, is_multi_angle))
+    let connection_condition = (b & 0x0F) as u8;
+    Ok((input, (is_multi_angle, connection_condition)))
 }
 
-fn time_stamp(input: &[u8]) -> IResult<&[u8], TimeStamp> {
-    map(be_u32, |t| TimeStamp(t))(input)
+pub(crate) fn time_stamp(input: &[u8]) -> IResult<&[u8], TimeStamp> {
+    map(be_u32, TimeStamp)(input)
 }
 
 fn clip_with_clock_ref(input: &[u8]) -> IResult<&[u8], Clip> {
@@ -82,10 +94,11 @@ fn clip(input: &[u8], with_ref_to_stcid: bool) -> IResult<&[u8], Clip> {
         input,
         f: clip_file_name
             >> c: clip_codec_id
-            >> cond!(with_ref_to_stcid, take!(1usize))
+            >> stc_id: map!(cond!(with_ref_to_stcid, be_u8), |s| s.unwrap_or(0))
             >> (Clip {
                 file_name: f.into(),
-                codec_id: c.into()
+                codec_id: c.into(),
+                stc_id,
             })
     )
 }
@@ -113,7 +126,7 @@ fn stream_entry(input: &[u8]) -> IResult<&[u8], StreamEntry> {
     fn parser(input: &[u8]) -> IResult<&[u8], StreamEntry> {
         let (input, stream_type) = stream_type(input)?;
         let (input, refs) = match stream_type {
-            0x1 => map(stream_pid, |s| StreamEntryRef::PlayItem(s))(input),
+            0x1 => map(stream_pid, StreamEntryRef::PlayItem)(input),
             0x2 => {
                 let (input, sub_path_ref) = sub_path_id(input)?;
                 let (input, sub_clip_ref) = sub_clip_id(input)?;
@@ -140,7 +153,7 @@ fn stream_entry(input: &[u8]) -> IResult<&[u8], StreamEntry> {
     length_value(be_u8, parser)(input)
 }
 
-fn stream_attrs(input: &[u8]) -> IResult<&[u8], StreamAttributes> {
+pub(crate) fn stream_attrs(input: &[u8]) -> IResult<&[u8], StreamAttributes> {
     fn video_format(input: &[u8]) -> IResult<&[u8], (VideoFormat, FrameRate)> {
         map(be_u8, |n| {
             let video_format = match (n & 0xF0) >> 4 {
@@ -258,7 +271,7 @@ fn stream_attrs(input: &[u8]) -> IResult<&[u8], StreamAttributes> {
             }
             0x90 | 0x91 => {
                 // Graphics (PGS)
-                map(lang_code, |l| StreamType::Graphics(l))(input)
+                map(lang_code, StreamType::Graphics)(input)
             }
             0x92 => {
                 // Text
@@ -330,7 +343,8 @@ fn stream_number_table(input: &[u8]) -> IResult<&[u8], StreamNumberTable> {
 
 fn sub_play_item(input: &[u8]) -> IResult<&[u8], SubPlayItem> {
     fn multi_clip_entries(input: &[u8]) -> IResult<&[u8], Vec<Clip>> {
-        let (input, num_entries) = be_u8(input)?;
+        // the main clip counts as an entry, too, so we want to read (n - 1) clips
+        let (input, num_entries) = map(be_u8, |n| n.saturating_sub(1))(input)?;
         let (input, _) = take(1usize)(input)?;
         count(clip_with_clock_ref, num_entries as usize)(input)
     }
@@ -338,16 +352,16 @@ fn sub_play_item(input: &[u8]) -> IResult<&[u8], SubPlayItem> {
         do_parse!(
             input,
             clip: play_item_clip >>
-            is_multi_clip: map!(be_u32, |n| (n & 0x1) == 1) >>
-            // reftostcid
-            take!(1usize) >>
+            flags: be_u32 >>
+            stc_id: be_u8 >>
             in_time: time_stamp >>
             out_time: time_stamp >>
             sync_play_item_id: be_u16 >>
             sync_start_pts: be_u32 >>
-            multi_clip_entries: map!(cond!(is_multi_clip, multi_clip_entries), |c| c.unwrap_or(Vec::new())) >>
+            multi_clip_entries: map!(cond!((flags & 0x1) == 1, multi_clip_entries), |c| c.unwrap_or(Vec::new())) >>
             (SubPlayItem {
-                clip,
+                clip: Clip { stc_id, ..clip },
+                connection_condition: ((flags >> 1) & 0x0F) as u8,
                 in_time,
                 out_time,
                 sync_play_item_id,
@@ -360,7 +374,7 @@ fn sub_play_item(input: &[u8]) -> IResult<&[u8], SubPlayItem> {
     length_value(be_u16, parser)(input)
 }
 
-fn sub_path(input: &[u8]) -> IResult<&[u8], SubPath> {
+pub(crate) fn sub_path(input: &[u8]) -> IResult<&[u8], SubPath> {
     fn parser(input: &[u8]) -> IResult<&[u8], SubPath> {
         do_parse!(
             input,
@@ -397,29 +411,31 @@ fn play_item_angles(input: &[u8]) -> IResult<&[u8], (AngleInfo, Vec<Clip>)> {
     Ok((input, (angle_info, clips)))
 }
 
-fn play_item(input: &[u8]) -> IResult<&[u8], PlayItem> {
+pub(crate) fn play_item(input: &[u8]) -> IResult<&[u8], PlayItem> {
     fn parser(input: &[u8]) -> IResult<&[u8], PlayItem> {
         do_parse!(
             input,
             clip: play_item_clip >>
-            is_multi_angle: is_multi_angle >>
-            // RefToSTCID
-            take!(1usize) >>
+            multi_angle_connection: multi_angle_connection >>
+            stc_id: be_u8 >>
             in_time: time_stamp >>
             out_time: time_stamp >>
             user_opt_mask: be_u64 >>
-            // PlayItemRandomAccessFlag
-            take!(1usize) >>
-            // StillMode/StillTime
-            take!(3usize) >>
-            angle_data: map!(cond!(is_multi_angle, play_item_angles),
+            random_access_flag: map!(be_u8, |n| (n >> 7) == 1) >>
+            still_mode: be_u8 >>
+            still_time: be_u16 >>
+            angle_data: map!(cond!(multi_angle_connection.0, play_item_angles),
                 |o| o.map(|(a, b)| (Some(a), b)).unwrap_or((None, Vec::new()))) >>
             stream_number_table: stream_number_table >>
             (PlayItem {
-                clip,
+                clip: Clip { stc_id, ..clip },
+                connection_condition: multi_angle_connection.1,
                 in_time,
                 out_time,
                 user_opt_mask,
+                random_access_flag,
+                still_mode,
+                still_time,
                 angle_info: angle_data.0,
                 angles: angle_data.1,
                 stream_number_table,
@@ -449,15 +465,7 @@ fn play_list(input: &[u8]) -> IResult<&[u8], PlayList> {
     length_value(be_u32, parser)(input)
 }
 
-fn app_info_play_list(input: &[u8]) -> IResult<&[u8], AppInfoPlayList> {
-    fn playback_count(input: &[u8]) -> IResult<&[u8], Option<u16>> {
-        let (input, v) = be_u16(input)?;
-        let res = match v {
-            0x2 | 0x3 => Some(v),
-            _ => None,
-        };
-        Ok((input, res))
-    }
+pub(crate) fn app_info_play_list(input: &[u8]) -> IResult<&[u8], AppInfoPlayList> {
     fn playback_type(input: &[u8]) -> IResult<&[u8], PlaybackType> {
         map(be_u8, |n| match n {
             0x1 => PlaybackType::Standard,
@@ -471,7 +479,10 @@ fn app_info_play_list(input: &[u8]) -> IResult<&[u8], AppInfoPlayList> {
             input,
             take!(1usize)
                 >> playback_type: playback_type
-                >> playback_count: playback_count
+                >> playback_count: map!(be_u16, |n| match playback_type {
+                    PlaybackType::Random | PlaybackType::Shuffle => Some(n),
+                    _ => None,
+                })
                 >> user_opt_mask: be_u64
                 >> flags: be_u16
                 >> (AppInfoPlayList {
@@ -486,7 +497,7 @@ fn app_info_play_list(input: &[u8]) -> IResult<&[u8], AppInfoPlayList> {
     length_value(be_u32, parser)(input)
 }
 
-fn play_list_mark(input: &[u8]) -> IResult<&[u8], Vec<PlayListMark>> {
+pub(crate) fn play_list_mark(input: &[u8]) -> IResult<&[u8], Vec<PlayListMark>> {
     fn mark_type(input: &[u8]) -> IResult<&[u8], MarkType> {
         map(be_u8, |n| match n {
             0x1 => MarkType::EntryPoint,
@@ -499,15 +510,15 @@ fn play_list_mark(input: &[u8]) -> IResult<&[u8], Vec<PlayListMark>> {
             input,
             be_u8 >>
             mark_type: mark_type >>
-            play_item: map!(be_u16, |n| PlayItemRef(n)) >>
+            play_item: map!(be_u16, PlayItemRef) >>
             ts: time_stamp >>
-            // EntryESPID, meaning unknown
-            take!(2usize) >>
+            entry_es_pid: be_u16 >>
             duration: map!(time_stamp, |t| if t.0 == 0 { None } else { Some(t) }) >>
             (PlayListMark {
                 mark_type,
                 play_item,
                 time_stamp: ts,
+                entry_es_pid,
                 duration
             })
         )
@@ -526,7 +537,7 @@ struct ExtEntryHeader {
     data_len: u32,
 }
 
-fn extension_data(input: &[u8]) -> IResult<&[u8], Vec<ExtensionDataEntry>> {
+pub(crate) fn extension_data(input: &[u8]) -> IResult<&[u8], Vec<ExtensionDataEntry>> {
     fn ext_data_entry(input: &[u8]) -> IResult<&[u8], ExtEntryHeader> {
         do_parse!(
             input,
@@ -572,7 +583,7 @@ pub fn parse_mpls(input: &[u8]) -> IResult<&[u8], Mpls> {
     do_parse!(
         input,
         header_tag
-            >> version
+            >> version: version
             >> count!(addr, 2)
             >> has_ext_data: map!(addr, |a| a != 0)
             >> take!(20usize) // reserved
@@ -582,6 +593,7 @@ pub fn parse_mpls(input: &[u8]) -> IResult<&[u8], Mpls> {
             >> ext: map!(cond!(has_ext_data, extension_data), |e| e
                 .unwrap_or(Vec::new()))
             >> (Mpls {
+                version: version.into(),
                 app_info_play_list,
                 play_list,
                 marks,
@@ -605,7 +617,7 @@ mod tests {
         let sl = &data[..];
         assert_eq!(
             super::header_tag(sl),
-            Err(nom::Err::Error((&sl[..], nom::error::ErrorKind::Tag)))
+            Err(nom::Err::Error((sl, nom::error::ErrorKind::Tag)))
         );
     }
 
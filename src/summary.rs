@@ -0,0 +1,195 @@
+//! A fast, summary-only playlist parser.
+//!
+//! Listings of many playlists usually only need a few numbers per playlist.
+//! [`MplsSummary::from`] reads those without parsing the whole playlist: it
+//! uses the section start addresses in the header to jump straight to the
+//! play list, only reads the fixed-size fields of each play item and skips
+//! the stream attributes, sub paths, marks and extension data entirely.
+//!
+//! [`MplsSummary::from`]: struct.MplsSummary.html#method.from
+
+use crate::parser::{
+    addr, clip_file_name, header_tag, parse_error, section_data, time_stamp, version,
+};
+use crate::types::TimeStamp;
+use crate::MplsError;
+use nom::{
+    bytes::complete::take,
+    do_parse,
+    multi::length_value,
+    number::complete::{be_u16, be_u8},
+    take, IResult,
+};
+use std::io::Read;
+
+/// The number of streams of each kind in a play item's stream number table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct StreamCounts {
+    pub primary_video: u8,
+    pub primary_audio: u8,
+    pub primary_pgs: u8,
+    pub primary_igs: u8,
+    pub secondary_audio: u8,
+    pub secondary_video: u8,
+    pub secondary_pgs: u8,
+    pub dolby_vision: u8,
+}
+
+/// The key facts of a movie playlist, see [`MplsSummary::from`].
+///
+/// [`MplsSummary::from`]: #method.from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MplsSummary {
+    /// The file format version, e.g. "0200" or "0300".
+    pub version: String,
+    /// The playlist's duration, see [`Mpls::duration`].
+    ///
+    /// [`Mpls::duration`]: ../types/struct.Mpls.html#method.duration
+    pub duration: TimeStamp,
+    /// The number of play items.
+    pub play_items: usize,
+    /// The number of sub paths.
+    pub sub_paths: usize,
+    /// The number of angles, i.e. 1 for single-angle playlists.
+    pub angles: usize,
+    /// The clip name (e.g. "00055") of each play item, for the first angle.
+    pub clips: Vec<String>,
+    /// The stream counts of the first play item, or all zeros if the
+    /// playlist has no play items.
+    pub streams: StreamCounts,
+}
+
+// the fields of a play item the summary needs
+struct PlayItemSummary<'a> {
+    clip: &'a str,
+    duration: TimeStamp,
+    angles: usize,
+    streams: StreamCounts,
+}
+
+fn stream_counts(input: &[u8]) -> IResult<&[u8], StreamCounts> {
+    do_parse!(
+        input,
+        take!(2usize)
+            >> primary_video: be_u8
+            >> primary_audio: be_u8
+            >> primary_pgs: be_u8
+            >> primary_igs: be_u8
+            >> secondary_audio: be_u8
+            >> secondary_video: be_u8
+            >> secondary_pgs: be_u8
+            >> dolby_vision: be_u8
+            >> (StreamCounts {
+                primary_video,
+                primary_audio,
+                primary_pgs,
+                primary_igs,
+                secondary_audio,
+                secondary_video,
+                secondary_pgs,
+                dolby_vision,
+            })
+    )
+}
+
+fn play_item(input: &[u8]) -> IResult<&[u8], PlayItemSummary<'_>> {
+    fn parser(input: &[u8]) -> IResult<&[u8], PlayItemSummary<'_>> {
+        let (input, (clip, is_multi_angle, in_time, out_time)) = do_parse!(
+            input,
+            clip: clip_file_name
+                >> take!(4usize) // codec id
+                >> flags: be_u16
+                >> take!(1usize) // stc id
+                >> in_time: time_stamp
+                >> out_time: time_stamp
+                >> take!(12usize) // user operation mask, flags, still mode
+                >> ((clip, (flags >> 4) & 0x1 == 1, in_time, out_time))
+        )?;
+        let (input, angles) = if is_multi_angle {
+            // each additional angle is a clip reference of 10 bytes
+            let (input, angles) = be_u8(input)?;
+            let (input, _) = take(1 + 10 * (angles.saturating_sub(1) as usize))(input)?;
+            (input, angles.max(1) as usize)
+        } else {
+            (input, 1)
+        };
+        // only the header of the stream number table, not its streams
+        let (input, streams) = length_value(be_u16, stream_counts)(input)?;
+        Ok((
+            input,
+            PlayItemSummary {
+                clip,
+                duration: TimeStamp(out_time.0.saturating_sub(in_time.0)),
+                angles,
+                streams,
+            },
+        ))
+    }
+
+    length_value(be_u16, parser)(input)
+}
+
+fn summary(input: &[u8]) -> IResult<&[u8], MplsSummary> {
+    let (_, (version, play_list_start)) = do_parse!(
+        input,
+        header_tag >> version: version >> start: addr >> ((version, start))
+    )?;
+    let (section, _) = take(play_list_start as usize)(input)?;
+    let (_, play_list) = section_data(section)?;
+    let (mut rest, (n_play_items, sub_paths)) = do_parse!(
+        play_list,
+        take!(2usize) >> n: be_u16 >> s: be_u16 >> ((n, s))
+    )?;
+
+    let mut summary = MplsSummary {
+        version: version.into(),
+        duration: TimeStamp(0),
+        play_items: n_play_items as usize,
+        sub_paths: sub_paths as usize,
+        angles: 1,
+        clips: Vec::with_capacity(n_play_items as usize),
+        streams: StreamCounts::default(),
+    };
+    let mut durations = Vec::with_capacity(n_play_items as usize);
+    for i in 0..n_play_items {
+        let (r, item) = play_item(rest)?;
+        rest = r;
+        if i == 0 {
+            summary.streams = item.streams;
+        }
+        durations.push(item.duration);
+        summary.angles = summary.angles.max(item.angles);
+        summary.clips.push(item.clip.into());
+    }
+    summary.duration = TimeStamp::saturating_sum(durations);
+    Ok((&[], summary))
+}
+
+impl MplsSummary {
+    /// Reads the summary of a movie playlist from the given reader.
+    ///
+    /// This only validates the parts of the playlist that it reads, so a
+    /// playlist that [`Mpls::from`] rejects may still be summarized.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::summary::MplsSummary;
+    ///
+    /// let bytes = include_bytes!("../assets/simple.mpls");
+    /// let summary = MplsSummary::from(&bytes[..]).unwrap();
+    /// assert_eq!(summary.play_items, 3);
+    /// assert_eq!(summary.clips, &["00055", "00059", "00061"]);
+    /// println!("{:.0}s", summary.duration.seconds());
+    /// ```
+    ///
+    /// [`Mpls::from`]: ../types/struct.Mpls.html#method.from
+    pub fn from<R: Read>(mut reader: R) -> Result<MplsSummary, MplsError> {
+        let bytes = {
+            let mut buffer = Vec::new();
+            reader.read_to_end(&mut buffer)?;
+            buffer
+        };
+
+        summary(&bytes).map_err(parse_error).map(|(_, s)| s)
+    }
+}
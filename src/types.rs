@@ -1,8 +1,10 @@
 use crate::parser::parse_mpls;
+use crate::writer::write_mpls;
 use crate::MplsError;
 use std::{
     fmt::{Debug, Display},
-    io::Read,
+    io::{Read, Write},
+    ops::Range,
 };
 
 /// The movie playlist.
@@ -12,6 +14,8 @@ use std::{
 /// [crate-level docs]: ../index.html
 #[derive(Debug, Clone)]
 pub struct Mpls {
+    /// The file format version, e.g. "0200" or "0300".
+    pub version: String,
     pub app_info_play_list: AppInfoPlayList,
     pub play_list: PlayList,
     pub marks: Vec<PlayListMark>,
@@ -35,7 +39,7 @@ pub struct Angle<'mpls> {
     mpls: &'mpls Mpls,
 }
 
-impl<'a> Display for Angle<'_> {
+impl Display for Angle<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.index)
     }
@@ -68,6 +72,38 @@ impl Mpls {
             .map(|(_, m)| m)
     }
 
+    /// Serializes this movie playlist into the MPLS binary format.
+    ///
+    /// The section start addresses in the header are recomputed, so the
+    /// playlist can be freely modified (e.g. its [`marks`]) before writing it.
+    /// Fails with an [`InvalidInput`] I/O error if a list is longer than the
+    /// format can store, e.g. if there are more than 65535 marks.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::Mpls;
+    ///
+    /// let bytes = include_bytes!("../assets/simple.mpls");
+    /// let mpls = Mpls::from(&bytes[..]).unwrap();
+    /// assert_eq!(&mpls.to_bytes().unwrap()[..], &bytes[..]);
+    /// ```
+    ///
+    /// [`marks`]: #structfield.marks
+    /// [`InvalidInput`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MplsError> {
+        write_mpls(self)
+    }
+
+    /// Serializes this movie playlist into the given writer.
+    ///
+    /// See [`to_bytes`] for details.
+    ///
+    /// [`to_bytes`]: #method.to_bytes
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), MplsError> {
+        writer.write_all(&self.to_bytes()?)?;
+        Ok(())
+    }
+
     /// Gets all of the movie's angles.
     ///
     /// This method will always return at least one element, since it counts the
@@ -102,7 +138,7 @@ impl Mpls {
     /// # let angles = mpls.angles();
     /// assert_eq!(angles.len(), 1);
     /// ```
-    pub fn angles(&self) -> Vec<Angle> {
+    pub fn angles(&self) -> Vec<Angle<'_>> {
         self.play_list
             .play_items
             .iter()
@@ -112,15 +148,259 @@ impl Mpls {
                 (0..n)
                     .map(|i| Angle {
                         index: i as u8,
-                        mpls: &self,
+                        mpls: self,
                     })
                     .collect()
             })
             .unwrap_or(Vec::new())
     }
+
+    /// Gets all clips referenced by this playlist, across all angles, sub
+    /// paths and multi-clip entries.
+    ///
+    /// Clips are returned in playlist order and may appear more than once.
+    pub fn clips(&self) -> Vec<&Clip> {
+        let mut clips: Vec<&Clip> = Vec::new();
+        for item in self.play_list.play_items.iter() {
+            clips.push(&item.clip);
+            clips.extend(item.angles.iter());
+        }
+        for sub_path in self.play_list.sub_paths.iter() {
+            for item in sub_path.play_items.iter() {
+                clips.push(&item.clip);
+                clips.extend(item.multi_clip_entries.iter());
+            }
+        }
+        clips
+    }
+
+    /// Gets the playlist's total running time.
+    ///
+    /// This is the sum of the durations of all play items on the main path.
+    /// The sum saturates at the largest time stamp (about 26.5 hours), which
+    /// only very long loop or play-all playlists can reach.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::Mpls;
+    ///
+    /// # let mpls = {
+    /// #     let bytes = include_bytes!("../assets/simple.mpls");
+    /// #     Mpls::from(&bytes[..]).unwrap()
+    /// # };
+    /// assert_eq!(mpls.duration().0, 289_468_553);
+    /// ```
+    pub fn duration(&self) -> TimeStamp {
+        TimeStamp::saturating_sum(self.play_list.play_items.iter().map(PlayItem::duration))
+    }
+
+    /// Converts a time stamp on a play item's clip timeline into a time stamp
+    /// that is relative to the start of the playlist.
+    ///
+    /// Returns `None` if the play item doesn't exist. Time stamps outside of
+    /// the play item's in and out times are clamped to that range. Like
+    /// [`duration`], the result saturates at the largest time stamp.
+    ///
+    /// [`duration`]: #method.duration
+    pub fn playlist_time(&self, play_item: PlayItemRef, time: TimeStamp) -> Option<TimeStamp> {
+        let items = &self.play_list.play_items;
+        let idx = play_item.0 as usize;
+        let item = items.get(idx)?;
+        let offset = TimeStamp::saturating_sum(items[..idx].iter().map(PlayItem::duration));
+        let clamped = time.0.max(item.in_time.0).min(item.out_time.0);
+        Some(TimeStamp(offset.0.saturating_add(clamped - item.in_time.0)))
+    }
+
+    /// Finds the parts of the playlist where the angles actually diverge.
+    ///
+    /// Every play item that uses a different clip for at least one angle than
+    /// for angle 0 is part of a change point. Adjacent play items are merged
+    /// into a single change point as long as the same angles differ and their
+    /// [`AngleInfo`] flags match. Like [`duration`], the time ranges saturate
+    /// at the largest time stamp.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::Mpls;
+    ///
+    /// # let mpls = {
+    /// #     let bytes = include_bytes!("../assets/multi-angle.mpls");
+    /// #     Mpls::from(&bytes[..]).unwrap()
+    /// # };
+    /// let changes = mpls.angle_change_points();
+    /// assert_eq!(changes[0].play_items, 1..2);
+    /// assert_eq!(changes[0].angles, &[1, 2, 3]);
+    ///
+    /// // extract the alternate scene for angle 2
+    /// let angle = mpls.angles()[2];
+    /// let clips = &angle.segments()[changes[0].play_items.clone()];
+    /// assert_eq!(clips[0].file_name, "00084");
+    /// ```
+    ///
+    /// [`AngleInfo`]: struct.AngleInfo.html
+    /// [`duration`]: #method.duration
+    pub fn angle_change_points(&self) -> Vec<AngleChangePoint> {
+        let mut changes: Vec<AngleChangePoint> = Vec::new();
+        let mut offset = 0u32;
+        for (i, item) in self.play_list.play_items.iter().enumerate() {
+            let start = TimeStamp(offset);
+            offset = offset.saturating_add(item.duration().0);
+            let end = TimeStamp(offset);
+
+            let angles: Vec<u8> = item
+                .angles
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.file_name != item.clip.file_name)
+                .map(|(a, _)| a as u8 + 1)
+                .collect();
+            let angle_info = match (item.angle_info, angles.is_empty()) {
+                (Some(info), false) => info,
+                _ => continue,
+            };
+
+            match changes.last_mut() {
+                Some(last)
+                    if last.play_items.end == i
+                        && last.angle_info == angle_info
+                        && last.angles == angles =>
+                {
+                    last.play_items.end = i + 1;
+                    last.range.end = end;
+                }
+                _ => changes.push(AngleChangePoint {
+                    play_items: i..i + 1,
+                    range: start..end,
+                    angle_info,
+                    angles,
+                }),
+            }
+        }
+        changes
+    }
+
+    /// Gets the time range a sub play item covers on the playlist timeline.
+    ///
+    /// The sub play item starts at its `sync_start_pts` on the clip timeline
+    /// of the main path play item referenced by its `sync_play_item_id`, and
+    /// lasts for the time between its own in and out times. Returns `None` if
+    /// the referenced play item doesn't exist.
+    pub fn sub_play_item_range(&self, item: &SubPlayItem) -> Option<Range<TimeStamp>> {
+        let start = self.playlist_time(
+            PlayItemRef(item.sync_play_item_id),
+            TimeStamp(item.sync_start_pts),
+        )?;
+        let end = TimeStamp(start.0.saturating_add(item.duration().0));
+        Some(start..end)
+    }
+
+    /// Places every sub play item of every synchronous sub path on the
+    /// playlist timeline.
+    ///
+    /// This lines up e.g. secondary audio, picture-in-picture video or text
+    /// subtitles with the main feature. Sub paths that play asynchronously
+    /// (see [`SubPath::is_synchronous`]) have no fixed position and are
+    /// skipped, as are sub play items that reference a nonexistent play item.
+    ///
+    /// [`SubPath::is_synchronous`]: struct.SubPath.html#method.is_synchronous
+    pub fn sub_path_timeline(&self) -> Vec<SubPlayItemPlacement> {
+        let mut placements = Vec::new();
+        for (i, sub_path) in self.play_list.sub_paths.iter().enumerate() {
+            if !sub_path.is_synchronous() {
+                continue;
+            }
+            for (j, item) in sub_path.play_items.iter().enumerate() {
+                if let Some(range) = self.sub_play_item_range(item) {
+                    placements.push(SubPlayItemPlacement {
+                        sub_path: i,
+                        sub_play_item: j,
+                        range,
+                    });
+                }
+            }
+        }
+        placements
+    }
+
+    /// Gets the frame rate of the playlist's primary video stream.
+    ///
+    /// This is the frame rate of the first play item that has a primary video
+    /// stream with a known frame rate.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::Mpls;
+    ///
+    /// # let mpls = {
+    /// #     let bytes = include_bytes!("../assets/simple.mpls");
+    /// #     Mpls::from(&bytes[..]).unwrap()
+    /// # };
+    /// let frame_rate = mpls.frame_rate().unwrap();
+    /// assert_eq!((frame_rate.numerator, frame_rate.denominator), (24_000, 1_001));
+    /// ```
+    pub fn frame_rate(&self) -> FrameRate {
+        self.play_list
+            .play_items
+            .iter()
+            .find_map(|p| p.frame_rate())
+    }
+
+    /// Gets the index of the frame at the given playlist-relative time.
+    ///
+    /// Returns `None` if the playlist's frame rate is unknown.
+    pub fn frame_index(&self, time: TimeStamp, rounding: RoundingMode) -> Option<u64> {
+        self.frame_rate().map(|f| f.frames(time, rounding))
+    }
+
+    /// Gets the playlist-relative time of the frame with the given index.
+    ///
+    /// Returns `None` if the playlist's frame rate is unknown.
+    pub fn frame_time(&self, frame: u64, rounding: RoundingMode) -> Option<TimeStamp> {
+        self.frame_rate().map(|f| f.time_stamp(frame, rounding))
+    }
+
+    /// Gets the playlist's length in frames.
+    ///
+    /// Returns `None` if the playlist's frame rate is unknown.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::{Mpls, RoundingMode};
+    ///
+    /// # let mpls = {
+    /// #     let bytes = include_bytes!("../assets/simple.mpls");
+    /// #     Mpls::from(&bytes[..]).unwrap()
+    /// # };
+    /// assert_eq!(mpls.frame_count(RoundingMode::Floor), Some(154_228));
+    /// assert_eq!(mpls.frame_count(RoundingMode::Nearest), Some(154_229));
+    /// ```
+    pub fn frame_count(&self, rounding: RoundingMode) -> Option<u64> {
+        self.frame_index(self.duration(), rounding)
+    }
+
+    /// Converts a time stamp that is relative to the start of the playlist
+    /// into a play item and a time stamp on that play item's clip timeline.
+    ///
+    /// This is the inverse of [`playlist_time`]. Returns `None` if the time
+    /// stamp lies at or beyond the end of the playlist.
+    ///
+    /// [`playlist_time`]: #method.playlist_time
+    pub fn clip_time(&self, time: TimeStamp) -> Option<(PlayItemRef, TimeStamp)> {
+        // the offsets may exceed the largest time stamp
+        let mut offset = 0u64;
+        for (i, item) in self.play_list.play_items.iter().enumerate() {
+            let duration = item.duration().0 as u64;
+            if (time.0 as u64) < offset + duration {
+                let clip_time = TimeStamp(item.in_time.0 + (time.0 as u64 - offset) as u32);
+                return Some((PlayItemRef(i as u16), clip_time));
+            }
+            offset += duration;
+        }
+        None
+    }
 }
 
-impl Angle<'_> {
+impl<'mpls> Angle<'mpls> {
     /// Gets all segments for this angle.
     ///
     /// # Examples
@@ -161,9 +441,9 @@ impl Angle<'_> {
     /// assert_eq!(&segments.0[..5], &["00081", "00082", "00086", "00087", "00091"]);
     /// assert_eq!(&segments.1[..5], &["00081", "00083", "00086", "00088", "00091"]);
     /// ```
-    pub fn segments(&self) -> Vec<&Clip> {
+    pub fn segments(&self) -> Vec<&'mpls Clip> {
         let play_items = &self.mpls.play_list.play_items;
-        let mut clips: Vec<&Clip> = Vec::with_capacity(play_items.len());
+        let mut clips: Vec<&'mpls Clip> = Vec::with_capacity(play_items.len());
         for play_item in play_items.iter() {
             let clip = play_item.clip_for_angle(self);
             clips.push(clip);
@@ -175,21 +455,75 @@ impl Angle<'_> {
 #[derive(Debug, Clone)]
 pub struct PlayItem {
     pub clip: Clip,
+    pub connection_condition: u8,
     pub in_time: TimeStamp,
     pub out_time: TimeStamp,
     pub user_opt_mask: u64,
+    pub random_access_flag: bool,
+    pub still_mode: u8,
+    pub still_time: u16,
     pub angles: Vec<Clip>,
     pub angle_info: Option<AngleInfo>,
     pub stream_number_table: StreamNumberTable,
 }
 
 impl PlayItem {
+    /// Gets the play item's duration, i.e. the time between its in and out times.
+    pub fn duration(&self) -> TimeStamp {
+        TimeStamp(self.out_time.0.saturating_sub(self.in_time.0))
+    }
+
+    /// Gets the frame rate of this play item's primary video stream.
+    ///
+    /// Returns `None` if the play item has no primary video stream or if its
+    /// frame rate is unknown.
+    pub fn frame_rate(&self) -> FrameRate {
+        self.stream_number_table
+            .primary_video_streams
+            .iter()
+            .find_map(|s| match s.attrs.stream_type {
+                StreamType::SdrVideo(_, f) | StreamType::HdrVideo(_, f, _, _) => f,
+                _ => None,
+            })
+    }
+
+    /// Gets the index of the frame at the given clip time, counted from this
+    /// play item's in time.
+    ///
+    /// Returns `None` if the play item's frame rate is unknown.
+    pub fn frame_index(&self, time: TimeStamp, rounding: RoundingMode) -> Option<u64> {
+        let offset = TimeStamp(time.0.saturating_sub(self.in_time.0));
+        self.frame_rate().map(|f| f.frames(offset, rounding))
+    }
+
+    /// Gets the clip time of the frame with the given index, counted from this
+    /// play item's in time.
+    ///
+    /// Returns `None` if the play item's frame rate is unknown.
+    pub fn frame_time(&self, frame: u64, rounding: RoundingMode) -> Option<TimeStamp> {
+        self.frame_rate().map(|f| {
+            TimeStamp(
+                self.in_time
+                    .0
+                    .saturating_add(f.time_stamp(frame, rounding).0),
+            )
+        })
+    }
+
+    /// Gets this play item's length in frames.
+    ///
+    /// Returns `None` if the play item's frame rate is unknown.
+    pub fn frame_count(&self, rounding: RoundingMode) -> Option<u64> {
+        self.frame_rate()
+            .map(|f| f.frames(self.duration(), rounding))
+    }
+
     pub fn clip_for_angle(&self, angle: &Angle) -> &Clip {
         match angle.index {
             0 => &self.clip,
             i => {
                 let idx = i.saturating_sub(1) as usize;
-                match (&self.angles).get(idx) {
+                match self.angles.get(idx) {
                     Some(c) => c,
                     None => &self.clip,
                 }
@@ -201,6 +535,7 @@ impl PlayItem {
 #[derive(Debug, Clone)]
 pub struct SubPlayItem {
     pub clip: Clip,
+    pub connection_condition: u8,
     pub in_time: TimeStamp,
     pub out_time: TimeStamp,
     pub sync_play_item_id: u16,
@@ -208,6 +543,14 @@ pub struct SubPlayItem {
     pub multi_clip_entries: Vec<Clip>,
 }
 
+impl SubPlayItem {
+    /// Gets the sub play item's duration, i.e. the time between its in and out
+    /// times.
+    pub fn duration(&self) -> TimeStamp {
+        TimeStamp(self.out_time.0.saturating_sub(self.in_time.0))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ExtensionDataEntry {
     pub data_type: u16,
@@ -222,7 +565,55 @@ pub struct SubPath {
     pub play_items: Vec<SubPlayItem>,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl SubPath {
+    /// Returns whether this sub path plays in sync with the main path.
+    ///
+    /// Browsable slideshow audio (type 2), pop-up interactive graphics menus
+    /// (type 3) and asynchronous picture-in-picture video (type 6) play
+    /// independently of the main path. All other sub paths, such as text
+    /// subtitles, secondary audio and synchronous picture-in-picture video, are
+    /// anchored to a point on the main path through their sub play items'
+    /// `sync_play_item_id` and `sync_start_pts`.
+    pub fn is_synchronous(&self) -> bool {
+        !matches!(self.sub_path_type, 2 | 3 | 6)
+    }
+}
+
+/// A stretch of the playlist where one or more angles differ from the main
+/// angle.
+///
+/// See [`Mpls::angle_change_points`].
+///
+/// [`Mpls::angle_change_points`]: struct.Mpls.html#method.angle_change_points
+#[derive(Debug, Clone)]
+pub struct AngleChangePoint {
+    /// The indices of the play items in this stretch.
+    pub play_items: Range<usize>,
+    /// The time range of this stretch, relative to the start of the playlist.
+    pub range: Range<TimeStamp>,
+    /// The angle flags shared by all play items in this stretch.
+    pub angle_info: AngleInfo,
+    /// The indices of the angles whose clips differ from angle 0.
+    pub angles: Vec<u8>,
+}
+
+/// The position of a sub play item on the playlist timeline.
+///
+/// See [`Mpls::sub_path_timeline`].
+///
+/// [`Mpls::sub_path_timeline`]: struct.Mpls.html#method.sub_path_timeline
+#[derive(Debug, Clone)]
+pub struct SubPlayItemPlacement {
+    /// The index of the sub path within the playlist.
+    pub sub_path: usize,
+    /// The index of the sub play item within its sub path.
+    pub sub_play_item: usize,
+    /// The time range the sub play item covers, relative to the start of the
+    /// playlist.
+    pub range: Range<TimeStamp>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct AngleInfo {
     pub is_different_audios: bool,
     pub is_seamless_angle_change: bool,
@@ -232,11 +623,13 @@ pub struct AngleInfo {
 ///
 /// This identifies the playable stream file. `file_name` consists of 5 numbers
 /// (e.g. "00055"), and `codec_id` of 4 letters which will usually be "M2TS" on
-/// blu-rays.
+/// blu-rays. `stc_id` refers to the System Time Clock sequence within the clip
+/// that the playlist's time stamps are relative to.
 #[derive(Debug, Clone)]
 pub struct Clip {
     pub file_name: String,
     pub codec_id: String,
+    pub stc_id: u8,
 }
 
 #[derive(Debug, Clone)]
@@ -250,6 +643,7 @@ pub struct PlayListMark {
     pub mark_type: MarkType,
     pub play_item: PlayItemRef,
     pub time_stamp: TimeStamp,
+    pub entry_es_pid: u16,
     pub duration: Option<TimeStamp>,
 }
 
@@ -260,7 +654,7 @@ pub enum MarkType {
     Unknown,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PlaybackType {
     Standard,
     Random,
@@ -429,6 +823,65 @@ impl FrameRateFraction {
     pub fn fps_single(&self) -> f32 {
         (self.numerator as f32) / (self.denominator as f32)
     }
+
+    /// Converts a time span into a number of frames at this frame rate.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::{FrameRateFraction, RoundingMode, TimeStamp};
+    ///
+    /// let ntsc_film = FrameRateFraction { numerator: 24_000, denominator: 1_001 };
+    /// // one frame lasts 1876.875 ticks
+    /// let ts = TimeStamp(1_877);
+    /// assert_eq!(ntsc_film.frames(ts, RoundingMode::Floor), 1);
+    /// assert_eq!(ntsc_film.frames(ts, RoundingMode::Ceil), 2);
+    /// assert_eq!(ntsc_film.frames(ts, RoundingMode::Nearest), 1);
+    /// ```
+    pub fn frames(&self, time: TimeStamp, rounding: RoundingMode) -> u64 {
+        let n = time.0 as u128 * self.numerator as u128;
+        let d = 45_000u128 * self.denominator as u128;
+        rounding.div(n, d) as u64
+    }
+
+    /// Converts a number of frames at this frame rate into a time span.
+    ///
+    /// # Examples
+    /// ```
+    /// use mpls::{FrameRateFraction, RoundingMode, TimeStamp};
+    ///
+    /// let ntsc_film = FrameRateFraction { numerator: 24_000, denominator: 1_001 };
+    /// assert_eq!(ntsc_film.time_stamp(8, RoundingMode::Nearest), TimeStamp(15_015));
+    /// assert_eq!(ntsc_film.time_stamp(1, RoundingMode::Floor), TimeStamp(1_876));
+    /// assert_eq!(ntsc_film.time_stamp(1, RoundingMode::Ceil), TimeStamp(1_877));
+    /// ```
+    pub fn time_stamp(&self, frames: u64, rounding: RoundingMode) -> TimeStamp {
+        let n = frames as u128 * 45_000 * self.denominator as u128;
+        let d = self.numerator as u128;
+        TimeStamp(rounding.div(n, d).min(u32::MAX as u128) as u32)
+    }
+}
+
+/// How to round when converting between time stamps and frames, since a time
+/// stamp rarely falls exactly on a frame boundary.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round towards zero, e.g. to get the frame that is showing at a time.
+    Floor,
+    /// Round away from zero, e.g. to get the first frame that starts at or
+    /// after a time.
+    Ceil,
+    /// Round to the nearest value, with halfway cases rounded up.
+    Nearest,
+}
+
+impl RoundingMode {
+    fn div(self, n: u128, d: u128) -> u128 {
+        match self {
+            RoundingMode::Floor => n / d,
+            RoundingMode::Ceil => n.div_ceil(d),
+            RoundingMode::Nearest => (2 * n + d) / (2 * d),
+        }
+    }
 }
 
 /// A time stamp, relative to some System Time Clock sequence, expressed in 45 KHz.
@@ -436,7 +889,7 @@ impl FrameRateFraction {
 /// To get a floating-point value in seconds, you can use the [`seconds`] method.
 ///
 /// [`seconds`]: #method.seconds
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct TimeStamp(pub u32);
 
 impl TimeStamp {
@@ -444,6 +897,18 @@ impl TimeStamp {
     pub fn seconds(&self) -> f64 {
         (self.0 as f64) / 45_000f64
     }
+
+    /// Returns this time stamp in whole milliseconds, rounded to the nearest
+    /// millisecond.
+    pub fn millis(&self) -> u64 {
+        (self.0 as u64 * 1_000 + 22_500) / 45_000
+    }
+
+    // adds up time stamps, saturating at the largest time stamp
+    pub(crate) fn saturating_sum<I: IntoIterator<Item = TimeStamp>>(times: I) -> TimeStamp {
+        let sum: u64 = times.into_iter().map(|t| t.0 as u64).sum();
+        TimeStamp(sum.min(u32::MAX as u64) as u32)
+    }
 }
 
 impl Debug for TimeStamp {
@@ -0,0 +1,14 @@
+//! A reader for the UDF file system of Blu-ray disc images.
+//!
+//! Blu-ray discs are formatted with UDF 2.50 or 2.60. [`UdfImage`] reads the
+//! directories and files of such a file system straight from a disc image
+//! (an ISO file), so that a disc's playlists, clip information and streams
+//! can be accessed without mounting or extracting it. See also
+//! [`Disc::open_iso`].
+//!
+//! [`UdfImage`]: struct.UdfImage.html
+//! [`Disc::open_iso`]: ../disc/struct.Disc.html#method.open_iso
+mod parser;
+mod types;
+
+pub use types::*;
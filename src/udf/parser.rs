@@ -0,0 +1,438 @@
+use nom::{
+    bytes::complete::take,
+    do_parse,
+    error::ErrorKind,
+    number::complete::{le_u16, le_u32, le_u64, le_u8},
+    take, Err, IResult,
+};
+
+pub const TAG_ANCHOR: u16 = 2;
+pub const TAG_PARTITION: u16 = 5;
+pub const TAG_LOGICAL_VOLUME: u16 = 6;
+pub const TAG_TERMINATING: u16 = 8;
+pub const TAG_FILE_SET: u16 = 256;
+pub const TAG_FILE_IDENTIFIER: u16 = 257;
+pub const TAG_ALLOCATION_EXTENT: u16 = 258;
+pub const TAG_FILE_ENTRY: u16 = 261;
+pub const TAG_EXTENDED_FILE_ENTRY: u16 = 266;
+
+pub const FILE_TYPE_DIRECTORY: u8 = 4;
+
+/// The 16-byte tag every descriptor starts with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub id: u16,
+    pub location: u32,
+}
+
+/// An extent of the volume, in sectors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExtentAd {
+    pub length: u32,
+    pub location: u32,
+}
+
+/// An allocation descriptor: an extent of a partition.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Extent {
+    /// 0: recorded, 1: allocated but not recorded, 2: not allocated, 3: the
+    /// next extent of allocation descriptors.
+    pub kind: u8,
+    pub length: u32,
+    pub block: u32,
+    /// The partition reference, or `None` for short allocation descriptors,
+    /// whose partition depends on the file entry.
+    pub partition: Option<u16>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PartitionDescriptor {
+    pub number: u16,
+    pub start: u32,
+    pub length: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PartitionMap {
+    /// A type 1 map of a physical partition.
+    Physical { partition: u16 },
+    /// A UDF 2.50 metadata partition, whose blocks are stored in the
+    /// metadata file.
+    Metadata {
+        partition: u16,
+        file_location: u32,
+        mirror_location: u32,
+    },
+    /// Any other type 2 map (e.g. virtual or sparable partitions).
+    Unsupported,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogicalVolume {
+    pub block_size: u32,
+    pub file_set: Extent,
+    pub maps: Vec<PartitionMap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Allocation {
+    Extents(Vec<Extent>),
+    /// The file's data is stored in the file entry itself.
+    Embedded(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    pub file_type: u8,
+    pub size: u64,
+    pub allocation: Allocation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIdentifier {
+    pub characteristics: u8,
+    pub icb: Extent,
+    pub name: String,
+}
+
+impl FileIdentifier {
+    pub fn is_directory(&self) -> bool {
+        self.characteristics & 0x02 != 0
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.characteristics & 0x04 != 0
+    }
+
+    pub fn is_parent(&self) -> bool {
+        self.characteristics & 0x08 != 0
+    }
+}
+
+// parses a descriptor tag, verifying its checksum
+pub fn tag(input: &[u8]) -> IResult<&[u8], Tag> {
+    if input.len() >= 16 {
+        let sum = input[..16]
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 4)
+            .fold(0u8, |sum, (_, b)| sum.wrapping_add(*b));
+        if sum != input[4] {
+            return Err(Err::Error((input, ErrorKind::Verify)));
+        }
+    }
+    do_parse!(
+        input,
+        id: le_u16 >> take!(10usize) >> location: le_u32 >> (Tag { id, location })
+    )
+}
+
+// parses a tag and checks that it has the expected identifier
+pub fn expect_tag(id: u16, input: &[u8]) -> IResult<&[u8], Tag> {
+    let (rest, t) = tag(input)?;
+    if t.id == id {
+        Ok((rest, t))
+    } else {
+        Err(Err::Error((input, ErrorKind::Tag)))
+    }
+}
+
+fn extent_ad(input: &[u8]) -> IResult<&[u8], ExtentAd> {
+    do_parse!(
+        input,
+        length: le_u32 >> location: le_u32 >> (ExtentAd { length, location })
+    )
+}
+
+fn short_ad(input: &[u8]) -> IResult<&[u8], Extent> {
+    do_parse!(
+        input,
+        length: le_u32
+            >> block: le_u32
+            >> (Extent {
+                kind: (length >> 30) as u8,
+                length: length & 0x3FFF_FFFF,
+                block,
+                partition: None,
+            })
+    )
+}
+
+pub fn long_ad(input: &[u8]) -> IResult<&[u8], Extent> {
+    do_parse!(
+        input,
+        length: le_u32
+            >> block: le_u32
+            >> partition: le_u16
+            >> take!(6usize)
+            >> (Extent {
+                kind: (length >> 30) as u8,
+                length: length & 0x3FFF_FFFF,
+                block,
+                partition: Some(partition),
+            })
+    )
+}
+
+/// Parses the main volume descriptor sequence extent of an anchor volume
+/// descriptor pointer.
+pub fn anchor(input: &[u8]) -> IResult<&[u8], ExtentAd> {
+    let (input, _) = expect_tag(TAG_ANCHOR, input)?;
+    extent_ad(input)
+}
+
+pub fn partition_descriptor(input: &[u8]) -> IResult<&[u8], PartitionDescriptor> {
+    do_parse!(
+        input,
+        _tag: tag
+            >> take!(6usize)
+            >> number: le_u16
+            >> take!(164usize)
+            >> start: le_u32
+            >> length: le_u32
+            >> (PartitionDescriptor {
+                number,
+                start,
+                length,
+            })
+    )
+}
+
+fn partition_map(input: &[u8]) -> IResult<&[u8], PartitionMap> {
+    let (_, (map_type, len)) = do_parse!(input, t: le_u8 >> l: le_u8 >> ((t, l)))?;
+    let (rest, map) = take(len as usize)(input)?;
+    let map = match map_type {
+        1 => {
+            let (_, partition) = do_parse!(map, take!(4usize) >> p: le_u16 >> (p))?;
+            PartitionMap::Physical { partition }
+        }
+        2 if map.len() >= 48 && &map[5..28] == b"*UDF Metadata Partition" => {
+            let (_, m) = do_parse!(
+                map,
+                take!(38usize)
+                    >> partition: le_u16
+                    >> file_location: le_u32
+                    >> mirror_location: le_u32
+                    >> (PartitionMap::Metadata {
+                        partition,
+                        file_location,
+                        mirror_location,
+                    })
+            )?;
+            m
+        }
+        _ => PartitionMap::Unsupported,
+    };
+    Ok((rest, map))
+}
+
+pub fn logical_volume(input: &[u8]) -> IResult<&[u8], LogicalVolume> {
+    let (_, (block_size, file_set, n_maps)) = do_parse!(
+        input,
+        _tag: tag
+            >> take!(196usize)
+            >> block_size: le_u32
+            >> take!(32usize)
+            >> file_set: long_ad
+            >> take!(4usize)
+            >> n_maps: le_u32
+            >> ((block_size, file_set, n_maps))
+    )?;
+    let mut maps = Vec::with_capacity(n_maps.min(16) as usize);
+    let mut rest = input.get(440..).unwrap_or(&[]);
+    for _ in 0..n_maps {
+        let (r, map) = partition_map(rest)?;
+        maps.push(map);
+        rest = r;
+    }
+    Ok((
+        rest,
+        LogicalVolume {
+            block_size,
+            file_set,
+            maps,
+        },
+    ))
+}
+
+/// Parses the root directory ICB of a file set descriptor.
+pub fn file_set(input: &[u8]) -> IResult<&[u8], Extent> {
+    let (input, _) = expect_tag(TAG_FILE_SET, input)?;
+    let (input, _) = take(384usize)(input)?;
+    long_ad(input)
+}
+
+// parses `len` bytes of allocation descriptors of the given type
+pub fn allocation_descriptors(ad_type: u8, input: &[u8]) -> IResult<&[u8], Vec<Extent>> {
+    let size = match ad_type {
+        0 => 8,
+        1 => 16,
+        _ => return Err(Err::Error((input, ErrorKind::Switch))),
+    };
+    let mut extents = Vec::with_capacity(input.len() / size);
+    let mut rest = input;
+    while rest.len() >= size {
+        let (r, extent) = if size == 8 {
+            short_ad(rest)?
+        } else {
+            long_ad(rest)?
+        };
+        rest = r;
+        // a zero length terminates the descriptors
+        if extent.length == 0 {
+            break;
+        }
+        extents.push(extent);
+    }
+    Ok((rest, extents))
+}
+
+/// Parses a file entry or an extended file entry.
+pub fn file_entry(input: &[u8]) -> IResult<&[u8], FileEntry> {
+    let (_, t) = tag(input)?;
+    let (ea_offset, header_len) = match t.id {
+        TAG_FILE_ENTRY => (168, 176),
+        TAG_EXTENDED_FILE_ENTRY => (208, 216),
+        _ => return Err(Err::Error((input, ErrorKind::Tag))),
+    };
+    let (_, (file_type, flags, size)) = do_parse!(
+        input,
+        take!(27usize)
+            >> file_type: le_u8
+            >> take!(6usize)
+            >> flags: le_u16
+            >> take!(20usize)
+            >> size: le_u64
+            >> ((file_type, flags, size))
+    )?;
+    let (_, (ea_len, ad_len)) = do_parse!(
+        &input[ea_offset.min(input.len())..],
+        ea: le_u32 >> ad: le_u32 >> ((ea, ad))
+    )?;
+    let start = header_len + ea_len as usize;
+    let ads = input
+        .get(start..start + ad_len as usize)
+        .ok_or(Err::Error((input, ErrorKind::Eof)))?;
+    let allocation = match flags & 0x7 {
+        3 => Allocation::Embedded(ads.to_vec()),
+        ad_type => Allocation::Extents(allocation_descriptors(ad_type as u8, ads)?.1),
+    };
+    Ok((
+        &[],
+        FileEntry {
+            file_type,
+            size,
+            allocation,
+        },
+    ))
+}
+
+/// Parses an allocation extent descriptor, returning its allocation
+/// descriptors.
+pub fn allocation_extent(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let (input, _) = expect_tag(TAG_ALLOCATION_EXTENT, input)?;
+    let (input, len) = do_parse!(input, take!(4usize) >> len: le_u32 >> (len))?;
+    take(len as usize)(input)
+}
+
+// decodes an OSTA compressed unicode string
+fn dstring(bytes: &[u8]) -> String {
+    match bytes.split_first() {
+        Some((8, rest)) => rest.iter().map(|b| *b as char).collect(),
+        Some((16, rest)) => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::new(),
+    }
+}
+
+fn fid_tag(input: &[u8]) -> IResult<&[u8], Tag> {
+    expect_tag(TAG_FILE_IDENTIFIER, input)
+}
+
+pub fn file_identifier(input: &[u8]) -> IResult<&[u8], FileIdentifier> {
+    let (rest, (characteristics, name_len, icb, iu_len)) = do_parse!(
+        input,
+        _tag: fid_tag
+            >> take!(2usize)
+            >> characteristics: le_u8
+            >> name_len: le_u8
+            >> icb: long_ad
+            >> iu_len: le_u16
+            >> ((characteristics, name_len, icb, iu_len))
+    )?;
+    let (rest, _) = take(iu_len as usize)(rest)?;
+    let (rest, name) = take(name_len as usize)(rest)?;
+    // descriptors are padded to a multiple of 4 bytes
+    let len = 38 + iu_len as usize + name_len as usize;
+    let padding = (4 - len % 4) % 4;
+    let (rest, _) = take(padding.min(rest.len()))(rest)?;
+    Ok((
+        rest,
+        FileIdentifier {
+            characteristics,
+            icb,
+            name: dstring(name),
+        },
+    ))
+}
+
+/// Parses all file identifier descriptors of a directory's data.
+pub fn directory(input: &[u8]) -> IResult<&[u8], Vec<FileIdentifier>> {
+    let mut entries = Vec::new();
+    let mut rest = input;
+    // the data may be padded with zeros after the last descriptor
+    while rest.len() >= 38 && rest[..2] != [0, 0] {
+        let (r, fid) = file_identifier(rest)?;
+        rest = r;
+        entries.push(fid);
+    }
+    Ok((rest, entries))
+}
+
+#[cfg(test)]
+mod tests {
+    // sets the checksum of a 16-byte tag
+    fn with_checksum(mut tag: [u8; 16]) -> [u8; 16] {
+        tag[4] = 0;
+        tag[4] = tag.iter().fold(0u8, |s, b| s.wrapping_add(*b));
+        tag
+    }
+
+    #[test]
+    fn tag() {
+        let data = with_checksum([2, 0, 3, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0]);
+        let (rest, tag) = super::tag(&data[..]).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(tag.id, 2);
+        assert_eq!(tag.location, 256);
+
+        let mut broken = data;
+        broken[12] = 1;
+        assert!(super::tag(&broken[..]).is_err());
+        assert!(super::expect_tag(5, &data[..]).is_err());
+    }
+
+    #[test]
+    fn long_ad() {
+        let data = [
+            0x00, 0x08, 0x00, 0x40, 0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0, 0, 0, 0, 0, 0,
+        ];
+        let (_, extent) = super::long_ad(&data[..]).unwrap();
+        assert_eq!(extent.kind, 1);
+        assert_eq!(extent.length, 2048);
+        assert_eq!(extent.block, 16);
+        assert_eq!(extent.partition, Some(1));
+    }
+
+    #[test]
+    fn dstring() {
+        assert_eq!(super::dstring(b"\x08BDMV"), "BDMV");
+        assert_eq!(super::dstring(b"\x10\x00B\x00D"), "BD");
+        assert_eq!(super::dstring(b""), "");
+    }
+}
@@ -0,0 +1,537 @@
+use super::parser::{
+    allocation_descriptors, allocation_extent, anchor, directory, expect_tag, file_entry, file_set,
+    logical_volume, partition_descriptor, tag, Allocation, Extent, FileEntry, FileIdentifier,
+    PartitionMap, FILE_TYPE_DIRECTORY, TAG_FILE_SET, TAG_LOGICAL_VOLUME, TAG_PARTITION,
+    TAG_TERMINATING,
+};
+use crate::MplsError;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+/// The size of a sector, which is also the logical block size of all
+/// Blu-ray discs.
+pub const SECTOR_SIZE: u64 = 2048;
+
+// the sectors an anchor volume descriptor pointer may be recorded at, besides
+// the last one
+const ANCHOR_SECTOR: u64 = 256;
+
+// the maximum length of the descriptor sequences and directories that are
+// read into memory
+const MAX_DESCRIPTORS: u64 = 64;
+const MAX_DIRECTORY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// A read-only view of the file system of a Blu-ray disc image (ISO).
+///
+/// Blu-ray discs use the UDF file system, revision 2.50 or 2.60, which keeps
+/// the file entries and directories in a metadata partition. Only the parts
+/// needed to read the disc's files are supported; virtual and sparable
+/// partitions, which rewritable media use, are not.
+///
+/// Paths are relative to the root directory and separated by `/`, e.g.
+/// `BDMV/PLAYLIST/00800.mpls`. File names are case-sensitive.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> Result<(), mpls::MplsError> {
+/// use mpls::udf::UdfImage;
+/// use mpls::Mpls;
+/// use std::fs::File;
+///
+/// let mut image = UdfImage::open(File::open("movie.iso")?)?;
+/// for entry in image.read_dir("BDMV/PLAYLIST")? {
+///     println!("{} ({} bytes)", entry.name, entry.size);
+/// }
+/// let bytes = image.read("BDMV/PLAYLIST/00800.mpls")?;
+/// let mpls = Mpls::from(&bytes[..])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct UdfImage<R> {
+    reader: R,
+    block_size: u64,
+    /// The partitions, indexed by their partition reference number.
+    partitions: Vec<Partition>,
+    root: Extent,
+}
+
+#[derive(Debug, Clone)]
+enum Partition {
+    Physical {
+        start: u64,
+        length: u64,
+    },
+    /// A metadata partition, whose blocks are the blocks of the metadata
+    /// file, which is recorded in another partition.
+    Metadata {
+        partition: u16,
+        extents: Vec<Extent>,
+    },
+    Unsupported,
+}
+
+/// An entry of a directory, see [`UdfImage::read_dir`].
+///
+/// [`UdfImage::read_dir`]: struct.UdfImage.html#method.read_dir
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdfEntry {
+    pub name: String,
+    pub is_dir: bool,
+    /// The size of the entry's data in bytes.
+    pub size: u64,
+}
+
+/// A file in a [`UdfImage`], resolved to the parts of the image it is
+/// recorded in.
+///
+/// [`UdfImage`]: struct.UdfImage.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdfFile {
+    size: u64,
+    is_dir: bool,
+    pieces: Vec<Piece>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    /// Bytes of the image, as (offset, length).
+    Data(u64, u64),
+    /// An extent that isn't recorded and reads as zeros.
+    Zeros(u64),
+    /// Data that is embedded in the file entry.
+    Embedded(Vec<u8>),
+}
+
+impl Piece {
+    fn len(&self) -> u64 {
+        match self {
+            Piece::Data(_, len) | Piece::Zeros(len) => *len,
+            Piece::Embedded(data) => data.len() as u64,
+        }
+    }
+}
+
+impl UdfFile {
+    /// Gets the file size in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns whether this is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+}
+
+fn udf_error<T>(_: T) -> MplsError {
+    MplsError::UdfParseError
+}
+
+fn not_found(path: &str) -> MplsError {
+    MplsError::IoError(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} doesn't exist in the disc image", path),
+    ))
+}
+
+impl<R: Read + Seek> UdfImage<R> {
+    /// Opens a disc image, reading its volume and file set descriptors.
+    ///
+    /// Fails with [`MplsError::UdfParseError`] if the image doesn't contain a
+    /// valid UDF file system.
+    ///
+    /// [`MplsError::UdfParseError`]: ../error/enum.MplsError.html#variant.UdfParseError
+    pub fn open(mut reader: R) -> Result<UdfImage<R>, MplsError> {
+        let vds = {
+            let sector = read_at(&mut reader, ANCHOR_SECTOR * SECTOR_SIZE, SECTOR_SIZE)?;
+            match anchor(&sector) {
+                Ok((_, vds)) => vds,
+                Err(_) => {
+                    // fall back to the anchor in the last sector
+                    let end = reader.seek(SeekFrom::End(0))?;
+                    let last = (end / SECTOR_SIZE).saturating_sub(1);
+                    let sector = read_at(&mut reader, last * SECTOR_SIZE, SECTOR_SIZE)?;
+                    anchor(&sector).map_err(udf_error)?.1
+                }
+            }
+        };
+
+        let mut descriptors = Vec::new();
+        let mut logical_volume_descriptor = None;
+        let n_sectors = (vds.length as u64 / SECTOR_SIZE).min(MAX_DESCRIPTORS);
+        for i in 0..n_sectors {
+            let offset = (vds.location as u64 + i) * SECTOR_SIZE;
+            let sector = read_at(&mut reader, offset, SECTOR_SIZE)?;
+            let id = match tag(&sector) {
+                Ok((_, t)) => t.id,
+                Err(_) => break,
+            };
+            match id {
+                TAG_PARTITION => {
+                    descriptors.push(partition_descriptor(&sector).map_err(udf_error)?.1)
+                }
+                TAG_LOGICAL_VOLUME => {
+                    logical_volume_descriptor = Some(logical_volume(&sector).map_err(udf_error)?.1)
+                }
+                TAG_TERMINATING => break,
+                _ => {}
+            }
+        }
+        let lvd = logical_volume_descriptor.ok_or(MplsError::UdfParseError)?;
+        // Blu-ray discs always use blocks of one sector, and blocks are read
+        // into memory whole, so don't trust any other size
+        if lvd.block_size as u64 != SECTOR_SIZE {
+            return Err(MplsError::UdfParseError);
+        }
+
+        // resolve the partition maps, physical partitions first, as the
+        // metadata partition refers to them
+        let mut partitions: Vec<Partition> = lvd
+            .maps
+            .iter()
+            .map(|map| match map {
+                PartitionMap::Physical { partition } => descriptors
+                    .iter()
+                    .find(|d| d.number == *partition)
+                    .map_or(Partition::Unsupported, |d| Partition::Physical {
+                        start: d.start as u64,
+                        length: d.length as u64,
+                    }),
+                _ => Partition::Unsupported,
+            })
+            .collect();
+        let mut image = UdfImage {
+            reader,
+            block_size: lvd.block_size as u64,
+            partitions: partitions.clone(),
+            root: lvd.file_set,
+        };
+        for (i, map) in lvd.maps.iter().enumerate() {
+            if let PartitionMap::Metadata {
+                partition,
+                file_location,
+                ..
+            } = map
+            {
+                let physical = lvd
+                    .maps
+                    .iter()
+                    .position(|m| {
+                        *m == PartitionMap::Physical {
+                            partition: *partition,
+                        }
+                    })
+                    .ok_or(MplsError::UdfParseError)? as u16;
+                let block = image.read_block(physical, *file_location as u64)?;
+                let entry = file_entry(&block).map_err(udf_error)?.1;
+                let extents = match entry.allocation {
+                    Allocation::Extents(extents) => image.resolve_extents(extents, physical)?,
+                    Allocation::Embedded(_) => return Err(MplsError::UdfParseError),
+                };
+                partitions[i] = Partition::Metadata {
+                    partition: physical,
+                    extents,
+                };
+            }
+        }
+        image.partitions = partitions;
+
+        let fsd = image.root;
+        let fsd_partition = fsd.partition.unwrap_or(0);
+        let block = image.read_block(fsd_partition, fsd.block as u64)?;
+        expect_tag(TAG_FILE_SET, &block).map_err(udf_error)?;
+        let mut root = file_set(&block).map_err(udf_error)?.1;
+        root.partition = root.partition.or(Some(fsd_partition));
+        image.root = root;
+        Ok(image)
+    }
+
+    /// Lists the entries of a directory, excluding the parent directory and
+    /// deleted files.
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<UdfEntry>, MplsError> {
+        let icb = self.lookup(path)?;
+        let dir = self.file_at(icb)?;
+        if !dir.is_dir {
+            return Err(MplsError::IoError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", path),
+            )));
+        }
+        let mut entries = Vec::new();
+        for fid in self.directory(&dir)? {
+            let icb = self.icb(fid.icb);
+            let size = self.entry(icb)?.size;
+            entries.push(UdfEntry {
+                is_dir: fid.is_directory(),
+                name: fid.name,
+                size,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Looks up a file or directory.
+    pub fn file(&mut self, path: &str) -> Result<UdfFile, MplsError> {
+        let icb = self.lookup(path)?;
+        self.file_at(icb)
+    }
+
+    /// Reads a whole file into memory.
+    pub fn read(&mut self, path: &str) -> Result<Vec<u8>, MplsError> {
+        let file = self.file(path)?;
+        self.read_file(&file, 0..file.size)
+    }
+
+    /// Reads a byte range of a file, e.g. a part of a transport stream.
+    ///
+    /// The range is clamped to the file's size.
+    pub fn read_range(&mut self, path: &str, range: Range<u64>) -> Result<Vec<u8>, MplsError> {
+        let file = self.file(path)?;
+        self.read_file(&file, range)
+    }
+
+    /// Reads a byte range of a file that was looked up with [`file`].
+    ///
+    /// The range is clamped to the file's size.
+    ///
+    /// [`file`]: #method.file
+    pub fn read_file(&mut self, file: &UdfFile, range: Range<u64>) -> Result<Vec<u8>, MplsError> {
+        let start = range.start.min(file.size);
+        let end = range.end.min(file.size).max(start);
+        let mut buf = Vec::with_capacity((end - start) as usize);
+        self.copy_range(file, start..end, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Copies a whole file to a writer, returning the number of bytes
+    /// copied.
+    pub fn copy_to<W: Write>(&mut self, path: &str, writer: &mut W) -> Result<u64, MplsError> {
+        let file = self.file(path)?;
+        self.copy_range(&file, 0..file.size, writer)
+    }
+
+    fn copy_range<W: Write>(
+        &mut self,
+        file: &UdfFile,
+        range: Range<u64>,
+        writer: &mut W,
+    ) -> Result<u64, MplsError> {
+        let mut position = 0;
+        let mut copied = 0;
+        for piece in file.pieces.iter() {
+            let piece_range = position..position + piece.len();
+            position = piece_range.end;
+            let start = range.start.max(piece_range.start);
+            let end = range.end.min(piece_range.end);
+            if start >= end {
+                continue;
+            }
+            let (skip, len) = (start - piece_range.start, end - start);
+            match piece {
+                Piece::Data(offset, _) => {
+                    self.reader.seek(SeekFrom::Start(offset + skip))?;
+                    let n = io::copy(&mut (&mut self.reader).take(len), writer)?;
+                    if n < len {
+                        return Err(MplsError::IoError(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                }
+                Piece::Zeros(_) => {
+                    io::copy(&mut io::repeat(0).take(len), writer)?;
+                }
+                Piece::Embedded(data) => {
+                    writer.write_all(&data[skip as usize..(skip + len) as usize])?;
+                }
+            }
+            copied += len;
+        }
+        Ok(copied)
+    }
+
+    // finds the ICB of the file or directory at `path`
+    fn lookup(&mut self, path: &str) -> Result<Extent, MplsError> {
+        let mut icb = self.root;
+        for name in path.split('/').filter(|c| !c.is_empty()) {
+            let dir = self.file_at(icb)?;
+            if !dir.is_dir {
+                return Err(not_found(path));
+            }
+            let fid = self
+                .directory(&dir)?
+                .into_iter()
+                .find(|f| f.name == name)
+                .ok_or_else(|| not_found(path))?;
+            icb = self.icb(fid.icb);
+        }
+        Ok(icb)
+    }
+
+    // the file identifiers of a directory, excluding the parent and deleted
+    // entries
+    fn directory(&mut self, dir: &UdfFile) -> Result<Vec<FileIdentifier>, MplsError> {
+        if dir.size > MAX_DIRECTORY_SIZE {
+            return Err(MplsError::UdfParseError);
+        }
+        let data = self.read_file(dir, 0..dir.size)?;
+        let entries = directory(&data).map_err(udf_error)?.1;
+        Ok(entries
+            .into_iter()
+            .filter(|f| !f.is_parent() && !f.is_deleted())
+            .collect())
+    }
+
+    // the ICB of a directory entry, whose partition defaults to partition 0
+    fn icb(&self, icb: Extent) -> Extent {
+        Extent {
+            partition: icb.partition.or(Some(0)),
+            ..icb
+        }
+    }
+
+    fn entry(&mut self, icb: Extent) -> Result<FileEntry, MplsError> {
+        let partition = icb.partition.unwrap_or(0);
+        let block = self.read_block(partition, icb.block as u64)?;
+        Ok(file_entry(&block).map_err(udf_error)?.1)
+    }
+
+    fn file_at(&mut self, icb: Extent) -> Result<UdfFile, MplsError> {
+        let entry = self.entry(icb)?;
+        let is_dir = entry.file_type == FILE_TYPE_DIRECTORY;
+        let mut partition = icb.partition.unwrap_or(0);
+        // the data of files whose entries are in the metadata partition is
+        // recorded in the partition the metadata file is in
+        if let Some(Partition::Metadata { partition: p, .. }) =
+            self.partitions.get(partition as usize)
+        {
+            if !is_dir {
+                partition = *p;
+            }
+        }
+        let mut pieces = Vec::new();
+        match entry.allocation {
+            Allocation::Embedded(data) => pieces.push(Piece::Embedded(data)),
+            Allocation::Extents(extents) => {
+                for extent in self.resolve_extents(extents, partition)? {
+                    let len = extent.length as u64;
+                    if extent.kind != 0 {
+                        pieces.push(Piece::Zeros(len));
+                        continue;
+                    }
+                    let partition = extent.partition.unwrap_or(partition);
+                    self.pieces(partition, extent.block as u64, len, &mut pieces)?;
+                }
+            }
+        }
+        Ok(UdfFile {
+            size: entry.size,
+            is_dir,
+            pieces,
+        })
+    }
+
+    // follows the continuation extents of a list of allocation descriptors,
+    // whose short descriptors refer to `partition`
+    fn resolve_extents(
+        &mut self,
+        extents: Vec<Extent>,
+        partition: u16,
+    ) -> Result<Vec<Extent>, MplsError> {
+        // continuations use the same type of descriptors
+        let ad_type = match extents.first() {
+            Some(Extent {
+                partition: Some(_), ..
+            }) => 1,
+            _ => 0,
+        };
+        let mut resolved = Vec::with_capacity(extents.len());
+        let mut pending = extents;
+        let mut continuations = 0;
+        loop {
+            let mut next = None;
+            for extent in pending {
+                let extent = Extent {
+                    partition: extent.partition.or(Some(partition)),
+                    ..extent
+                };
+                if extent.kind == 3 {
+                    next = Some(extent);
+                    break;
+                }
+                resolved.push(extent);
+            }
+            let next = match next {
+                Some(next) => next,
+                None => return Ok(resolved),
+            };
+            continuations += 1;
+            if continuations > MAX_DESCRIPTORS {
+                return Err(MplsError::UdfParseError);
+            }
+            let block = self.read_block(next.partition.unwrap_or(partition), next.block as u64)?;
+            let ads = allocation_extent(&block).map_err(udf_error)?.1;
+            pending = allocation_descriptors(ad_type, ads).map_err(udf_error)?.1;
+        }
+    }
+
+    // appends the image byte ranges of `len` bytes starting at a block of a
+    // partition
+    fn pieces(
+        &mut self,
+        partition: u16,
+        block: u64,
+        len: u64,
+        pieces: &mut Vec<Piece>,
+    ) -> Result<(), MplsError> {
+        let mut block = block;
+        let mut remaining = len;
+        while remaining > 0 {
+            let (offset, available) = self.locate(partition, block)?;
+            let n = remaining.min(available);
+            match pieces.last_mut() {
+                Some(Piece::Data(o, l)) if *o + *l == offset => *l += n,
+                _ => pieces.push(Piece::Data(offset, n)),
+            }
+            remaining -= n;
+            // only the last part may end in the middle of a block
+            block += n / self.block_size;
+        }
+        Ok(())
+    }
+
+    // the image offset of a block of a partition and the number of bytes
+    // that are contiguous from there on
+    fn locate(&self, partition: u16, block: u64) -> Result<(u64, u64), MplsError> {
+        match self.partitions.get(partition as usize) {
+            Some(Partition::Physical { start, length }) if block < *length => Ok((
+                (start + block) * self.block_size,
+                (length - block) * self.block_size,
+            )),
+            Some(Partition::Metadata { partition, extents }) => {
+                let mut first = 0;
+                for extent in extents.iter().filter(|e| e.kind == 0) {
+                    let n = extent.length as u64 / self.block_size;
+                    if block < first + n {
+                        let skip = block - first;
+                        let (offset, _) = self.locate(*partition, extent.block as u64 + skip)?;
+                        return Ok((offset, (n - skip) * self.block_size));
+                    }
+                    first += n;
+                }
+                Err(MplsError::UdfParseError)
+            }
+            _ => Err(MplsError::UdfParseError),
+        }
+    }
+
+    fn read_block(&mut self, partition: u16, block: u64) -> Result<Vec<u8>, MplsError> {
+        let (offset, _) = self.locate(partition, block)?;
+        Ok(read_at(&mut self.reader, offset, self.block_size)?)
+    }
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![0; len as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
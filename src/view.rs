@@ -0,0 +1,276 @@
+//! Lazy, section-on-demand playlist parsing.
+//!
+//! The MPLS header contains the start addresses of the play list, the marks
+//! and the extension data. [`MplsView`] validates the header and uses these
+//! addresses to locate the sections, which are only parsed when they're
+//! accessed. Play items are parsed one at a time. Since every section is
+//! located through its address, padding between sections, which some
+//! authoring tools emit, doesn't matter.
+//!
+//! [`MplsView`]: struct.MplsView.html
+
+use crate::parser::{
+    addr, app_info_play_list, extension_data, header_tag, parse_error, play_item, play_list_mark,
+    section_data, sub_path, version,
+};
+use crate::types::{
+    AppInfoPlayList, ExtensionDataEntry, Mpls, PlayItem, PlayList, PlayListMark, SubPath, TimeStamp,
+};
+use crate::MplsError;
+use nom::{bytes::complete::take, count, do_parse, number::complete::be_u16, take, IResult};
+use std::cell::OnceCell;
+use std::ops::Range;
+
+// the length of the header, which the app info directly follows
+const HEADER_LEN: usize = 40;
+
+/// A lazily parsed view of a movie playlist.
+///
+/// Accessors parse the part of the playlist they return on first use and
+/// cache the result, so repeated calls are cheap. Parse errors aren't
+/// cached.
+///
+/// # Examples
+/// ```
+/// use mpls::view::MplsView;
+///
+/// let bytes = include_bytes!("../assets/large.mpls");
+/// let view = MplsView::new(&bytes[..]).unwrap();
+/// // only parses the first play item
+/// let first = view.play_item(0).unwrap().unwrap();
+/// assert_eq!(first.clip.file_name, "00055");
+/// assert_eq!(view.play_item_count().unwrap(), 135);
+/// ```
+#[derive(Debug)]
+pub struct MplsView<'a> {
+    bytes: &'a [u8],
+    version: &'a str,
+    play_list_start: usize,
+    marks_start: usize,
+    ext_start: Option<usize>,
+    app_info_play_list: OnceCell<AppInfoPlayList>,
+    play_list: OnceCell<PlayListIndex<'a>>,
+    sub_paths: OnceCell<Vec<SubPath>>,
+    marks: OnceCell<Vec<PlayListMark>>,
+    ext: OnceCell<Vec<ExtensionDataEntry>>,
+}
+
+// the locations of the play items and sub paths within the play list
+#[derive(Debug)]
+struct PlayListIndex<'a> {
+    play_items: Vec<(&'a [u8], OnceCell<PlayItem>)>,
+    n_sub_paths: u16,
+    sub_paths: &'a [u8],
+}
+
+// returns the cached value or caches the parsed one
+fn cached<T, F>(cell: &OnceCell<T>, parse: F) -> Result<&T, MplsError>
+where
+    F: FnOnce() -> Result<T, MplsError>,
+{
+    if let Some(value) = cell.get() {
+        return Ok(value);
+    }
+    let value = parse()?;
+    Ok(cell.get_or_init(|| value))
+}
+
+// a play item including its 16-bit length, without parsing it
+fn play_item_data(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let (_, len) = be_u16(input)?;
+    let (rest, data) = take(2 + len as usize)(input)?;
+    Ok((rest, data))
+}
+
+fn play_list_index(input: &[u8]) -> IResult<&[u8], PlayListIndex<'_>> {
+    let (_, play_list) = section_data(input)?;
+    let (rest, (n_play_items, n_sub_paths)) = do_parse!(
+        play_list,
+        take!(2usize) >> n: be_u16 >> s: be_u16 >> ((n, s))
+    )?;
+    let (sub_paths, play_items) = count!(rest, play_item_data, n_play_items as usize)?;
+    Ok((
+        &[],
+        PlayListIndex {
+            play_items: play_items
+                .into_iter()
+                .map(|data| (data, OnceCell::new()))
+                .collect(),
+            n_sub_paths,
+            sub_paths,
+        },
+    ))
+}
+
+impl<'a> MplsView<'a> {
+    /// Creates a view of a movie playlist, validating its header.
+    ///
+    /// Fails if the data doesn't start with an MPLS header or if the
+    /// header's section addresses are out of order or out of bounds.
+    pub fn new(bytes: &'a [u8]) -> Result<MplsView<'a>, MplsError> {
+        let (_, (version, play_list_start, marks_start, ext_start)) = do_parse!(
+            bytes,
+            header_tag
+                >> version: version
+                >> play_list: addr
+                >> marks: addr
+                >> ext: addr
+                >> take!(20usize)
+                >> ((version, play_list, marks, ext))
+        )
+        .map_err(parse_error)?;
+
+        let (play_list_start, marks_start) = (play_list_start as usize, marks_start as usize);
+        let ext_start = match ext_start {
+            0 => None,
+            n => Some(n as usize),
+        };
+        let end = ext_start.unwrap_or(marks_start);
+        if play_list_start < HEADER_LEN || marks_start < play_list_start || end < marks_start {
+            return Err(MplsError::ParseError);
+        }
+        if end >= bytes.len() {
+            return Err(MplsError::ParseError);
+        }
+
+        Ok(MplsView {
+            bytes,
+            version,
+            play_list_start,
+            marks_start,
+            ext_start,
+            app_info_play_list: OnceCell::new(),
+            play_list: OnceCell::new(),
+            sub_paths: OnceCell::new(),
+            marks: OnceCell::new(),
+            ext: OnceCell::new(),
+        })
+    }
+
+    /// Gets the file format version, e.g. "0200" or "0300".
+    pub fn version(&self) -> &'a str {
+        self.version
+    }
+
+    /// Gets the byte ranges of the play list, marks and (if present)
+    /// extension data sections, as given by the header.
+    ///
+    /// A section's range ends where the next section starts, so it includes
+    /// any padding after the section.
+    pub fn sections(&self) -> (Range<usize>, Range<usize>, Option<Range<usize>>) {
+        let marks_end = self.ext_start.unwrap_or(self.bytes.len());
+        (
+            self.play_list_start..self.marks_start,
+            self.marks_start..marks_end,
+            self.ext_start.map(|start| start..self.bytes.len()),
+        )
+    }
+
+    /// Gets the app info, which directly follows the header.
+    pub fn app_info_play_list(&self) -> Result<&AppInfoPlayList, MplsError> {
+        cached(&self.app_info_play_list, || {
+            let data = &self.bytes[HEADER_LEN..self.play_list_start];
+            app_info_play_list(data)
+                .map(|(_, a)| a)
+                .map_err(parse_error)
+        })
+    }
+
+    fn play_list_index(&self) -> Result<&PlayListIndex<'a>, MplsError> {
+        let data: &'a [u8] = &self.bytes[self.play_list_start..self.marks_start];
+        cached(&self.play_list, || {
+            play_list_index(data).map(|(_, i)| i).map_err(parse_error)
+        })
+    }
+
+    /// Gets the number of play items.
+    pub fn play_item_count(&self) -> Result<usize, MplsError> {
+        Ok(self.play_list_index()?.play_items.len())
+    }
+
+    /// Gets a play item, parsing only that play item.
+    ///
+    /// Returns `None` if there's no play item with that index.
+    pub fn play_item(&self, index: usize) -> Result<Option<&PlayItem>, MplsError> {
+        let (data, cell) = match self.play_list_index()?.play_items.get(index) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        cached(cell, || {
+            play_item(data).map(|(_, p)| p).map_err(parse_error)
+        })
+        .map(Some)
+    }
+
+    /// Gets all play items.
+    pub fn play_items(&self) -> Result<Vec<&PlayItem>, MplsError> {
+        let n = self.play_item_count()?;
+        let mut play_items = Vec::with_capacity(n);
+        for i in 0..n {
+            play_items.extend(self.play_item(i)?);
+        }
+        Ok(play_items)
+    }
+
+    /// Gets the playlist's duration, see [`Mpls::duration`].
+    ///
+    /// [`Mpls::duration`]: ../types/struct.Mpls.html#method.duration
+    pub fn duration(&self) -> Result<TimeStamp, MplsError> {
+        let items = self.play_items()?;
+        Ok(TimeStamp::saturating_sum(
+            items.iter().map(|p| p.duration()),
+        ))
+    }
+
+    /// Gets the sub paths.
+    pub fn sub_paths(&self) -> Result<&[SubPath], MplsError> {
+        let index = self.play_list_index()?;
+        cached(&self.sub_paths, || {
+            count!(index.sub_paths, sub_path, index.n_sub_paths as usize)
+                .map(|(_, s)| s)
+                .map_err(parse_error)
+        })
+        .map(Vec::as_slice)
+    }
+
+    /// Gets the playlist marks.
+    pub fn marks(&self) -> Result<&[PlayListMark], MplsError> {
+        let data = &self.bytes[self.sections().1];
+        cached(&self.marks, || {
+            play_list_mark(data).map(|(_, m)| m).map_err(parse_error)
+        })
+        .map(Vec::as_slice)
+    }
+
+    /// Gets the extension data entries, or none if the playlist has no
+    /// extension data.
+    pub fn ext(&self) -> Result<&[ExtensionDataEntry], MplsError> {
+        let data = match self.sections().2 {
+            Some(range) => &self.bytes[range],
+            None => &[],
+        };
+        cached(&self.ext, || {
+            if data.is_empty() {
+                return Ok(Vec::new());
+            }
+            extension_data(data).map(|(_, e)| e).map_err(parse_error)
+        })
+        .map(Vec::as_slice)
+    }
+
+    /// Parses all sections into an [`Mpls`].
+    ///
+    /// [`Mpls`]: ../types/struct.Mpls.html
+    pub fn to_mpls(&self) -> Result<Mpls, MplsError> {
+        Ok(Mpls {
+            version: self.version.into(),
+            app_info_play_list: *self.app_info_play_list()?,
+            play_list: PlayList {
+                play_items: self.play_items()?.into_iter().cloned().collect(),
+                sub_paths: self.sub_paths()?.to_vec(),
+            },
+            marks: self.marks()?.to_vec(),
+            ext: self.ext()?.to_vec(),
+        })
+    }
+}
@@ -0,0 +1,475 @@
+//! A simulation of the HDMV navigation virtual machine.
+//!
+//! Which playlist a title actually plays is often only decided at run time:
+//! a movie object may pick one of several playlists depending on the
+//! player's language or region settings, or hide the real one among dozens of
+//! decoys. The [`Vm`] executes the movie objects of a title with a set of
+//! simulated player status registers (see [`VmConfig`]) and reports the
+//! playlists it reaches.
+//!
+//! The simulation doesn't play anything: `PlayPL` commands are recorded and
+//! execution continues with the next command, as if the playlist had played
+//! to its end. There is no user input either, so button commands of
+//! interactive graphics menus are never run.
+//!
+//! [`Vm`]: struct.Vm.html
+//! [`VmConfig`]: struct.VmConfig.html
+
+use crate::index::{Index, IndexObject};
+use crate::movie_objects::{CompareOp, Instruction, MovieObjects, Operand, SetOp};
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+/// The number of general purpose registers.
+pub const GPR_COUNT: usize = 4096;
+/// The number of player status registers.
+pub const PSR_COUNT: usize = 128;
+
+/// The player settings the simulation runs with.
+#[derive(Debug, Clone)]
+pub struct VmConfig {
+    /// The preferred audio language as ISO 639-2 code, e.g. "eng" (PSR 16).
+    pub audio_language: String,
+    /// The preferred subtitle language (PSR 17).
+    pub subtitle_language: String,
+    /// The preferred menu language (PSR 18).
+    pub menu_language: String,
+    /// The player's country as ISO 3166-1 alpha-2 code, e.g. "us" (PSR 19).
+    pub country: String,
+    /// The player's region (PSR 20): 1 for region A, 2 for B and 4 for C.
+    pub region: u32,
+    /// The parental level (PSR 13), 255 meaning no restriction.
+    pub parental_level: u32,
+    /// Whether `JumpTitle` and `CallTitle` commands are followed into the
+    /// other title. If not, the simulation stops at such a command.
+    pub follow_title_jumps: bool,
+    /// The maximum number of commands to execute before giving up.
+    pub step_limit: usize,
+    /// Whether to record every executed command in [`VmResult::trace`].
+    ///
+    /// [`VmResult::trace`]: struct.VmResult.html#structfield.trace
+    pub trace: bool,
+}
+
+impl Default for VmConfig {
+    fn default() -> Self {
+        VmConfig {
+            audio_language: "eng".into(),
+            subtitle_language: "eng".into(),
+            menu_language: "eng".into(),
+            country: "us".into(),
+            region: 1,
+            parental_level: 255,
+            follow_title_jumps: false,
+            step_limit: 10_000,
+            trace: false,
+        }
+    }
+}
+
+/// Why the simulation stopped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VmStop {
+    /// A `Break` command was executed, the object ran past its last command
+    /// or `Resume` was executed without an object to resume.
+    Finished,
+    /// A jump to (or call of) the given title, which wasn't followed.
+    Title(u16),
+    /// The given title is a BD-J title, which can't be simulated.
+    BdJTitle(u16),
+    /// A command referred to a movie object that doesn't exist.
+    InvalidObject(u32),
+    /// A command referred to a title that doesn't exist.
+    InvalidTitle(u32),
+    /// The step limit was reached, most likely because of an endless loop.
+    StepLimit,
+}
+
+/// Where playback of a playlist starts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlayStart {
+    Beginning,
+    PlayItem(u32),
+    Mark(u32),
+}
+
+/// A playlist that was played during the simulation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlayedPlaylist {
+    /// The playlist number, e.g. 800 for `00800.mpls`.
+    pub playlist: u16,
+    pub start: PlayStart,
+    /// The movie object that played the playlist.
+    pub object: u16,
+}
+
+/// A single executed command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub object: u16,
+    /// The index of the command within the object.
+    pub command: usize,
+    pub instruction: Instruction,
+}
+
+impl Display for TraceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>5}:{:<4} {}",
+            self.object, self.command, self.instruction
+        )
+    }
+}
+
+/// The outcome of a simulation.
+#[derive(Debug, Clone)]
+pub struct VmResult {
+    /// The played playlists, in order.
+    pub played: Vec<PlayedPlaylist>,
+    pub stop: VmStop,
+    /// The number of executed commands.
+    pub steps: usize,
+    /// The executed commands, if tracing was enabled.
+    pub trace: Vec<TraceStep>,
+}
+
+impl VmResult {
+    /// Lists the numbers of the played playlists in order of their first
+    /// playback, without duplicates.
+    pub fn playlists(&self) -> Vec<u16> {
+        let mut playlists = Vec::new();
+        for p in self.played.iter() {
+            if !playlists.contains(&p.playlist) {
+                playlists.push(p.playlist);
+            }
+        }
+        playlists
+    }
+}
+
+/// The HDMV virtual machine.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> Result<(), mpls::MplsError> {
+/// use std::fs::File;
+/// use mpls::vm::{Vm, VmConfig};
+/// use mpls::{Index, MovieObjects};
+///
+/// let index = Index::from(File::open("BDMV/index.bdmv")?)?;
+/// let mobj = MovieObjects::from(File::open("BDMV/MovieObject.bdmv")?)?;
+/// let config = VmConfig {
+///     audio_language: "deu".into(),
+///     region: 2,
+///     ..VmConfig::default()
+/// };
+/// let mut vm = Vm::new(&index, &mobj, config);
+/// let result = vm.run_title(1);
+/// println!("title 1 plays {:?} ({:?})", result.playlists(), result.stop);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Vm<'a> {
+    index: &'a Index,
+    movie_objects: &'a MovieObjects,
+    config: VmConfig,
+    /// The general purpose registers.
+    pub gpr: Vec<u32>,
+    /// The player status registers.
+    pub psr: Vec<u32>,
+    rng: u32,
+}
+
+// packs an ASCII code such as "eng" into a register value, or returns the
+// "unspecified" value if it's invalid
+fn language_code(code: &str, len: usize) -> u32 {
+    if code.len() != len || !code.is_ascii() {
+        return (1 << (8 * len)) - 1;
+    }
+    code.bytes().fold(0, |acc, b| (acc << 8) | b as u32)
+}
+
+impl<'a> Vm<'a> {
+    /// Creates a virtual machine with all general purpose registers set to
+    /// zero and the player status registers set up according to `config`.
+    pub fn new(index: &'a Index, movie_objects: &'a MovieObjects, config: VmConfig) -> Vm<'a> {
+        let mut psr = vec![0; PSR_COUNT];
+        psr[0] = 1; // interactive graphics stream
+        psr[1] = 0xFF; // primary audio stream
+        psr[2] = 0x0FFF_0FFF; // presentation graphics stream
+        psr[3] = 1; // angle
+        psr[4] = 0xFFFF; // title
+        psr[5] = 0xFFFF; // chapter
+        psr[10] = 0xFFFF; // selected button
+        psr[12] = 0xFF; // text subtitle style
+        psr[13] = config.parental_level;
+        psr[14] = 0xFFFF; // secondary audio and video streams
+        psr[15] = 0x10F0; // audio capability
+        psr[16] = language_code(&config.audio_language, 3);
+        psr[17] = language_code(&config.subtitle_language, 3);
+        psr[18] = language_code(&config.menu_language, 3);
+        psr[19] = language_code(&config.country, 2);
+        psr[20] = config.region;
+        psr[29] = 0x03; // video capability
+        psr[30] = 0x1FFF; // text subtitle capability
+        psr[31] = 0x0003_0250; // player profile 5, version 2.5
+
+        Vm {
+            index,
+            movie_objects,
+            config,
+            gpr: vec![0; GPR_COUNT],
+            psr,
+            rng: 0x2545_F491,
+        }
+    }
+
+    /// Runs the given title, starting at 1.
+    pub fn run_title(&mut self, number: u16) -> VmResult {
+        let mut result = VmResult {
+            played: Vec::new(),
+            stop: VmStop::Finished,
+            steps: 0,
+            trace: Vec::new(),
+        };
+        match self.title_object(number as u32) {
+            Ok(object) => {
+                self.psr[4] = number as u32;
+                result.stop = self.execute(object, &mut result);
+            }
+            Err(stop) => result.stop = stop,
+        }
+        result
+    }
+
+    /// Runs the first playback object of the index table.
+    pub fn run_first_playback(&mut self) -> VmResult {
+        let object = self.index.first_playback.clone();
+        self.run_index_object(object, 0xFFFF)
+    }
+
+    /// Runs the top menu object of the index table.
+    pub fn run_top_menu(&mut self) -> VmResult {
+        // the top menu is title 0
+        let object = self.index.top_menu.clone();
+        self.run_index_object(object, 0)
+    }
+
+    /// Runs the movie object with the given index.
+    pub fn run_object(&mut self, object: u16) -> VmResult {
+        let mut result = VmResult {
+            played: Vec::new(),
+            stop: VmStop::Finished,
+            steps: 0,
+            trace: Vec::new(),
+        };
+        result.stop = self.execute(object as u32, &mut result);
+        result
+    }
+
+    fn run_index_object(&mut self, object: Option<IndexObject>, title: u16) -> VmResult {
+        match object {
+            Some(IndexObject::Hdmv { movie_object, .. }) => {
+                self.psr[4] = title as u32;
+                self.run_object(movie_object)
+            }
+            Some(IndexObject::BdJ { .. }) => VmResult {
+                played: Vec::new(),
+                stop: VmStop::BdJTitle(title),
+                steps: 0,
+                trace: Vec::new(),
+            },
+            None => VmResult {
+                played: Vec::new(),
+                stop: VmStop::Finished,
+                steps: 0,
+                trace: Vec::new(),
+            },
+        }
+    }
+
+    // the movie object of a title
+    fn title_object(&self, number: u32) -> Result<u32, VmStop> {
+        let title = u16::try_from(number)
+            .ok()
+            .and_then(|n| self.index.title(n))
+            .ok_or(VmStop::InvalidTitle(number))?;
+        match &title.object {
+            IndexObject::Hdmv { movie_object, .. } => Ok(*movie_object as u32),
+            IndexObject::BdJ { .. } => Err(VmStop::BdJTitle(number as u16)),
+        }
+    }
+
+    fn read(&self, operand: Operand) -> u32 {
+        match operand {
+            Operand::Immediate(n) => n,
+            Operand::Gpr(n) => self.gpr.get(n as usize).copied().unwrap_or(0),
+            Operand::Psr(n) => self.psr.get(n as usize).copied().unwrap_or(0),
+        }
+    }
+
+    // movie objects can only write general purpose registers
+    fn write(&mut self, operand: Operand, value: u32) {
+        if let Operand::Gpr(n) = operand {
+            if let Some(r) = self.gpr.get_mut(n as usize) {
+                *r = value;
+            }
+        }
+    }
+
+    fn random(&mut self, max: u32) -> u32 {
+        // xorshift, so that runs are reproducible
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        if max == 0 {
+            0
+        } else {
+            self.rng % max + 1
+        }
+    }
+
+    fn compare(&self, op: CompareOp, a: u32, b: u32) -> bool {
+        match op {
+            CompareOp::Bc => a & b == b,
+            CompareOp::Eq => a == b,
+            CompareOp::Ne => a != b,
+            CompareOp::Ge => a >= b,
+            CompareOp::Gt => a > b,
+            CompareOp::Le => a <= b,
+            CompareOp::Lt => a < b,
+        }
+    }
+
+    fn set(&mut self, op: SetOp, dst: Operand, src: Operand) {
+        let a = self.read(dst);
+        let b = self.read(src);
+        let value = match op {
+            SetOp::Move => b,
+            SetOp::Swap => {
+                self.write(src, a);
+                b
+            }
+            SetOp::Add => a.saturating_add(b),
+            SetOp::Sub => a.saturating_sub(b),
+            SetOp::Mul => a.saturating_mul(b),
+            SetOp::Div => a.checked_div(b).unwrap_or(a),
+            SetOp::Mod => a.checked_rem(b).unwrap_or(a),
+            SetOp::Rnd => self.random(b),
+            SetOp::And => a & b,
+            SetOp::Or => a | b,
+            SetOp::Xor => a ^ b,
+            SetOp::BitSet => a | 1u32.checked_shl(b).unwrap_or(0),
+            SetOp::BitClear => a & !1u32.checked_shl(b).unwrap_or(0),
+            SetOp::ShiftLeft => a.checked_shl(b).unwrap_or(0),
+            SetOp::ShiftRight => a.checked_shr(b).unwrap_or(0),
+        };
+        self.write(dst, value);
+    }
+
+    fn play(&mut self, result: &mut VmResult, object: u32, playlist: u32, start: PlayStart) {
+        if let Ok(playlist) = u16::try_from(playlist) {
+            self.psr[6] = playlist as u32;
+            result.played.push(PlayedPlaylist {
+                playlist,
+                start,
+                object: object as u16,
+            });
+        }
+    }
+
+    fn execute(&mut self, mut object: u32, result: &mut VmResult) -> VmStop {
+        let mut pc = 0;
+        // the objects and commands to return to with `Resume`
+        let mut calls: Vec<(u32, usize)> = Vec::new();
+        loop {
+            let commands = match self.movie_objects.objects.get(object as usize) {
+                Some(o) => &o.commands,
+                None => return VmStop::InvalidObject(object),
+            };
+            let command = match commands.get(pc) {
+                Some(c) => c,
+                None => return VmStop::Finished,
+            };
+            if result.steps >= self.config.step_limit {
+                return VmStop::StepLimit;
+            }
+            result.steps += 1;
+
+            let instruction = command.instruction();
+            if self.config.trace {
+                result.trace.push(TraceStep {
+                    object: object as u16,
+                    command: pc,
+                    instruction,
+                });
+            }
+            pc += 1;
+
+            match instruction {
+                Instruction::Goto(target) => pc = self.read(target) as usize,
+                Instruction::Break => return VmStop::Finished,
+                Instruction::JumpObject(target) => {
+                    object = self.read(target);
+                    pc = 0;
+                }
+                Instruction::CallObject(target) => {
+                    calls.push((object, pc));
+                    object = self.read(target);
+                    pc = 0;
+                }
+                Instruction::JumpTitle(target) | Instruction::CallTitle(target) => {
+                    let title = self.read(target);
+                    if !self.config.follow_title_jumps {
+                        return VmStop::Title(title as u16);
+                    }
+                    if let Instruction::CallTitle(_) = instruction {
+                        calls.push((object, pc));
+                    } else {
+                        calls.clear();
+                    }
+                    object = match self.title_object(title) {
+                        Ok(o) => o,
+                        Err(stop) => return stop,
+                    };
+                    self.psr[4] = title;
+                    pc = 0;
+                }
+                Instruction::Resume => match calls.pop() {
+                    Some((o, p)) => {
+                        object = o;
+                        pc = p;
+                    }
+                    None => return VmStop::Finished,
+                },
+                Instruction::PlayPl(playlist) => {
+                    let playlist = self.read(playlist);
+                    self.play(result, object, playlist, PlayStart::Beginning);
+                }
+                Instruction::PlayPlAtPlayItem(playlist, item) => {
+                    let (playlist, item) = (self.read(playlist), self.read(item));
+                    self.play(result, object, playlist, PlayStart::PlayItem(item));
+                }
+                Instruction::PlayPlAtMark(playlist, mark) => {
+                    let (playlist, mark) = (self.read(playlist), self.read(mark));
+                    self.play(result, object, playlist, PlayStart::Mark(mark));
+                }
+                Instruction::Compare(op, a, b) => {
+                    if !self.compare(op, self.read(a), self.read(b)) {
+                        pc += 1;
+                    }
+                }
+                Instruction::Set(op, dst, src) => self.set(op, dst, src),
+                // only meaningful during playback or for menus
+                Instruction::Nop
+                | Instruction::TerminatePl
+                | Instruction::LinkPlayItem(_)
+                | Instruction::LinkMark(_)
+                | Instruction::SetSystem(..)
+                | Instruction::Unknown(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use super::types::{
+    AngleInfo, AppInfoPlayList, AudioFormat, CharacterCode, Clip, ColorSpace, DynamicRange,
+    ExtensionDataEntry, FrameRate, MarkType, Mpls, PlayItem, PlayList, PlayListMark, PlaybackType,
+    Ref, SampleRate, Stream, StreamAttributes, StreamEntry, StreamEntryRef, StreamNumberTable,
+    StreamType, SubPath, SubPlayItem, VideoFormat,
+};
+use crate::MplsError;
+use std::{convert::TryFrom, io};
+
+// every stream entry and stream attributes block on the discs we've seen is
+// padded to these lengths, so we do the same
+const STREAM_ENTRY_LEN: usize = 9;
+const STREAM_ATTRS_LEN: usize = 5;
+
+// fails if `n` doesn't fit into the field that stores it
+fn count<T: TryFrom<usize>>(n: usize, what: &str) -> Result<T, MplsError> {
+    T::try_from(n).map_err(|_| {
+        MplsError::IoError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("too many {} ({})", what, n),
+        ))
+    })
+}
+
+fn u16_len(buf: &mut Vec<u8>, body: Vec<u8>) -> Result<(), MplsError> {
+    buf.extend_from_slice(&count::<u16>(body.len(), "bytes in a play item")?.to_be_bytes());
+    buf.extend(body);
+    Ok(())
+}
+
+pub(crate) fn u32_len(buf: &mut Vec<u8>, body: Vec<u8>) {
+    buf.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    buf.extend(body);
+}
+
+fn padded_u8_len(buf: &mut Vec<u8>, mut body: Vec<u8>, len: usize) {
+    if body.len() < len {
+        body.resize(len, 0);
+    }
+    buf.push(body.len() as u8);
+    buf.extend(body);
+}
+
+// writes exactly `len` bytes of `s`, padding with zeroes if necessary
+pub(crate) fn str_len(buf: &mut Vec<u8>, s: &str, len: usize) {
+    let mut bytes = s.as_bytes().to_vec();
+    bytes.resize(len, 0);
+    buf.extend(bytes);
+}
+
+fn clip(buf: &mut Vec<u8>, clip: &Clip) {
+    str_len(buf, &clip.file_name, 5);
+    str_len(buf, &clip.codec_id, 4);
+}
+
+fn clip_with_clock_ref(buf: &mut Vec<u8>, c: &Clip) {
+    clip(buf, c);
+    buf.push(c.stc_id);
+}
+
+fn stream_entry(buf: &mut Vec<u8>, entry: &StreamEntry) {
+    fn id(r: &Ref) -> u16 {
+        match r {
+            Ref::SubPath(r) => r.0 as u16,
+            Ref::SubClip(r) => r.0 as u16,
+            Ref::PlayItem(r) => r.0,
+            Ref::Stream(r) => r.0,
+        }
+    }
+
+    let mut body = vec![entry.stream_type];
+    match &entry.refs {
+        StreamEntryRef::PlayItem(pid) => body.extend_from_slice(&id(pid).to_be_bytes()),
+        StreamEntryRef::SubPathKind1(sub_path, sub_clip, pid) => {
+            body.push(id(sub_path) as u8);
+            body.push(id(sub_clip) as u8);
+            body.extend_from_slice(&id(pid).to_be_bytes());
+        }
+        StreamEntryRef::SubPathKind2(sub_path, pid) => {
+            body.push(id(sub_path) as u8);
+            body.extend_from_slice(&id(pid).to_be_bytes());
+        }
+    }
+    padded_u8_len(buf, body, STREAM_ENTRY_LEN);
+}
+
+fn stream_attrs(buf: &mut Vec<u8>, attrs: &StreamAttributes) {
+    fn video_format(format: &VideoFormat, frame_rate: &FrameRate) -> u8 {
+        let format = match format {
+            VideoFormat::Interlaced480 => 0x1,
+            VideoFormat::Interlaced576 => 0x2,
+            VideoFormat::Progressive480 => 0x3,
+            VideoFormat::Interlaced1080 => 0x4,
+            VideoFormat::Progressive720 => 0x5,
+            VideoFormat::Progressive1080 => 0x6,
+            VideoFormat::Progressive576 => 0x7,
+            VideoFormat::Progressive2160 => 0x8,
+            VideoFormat::Unknown => 0x0,
+        };
+        let rate = match frame_rate.map(|f| (f.numerator, f.denominator)) {
+            Some((24_000, 1_001)) => 0x1,
+            Some((24, 1)) => 0x2,
+            Some((25, 1)) => 0x3,
+            Some((30_000, 1_001)) => 0x4,
+            Some((50, 1)) => 0x6,
+            Some((60_000, 1_001)) => 0x7,
+            _ => 0x0,
+        };
+        (format << 4) | rate
+    }
+    fn dyn_range_col_space(range: &DynamicRange, space: &ColorSpace) -> u8 {
+        let range = match range {
+            DynamicRange::Sdr => 0x0,
+            DynamicRange::Hdr10 => 0x1,
+            DynamicRange::DolbyVision => 0x2,
+            DynamicRange::Unknown => 0xF,
+        };
+        let space = match space {
+            ColorSpace::BT709 => 0x1,
+            ColorSpace::BT2020 => 0x2,
+            ColorSpace::Unknown => 0x0,
+        };
+        (range << 4) | space
+    }
+    fn audio_format(format: &AudioFormat, rate: &SampleRate) -> u8 {
+        let format = match format {
+            AudioFormat::Mono => 0x1,
+            AudioFormat::Stereo => 0x3,
+            AudioFormat::Multichannel => 0x6,
+            AudioFormat::StereoAndMultichannel => 0xC,
+            AudioFormat::Unknown => 0x0,
+        };
+        let rate = match rate {
+            SampleRate::One(48_000) => 0x1,
+            SampleRate::One(96_000) => 0x4,
+            SampleRate::One(192_000) => 0x5,
+            SampleRate::Two(48_000, 192_000) => 0xC,
+            SampleRate::Two(48_000, 96_000) => 0xE,
+            _ => 0x0,
+        };
+        (format << 4) | rate
+    }
+    fn char_code(code: &CharacterCode) -> u8 {
+        match code {
+            CharacterCode::Utf8 => 0x1,
+            CharacterCode::Utf16BE => 0x2,
+            CharacterCode::ShiftJIS => 0x3,
+            CharacterCode::EucKr => 0x4,
+            CharacterCode::Gb18030 => 0x5,
+            CharacterCode::EucCn => 0x6,
+            CharacterCode::Big5 => 0x7,
+            CharacterCode::Unknown => 0x0,
+        }
+    }
+
+    let mut body = vec![attrs.coding_type];
+    match &attrs.stream_type {
+        StreamType::SdrVideo(v, f) => body.push(video_format(v, f)),
+        StreamType::HdrVideo(v, f, d, c) => {
+            body.push(video_format(v, f));
+            body.push(dyn_range_col_space(d, c));
+        }
+        StreamType::Audio(a, s, l) => {
+            body.push(audio_format(a, s));
+            str_len(&mut body, l, 3);
+        }
+        StreamType::Graphics(l) => str_len(&mut body, l, 3),
+        StreamType::Text(l, c) => {
+            body.push(char_code(c));
+            str_len(&mut body, l, 3);
+        }
+        StreamType::Unknown => {}
+    }
+    padded_u8_len(buf, body, STREAM_ATTRS_LEN);
+}
+
+fn stream(buf: &mut Vec<u8>, stream: &Stream) {
+    stream_entry(buf, &stream.entry);
+    stream_attrs(buf, &stream.attrs);
+}
+
+fn stream_number_table(buf: &mut Vec<u8>, stn: &StreamNumberTable) -> Result<(), MplsError> {
+    let mut body = vec![0, 0];
+    body.extend_from_slice(&[
+        count(stn.primary_video_streams.len(), "primary video streams")?,
+        count(stn.primary_audio_streams.len(), "primary audio streams")?,
+        count(stn.primary_pgs_streams.len(), "primary PGS streams")?,
+        count(stn.primary_igs_streams.len(), "primary IGS streams")?,
+        count(stn.secondary_audio_streams.len(), "secondary audio streams")?,
+        count(stn.secondary_video_streams.len(), "secondary video streams")?,
+        count(stn.secondary_pgs_streams.len(), "secondary PGS streams")?,
+        count(stn.dolby_vision_streams.len(), "Dolby Vision streams")?,
+    ]);
+    body.extend_from_slice(&[0; 4]);
+    // same order as the parser reads them
+    for streams in &[
+        &stn.primary_video_streams,
+        &stn.primary_audio_streams,
+        &stn.primary_pgs_streams,
+        &stn.primary_igs_streams,
+        &stn.secondary_video_streams,
+        &stn.secondary_audio_streams,
+        &stn.secondary_pgs_streams,
+        &stn.dolby_vision_streams,
+    ] {
+        for s in streams.iter() {
+            stream(&mut body, s);
+        }
+    }
+    u16_len(buf, body)
+}
+
+fn sub_play_item(buf: &mut Vec<u8>, item: &SubPlayItem) -> Result<(), MplsError> {
+    let mut body = Vec::new();
+    clip(&mut body, &item.clip);
+    let is_multi_clip = !item.multi_clip_entries.is_empty();
+    let flags = ((item.connection_condition as u32 & 0x0F) << 1) | is_multi_clip as u32;
+    body.extend_from_slice(&flags.to_be_bytes());
+    body.push(item.clip.stc_id);
+    body.extend_from_slice(&item.in_time.0.to_be_bytes());
+    body.extend_from_slice(&item.out_time.0.to_be_bytes());
+    body.extend_from_slice(&item.sync_play_item_id.to_be_bytes());
+    body.extend_from_slice(&item.sync_start_pts.to_be_bytes());
+    if is_multi_clip {
+        // the main clip is counted as well
+        body.push(count(
+            item.multi_clip_entries.len() + 1,
+            "clips in a sub play item",
+        )?);
+        body.push(0);
+        for c in item.multi_clip_entries.iter() {
+            clip_with_clock_ref(&mut body, c);
+        }
+    }
+    u16_len(buf, body)
+}
+
+fn sub_path(buf: &mut Vec<u8>, path: &SubPath) -> Result<(), MplsError> {
+    let mut body = vec![0, path.sub_path_type];
+    body.extend_from_slice(&(path.is_repeat as u16).to_be_bytes());
+    body.push(0);
+    body.push(count(path.play_items.len(), "sub play items")?);
+    for item in path.play_items.iter() {
+        sub_play_item(&mut body, item)?;
+    }
+    u32_len(buf, body);
+    Ok(())
+}
+
+fn play_item_angles(buf: &mut Vec<u8>, info: &AngleInfo, clips: &[Clip]) -> Result<(), MplsError> {
+    // the main clip is counted as well
+    buf.push(count(clips.len() + 1, "angles")?);
+    buf.push(((info.is_different_audios as u8) << 1) | info.is_seamless_angle_change as u8);
+    for c in clips.iter() {
+        clip_with_clock_ref(buf, c);
+    }
+    Ok(())
+}
+
+fn play_item(buf: &mut Vec<u8>, item: &PlayItem) -> Result<(), MplsError> {
+    let mut body = Vec::new();
+    clip(&mut body, &item.clip);
+    let is_multi_angle = item.angle_info.is_some();
+    let flags = ((is_multi_angle as u16) << 4) | (item.connection_condition as u16 & 0x0F);
+    body.extend_from_slice(&flags.to_be_bytes());
+    body.push(item.clip.stc_id);
+    body.extend_from_slice(&item.in_time.0.to_be_bytes());
+    body.extend_from_slice(&item.out_time.0.to_be_bytes());
+    body.extend_from_slice(&item.user_opt_mask.to_be_bytes());
+    body.push((item.random_access_flag as u8) << 7);
+    body.push(item.still_mode);
+    body.extend_from_slice(&item.still_time.to_be_bytes());
+    if let Some(info) = &item.angle_info {
+        play_item_angles(&mut body, info, &item.angles)?;
+    }
+    stream_number_table(&mut body, &item.stream_number_table)?;
+    u16_len(buf, body)
+}
+
+fn play_list(buf: &mut Vec<u8>, list: &PlayList) -> Result<(), MplsError> {
+    let mut body = vec![0, 0];
+    body.extend_from_slice(&count::<u16>(list.play_items.len(), "play items")?.to_be_bytes());
+    body.extend_from_slice(&count::<u16>(list.sub_paths.len(), "sub paths")?.to_be_bytes());
+    for item in list.play_items.iter() {
+        play_item(&mut body, item)?;
+    }
+    for path in list.sub_paths.iter() {
+        sub_path(&mut body, path)?;
+    }
+    u32_len(buf, body);
+    Ok(())
+}
+
+fn app_info_play_list(buf: &mut Vec<u8>, info: &AppInfoPlayList) {
+    let playback_type = match info.playback_type {
+        PlaybackType::Standard => 0x1,
+        PlaybackType::Random => 0x2,
+        PlaybackType::Shuffle => 0x3,
+        PlaybackType::Unknown => 0x0,
+    };
+    let mut body = vec![0, playback_type];
+    body.extend_from_slice(&info.playback_count.unwrap_or(0).to_be_bytes());
+    body.extend_from_slice(&info.user_opt_mask.to_be_bytes());
+    body.extend_from_slice(&info.flags.to_be_bytes());
+    u32_len(buf, body);
+}
+
+fn play_list_mark(buf: &mut Vec<u8>, marks: &[PlayListMark]) -> Result<(), MplsError> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&count::<u16>(marks.len(), "marks")?.to_be_bytes());
+    for mark in marks.iter() {
+        body.push(0);
+        body.push(match mark.mark_type {
+            MarkType::EntryPoint => 0x1,
+            MarkType::LinkPoint => 0x2,
+            MarkType::Unknown => 0x0,
+        });
+        body.extend_from_slice(&mark.play_item.0.to_be_bytes());
+        body.extend_from_slice(&mark.time_stamp.0.to_be_bytes());
+        body.extend_from_slice(&mark.entry_es_pid.to_be_bytes());
+        body.extend_from_slice(&mark.duration.map(|d| d.0).unwrap_or(0).to_be_bytes());
+    }
+    u32_len(buf, body);
+    Ok(())
+}
+
+pub(crate) fn extension_data(buf: &mut Vec<u8>, entries: &[ExtensionDataEntry]) {
+    // the addresses are relative to the start of the section, including the
+    // length field: length (4), data block start (4), reserved + count (4),
+    // then 12 bytes for every entry
+    let data_start = 12 + 12 * entries.len() as u32;
+    let mut body = Vec::new();
+    body.extend_from_slice(&data_start.to_be_bytes());
+    body.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    let mut addr = data_start;
+    for entry in entries.iter() {
+        body.extend_from_slice(&entry.data_type.to_be_bytes());
+        body.extend_from_slice(&entry.data_version.to_be_bytes());
+        body.extend_from_slice(&addr.to_be_bytes());
+        body.extend_from_slice(&(entry.data.len() as u32).to_be_bytes());
+        addr += entry.data.len() as u32;
+    }
+    for entry in entries.iter() {
+        body.extend_from_slice(&entry.data);
+    }
+    u32_len(buf, body);
+}
+
+pub fn write_mpls(mpls: &Mpls) -> Result<Vec<u8>, MplsError> {
+    let mut sections = Vec::new();
+    app_info_play_list(&mut sections, &mpls.app_info_play_list);
+    let play_list_addr = 40 + sections.len() as u32;
+    play_list(&mut sections, &mpls.play_list)?;
+    let marks_addr = 40 + sections.len() as u32;
+    play_list_mark(&mut sections, &mpls.marks)?;
+    let ext_addr = if mpls.ext.is_empty() {
+        0
+    } else {
+        let addr = 40 + sections.len() as u32;
+        extension_data(&mut sections, &mpls.ext);
+        addr
+    };
+
+    let mut buf = Vec::with_capacity(40 + sections.len());
+    buf.extend_from_slice(b"MPLS");
+    str_len(&mut buf, &mpls.version, 4);
+    buf.extend_from_slice(&play_list_addr.to_be_bytes());
+    buf.extend_from_slice(&marks_addr.to_be_bytes());
+    buf.extend_from_slice(&ext_addr.to_be_bytes());
+    buf.extend_from_slice(&[0; 20]);
+    buf.extend(sections);
+    Ok(buf)
+}
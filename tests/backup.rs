@@ -0,0 +1,101 @@
+mod common;
+
+use common::{write, TempDir};
+use mpls::backup::FileKind;
+use mpls::{Disc, Mpls};
+use std::path::Path;
+
+fn disc() -> TempDir {
+    let dir = TempDir::new();
+    let root = dir.path();
+    write(
+        root,
+        "BDMV/PLAYLIST/00800.mpls",
+        include_bytes!("../assets/simple.mpls"),
+    );
+    write(
+        root,
+        "BDMV/PLAYLIST/00801.mpls",
+        include_bytes!("../assets/multi-angle.mpls"),
+    );
+    write(root, "BDMV/STREAM/00055.m2ts", &[0; 192 * 10]);
+    write(root, "BDMV/CLIPINF/00055.clpi", &[0; 100]);
+    write(root, "BDMV/STREAM/00059.m2ts", &[0; 192 * 5]);
+    write(root, "BDMV/STREAM/SSIF/00059.ssif", &[0; 192 * 8]);
+    write(root, "BDMV/CLIPINF/00059.clpi", &[0; 80]);
+    write(root, "BDMV/CLIPINF/00061.clpi", &[0; 60]);
+    dir
+}
+
+#[test]
+fn plan_files_and_sizes() {
+    let dir = disc();
+    let disc = Disc::open(dir.path()).unwrap();
+    let plan = disc.backup_plan(800, None).unwrap();
+
+    assert_eq!(plan.clips, &["00055", "00059", "00061"]);
+    let files: Vec<(FileKind, &Path)> = plan
+        .files
+        .iter()
+        .map(|f| (f.kind, f.relative_path.as_path()))
+        .collect();
+    assert_eq!(
+        files,
+        &[
+            (FileKind::Playlist, Path::new("PLAYLIST/00800.mpls")),
+            (FileKind::ClipInfo, Path::new("CLIPINF/00055.clpi")),
+            (FileKind::Stream, Path::new("STREAM/00055.m2ts")),
+            (FileKind::ClipInfo, Path::new("CLIPINF/00059.clpi")),
+            (FileKind::Stream, Path::new("STREAM/00059.m2ts")),
+            (FileKind::Ssif, Path::new("STREAM/SSIF/00059.ssif")),
+            (FileKind::ClipInfo, Path::new("CLIPINF/00061.clpi")),
+        ]
+    );
+    assert_eq!(plan.files[0].clip, None);
+    assert_eq!(plan.files[1].clip.as_deref(), Some("00055"));
+
+    let playlist_size = include_bytes!("../assets/simple.mpls").len() as u64;
+    assert_eq!(
+        plan.total_size(),
+        playlist_size + 1920 + 100 + 960 + 1536 + 80 + 60
+    );
+
+    assert!(!plan.is_complete());
+    assert_eq!(plan.missing, &[disc.root.join("STREAM/00061.m2ts")]);
+}
+
+#[test]
+fn restrict_to_angle() {
+    let dir = disc();
+    let disc = Disc::open(dir.path()).unwrap();
+    let mpls = Mpls::from(&include_bytes!("../assets/multi-angle.mpls")[..]).unwrap();
+
+    let all = disc.backup_plan(801, None).unwrap();
+    let first = disc.backup_plan(801, Some(0)).unwrap();
+    let second = disc.backup_plan(801, Some(1)).unwrap();
+    assert_eq!(second.angle, Some(1));
+
+    assert!(first.clips.contains(&"00082".to_string()));
+    assert!(!first.clips.contains(&"00083".to_string()));
+    assert!(second.clips.contains(&"00083".to_string()));
+    assert!(!second.clips.contains(&"00082".to_string()));
+    assert!(first.clips.len() < all.clips.len());
+    assert_eq!(
+        all.clips.len(),
+        mpls.clips()
+            .iter()
+            .map(|c| &c.file_name)
+            .collect::<std::collections::BTreeSet<_>>()
+            .len()
+    );
+    // nothing but the playlist exists for this one
+    assert_eq!(first.files.len(), 1);
+    assert_eq!(first.missing.len(), first.clips.len() * 2);
+}
+
+#[test]
+fn unknown_playlist() {
+    let dir = disc();
+    let disc = Disc::open(dir.path()).unwrap();
+    assert!(disc.backup_plan(1, None).is_none());
+}
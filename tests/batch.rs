@@ -0,0 +1,130 @@
+#![cfg(feature = "rayon")]
+
+mod common;
+
+use common::{write, TempDir};
+use mpls::batch::{self, BatchOptions};
+use std::sync::Mutex;
+
+fn library() -> TempDir {
+    let dir = TempDir::new();
+    let root = dir.path();
+    let spec = common::ClpiSpec {
+        presentation_start: 27_000_000,
+        presentation_end: 27_011_261,
+        source_packets: 1_000,
+        entry_points: vec![(27_000_000, 0)],
+    };
+    write(
+        root,
+        "a/BDMV/PLAYLIST/00800.mpls",
+        include_bytes!("../assets/simple.mpls"),
+    );
+    write(
+        root,
+        "a/BDMV/PLAYLIST/00801.MPLS",
+        include_bytes!("../assets/multi-angle.mpls"),
+    );
+    write(
+        root,
+        "a/BDMV/CLIPINF/00055.clpi",
+        &common::clpi_bytes(&spec),
+    );
+    write(root, "a/BDMV/STREAM/00055.m2ts", &[0; 192]);
+    write(
+        root,
+        "b/BDMV/PLAYLIST/00000.mpls",
+        b"MPLS0200 but not really",
+    );
+    write(root, "b/BDMV/PLAYLIST/00001.mpls", b"");
+    write(root, "b/notes.txt", b"ignored");
+    dir
+}
+
+#[test]
+fn find_files() {
+    let dir = library();
+    let files = batch::find_files(dir.path()).unwrap();
+    let relative: Vec<_> = files
+        .iter()
+        .map(|p| p.strip_prefix(dir.path()).unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(
+        relative,
+        &[
+            "a/BDMV/CLIPINF/00055.clpi",
+            "a/BDMV/PLAYLIST/00800.mpls",
+            "a/BDMV/PLAYLIST/00801.MPLS",
+            "b/BDMV/PLAYLIST/00000.mpls",
+            "b/BDMV/PLAYLIST/00001.mpls",
+        ]
+    );
+    assert!(batch::find_files(dir.path().join("missing")).is_err());
+}
+
+#[test]
+fn parse_files() {
+    let dir = library();
+    let files = batch::find_files(dir.path()).unwrap();
+    let options = BatchOptions {
+        threads: Some(2),
+        ..BatchOptions::default()
+    };
+    let results = batch::parse_files(&files, &options).unwrap();
+
+    let paths: Vec<_> = results.iter().map(|r| r.path.clone()).collect();
+    assert_eq!(paths, files);
+    let clpi = results[0].result.as_ref().unwrap().clpi().unwrap();
+    assert_eq!(clpi.clip_info.number_of_source_packets, 1_000);
+    let mpls = results[1].result.as_ref().unwrap().mpls().unwrap();
+    assert_eq!(mpls.play_list.play_items.len(), 3);
+    assert_eq!(
+        results[1].size,
+        include_bytes!("../assets/simple.mpls").len() as u64
+    );
+    assert!(results[2].result.as_ref().unwrap().mpls().is_some());
+    assert!(results[3].result.is_err());
+    assert!(results[4].result.is_err());
+    assert_eq!(results[4].size, 0);
+
+    // the global thread pool works as well
+    let results = batch::parse_files(&files, &BatchOptions::default()).unwrap();
+    assert_eq!(results.iter().filter(|r| r.result.is_ok()).count(), 3);
+}
+
+#[test]
+fn parse_file_limits_size() {
+    let dir = library();
+    let path = dir.path().join("a/BDMV/PLAYLIST/00800.mpls");
+    let result = batch::parse_file(&path, 100);
+    assert!(result.result.is_err());
+    assert!(result.size > 100);
+    assert!(batch::parse_file(&path, result.size).result.is_ok());
+
+    let result = batch::parse_file(dir.path().join("missing.mpls"), 100);
+    assert!(result.result.is_err());
+    assert_eq!(result.size, 0);
+}
+
+#[test]
+fn for_each_file() {
+    let dir = library();
+    let files = batch::find_files(dir.path()).unwrap();
+    let parsed = Mutex::new(Vec::new());
+    let options = BatchOptions {
+        threads: Some(3),
+        ..BatchOptions::default()
+    };
+    batch::for_each_file(&files, &options, |file| {
+        parsed
+            .lock()
+            .unwrap()
+            .push((file.path, file.result.is_ok()));
+    })
+    .unwrap();
+
+    let mut parsed = parsed.into_inner().unwrap();
+    parsed.sort();
+    let ok: Vec<bool> = parsed.iter().map(|(_, ok)| *ok).collect();
+    assert_eq!(ok, &[true, true, true, false, false]);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::bdjo_bytes;
+use mpls::Bdjo;
+
+#[test]
+fn parse_bdjo() {
+    let bytes = bdjo_bytes(false, true, &["00800", "00010"]);
+    let bdjo = Bdjo::from(&bytes[..]).unwrap();
+
+    assert_eq!(bdjo.version, "0200");
+    assert_eq!(bdjo.terminal_info.default_font_file_name, "00000");
+    assert_eq!(bdjo.terminal_info.initial_havi_configuration_id, 3);
+    assert!(bdjo.terminal_info.menu_call_mask);
+    assert!(!bdjo.terminal_info.title_search_mask);
+    assert_eq!(bdjo.app_cache_info.len(), 1);
+    assert_eq!(bdjo.app_cache_info[0].entry_type, 1);
+    assert_eq!(bdjo.app_cache_info[0].name, "00000");
+    assert_eq!(bdjo.app_cache_info[0].language, "*.*");
+    assert!(!bdjo.accessible_playlists.access_to_all);
+    assert_eq!(bdjo.accessible_playlists.playlists, &["00800", "00010"]);
+    assert_eq!(bdjo.application_management_table.len(), 10);
+
+    assert_eq!(bdjo.playlists(), &[800, 10]);
+    assert_eq!(bdjo.autostart_playlist(), Some(800));
+}
+
+#[test]
+fn no_autostart() {
+    let bytes = bdjo_bytes(true, false, &["00800"]);
+    let bdjo = Bdjo::from(&bytes[..]).unwrap();
+
+    assert!(bdjo.accessible_playlists.access_to_all);
+    assert_eq!(bdjo.autostart_playlist(), None);
+
+    let bytes = bdjo_bytes(false, true, &[]);
+    let bdjo = Bdjo::from(&bytes[..]).unwrap();
+    assert_eq!(bdjo.autostart_playlist(), None);
+}
+
+#[test]
+fn reject_garbage() {
+    assert!(Bdjo::from(&b"BDJO0200"[..]).is_err());
+    assert!(Bdjo::from(&include_bytes!("../assets/tiny.mpls")[..]).is_err());
+
+    let mut truncated = bdjo_bytes(false, true, &["00800", "00010"]);
+    truncated.truncate(70);
+    assert!(Bdjo::from(&truncated[..]).is_err());
+}
@@ -0,0 +1,162 @@
+use mpls::chapters::{
+    read_matroska_xml, read_ogm, write_csv, write_ffmetadata, write_matroska_xml, write_ogm,
+};
+use mpls::{Chapter, ChapterName, Mpls, MplsError, TimeStamp};
+
+fn chapters_of(bytes: &[u8]) -> Vec<Chapter> {
+    Mpls::from(bytes).unwrap().chapters()
+}
+
+#[test]
+fn chapters_are_playlist_relative() {
+    let data = include_bytes!("../assets/simple.mpls");
+    let chapters = chapters_of(&data[..]);
+
+    // second mark: 208523328 - 188925000 (play item 0 in time)
+    assert_eq!(chapters[1].start, TimeStamp(19_598_328));
+    // mark on play item 1 starts right where play item 0 ends
+    assert_eq!(chapters[19].start, TimeStamp(448_735_175 - 188_925_000));
+    assert_eq!(chapters[0].end, chapters[1].start);
+}
+
+#[test]
+fn chapters_skip_link_points() {
+    let data = include_bytes!("../assets/multi-angle.mpls");
+    let mpls = Mpls::from(&data[..]).unwrap();
+    let chapters = mpls.chapters();
+
+    assert!(chapters.len() < mpls.marks.len());
+    assert!(chapters.windows(2).all(|w| w[0].start < w[1].start));
+}
+
+#[test]
+fn formats_round_consistently() {
+    // 1 tick short of 1.0005s, which rounds down to 1000ms
+    let chapters = vec![Chapter {
+        start: TimeStamp(45_022),
+        end: TimeStamp(45_023),
+        names: Vec::new(),
+    }];
+
+    let mut ogm = Vec::new();
+    write_ogm(&chapters, &mut ogm).unwrap();
+    let mut xml = Vec::new();
+    write_matroska_xml(&chapters, &mut xml).unwrap();
+    let mut meta = Vec::new();
+    write_ffmetadata(&chapters, &mut meta).unwrap();
+
+    let ogm = String::from_utf8(ogm).unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+    let meta = String::from_utf8(meta).unwrap();
+    assert!(ogm.contains("CHAPTER01=00:00:01.000\n"));
+    assert!(xml.contains("<ChapterTimeStart>00:00:01.000000000</ChapterTimeStart>"));
+    assert!(xml.contains("<ChapterTimeEnd>00:00:01.001000000</ChapterTimeEnd>"));
+    assert!(meta.contains("START=1000\nEND=1001\n"));
+}
+
+#[test]
+fn named_chapters() {
+    let chapters = vec![Chapter {
+        start: TimeStamp(0),
+        end: TimeStamp(45_000 * 3_600),
+        names: vec![
+            ChapterName {
+                name: "Fish & Chips, Part 1".into(),
+                language: Some("eng".into()),
+            },
+            ChapterName {
+                name: "Fisch".into(),
+                language: Some("ger".into()),
+            },
+        ],
+    }];
+
+    let mut xml = Vec::new();
+    write_matroska_xml(&chapters, &mut xml).unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(xml.contains("<ChapterString>Fish &amp; Chips, Part 1</ChapterString>"));
+    assert!(xml.contains("<ChapterLanguage>ger</ChapterLanguage>"));
+    assert!(xml.contains("<ChapterTimeEnd>01:00:00.000000000</ChapterTimeEnd>"));
+
+    let mut csv = Vec::new();
+    write_csv(&chapters, &mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "1,Fish & Chips\\, Part 1\n"
+    );
+}
+
+#[test]
+fn import_replaces_entry_points() {
+    let data = include_bytes!("../assets/multi-angle.mpls");
+    let mut mpls = Mpls::from(&data[..]).unwrap();
+    let link_points = mpls.marks.len() - mpls.chapters().len();
+
+    let ogm = "CHAPTER01=00:00:00.000\nCHAPTER01NAME=Start\nCHAPTER02=00:01:00.000\n";
+    let chapters = read_ogm(ogm).unwrap();
+    mpls.set_chapters(&chapters);
+    assert_eq!(mpls.marks.len(), link_points + 2);
+
+    // survives a serialization round trip
+    let reparsed = Mpls::from(&mpls.to_bytes().unwrap()[..]).unwrap();
+    let starts: Vec<TimeStamp> = reparsed.chapters().iter().map(|c| c.start).collect();
+    assert_eq!(starts, &[TimeStamp(0), TimeStamp(45_000 * 60)]);
+}
+
+#[test]
+fn import_merges_with_existing() {
+    let data = include_bytes!("../assets/simple.mpls");
+    let mut mpls = Mpls::from(&data[..]).unwrap();
+    let before = mpls.chapters();
+
+    let mut chapters = before.clone();
+    chapters.push(Chapter {
+        start: TimeStamp(45_000),
+        end: TimeStamp(45_000),
+        names: Vec::new(),
+    });
+    // chapters past the end of the playlist are dropped
+    chapters.push(Chapter {
+        start: mpls.duration(),
+        end: mpls.duration(),
+        names: Vec::new(),
+    });
+    mpls.merge_chapters(&chapters);
+
+    let after = mpls.chapters();
+    assert_eq!(after.len(), before.len() + 1);
+    assert_eq!(after[1].start, TimeStamp(45_000));
+}
+
+#[test]
+fn matroska_xml_round_trip() {
+    let data = include_bytes!("../assets/large.mpls");
+    let chapters = chapters_of(&data[..]);
+
+    let mut xml = Vec::new();
+    write_matroska_xml(&chapters, &mut xml).unwrap();
+    let read = read_matroska_xml(&String::from_utf8(xml).unwrap()).unwrap();
+
+    assert_eq!(read.len(), chapters.len());
+    for (a, b) in read.iter().zip(chapters.iter()) {
+        assert_eq!(a.start.millis(), b.start.millis());
+        assert_eq!(a.end.millis(), b.end.millis());
+    }
+    assert_eq!(read[2].name(), Some("Chapter 03"));
+}
+
+#[test]
+fn invalid_chapter_files() {
+    assert!(read_ogm("CHAPTER01=1:2\n").is_err());
+    assert!(read_ogm("CHAPTER01NAME=Orphan\n").is_err());
+    // hours that overflow the nanosecond calculation
+    assert!(matches!(
+        read_ogm("CHAPTER01=18446744073709551615:00:00.000\n"),
+        Err(MplsError::ChapterParseError(_))
+    ));
+    assert!(read_ogm("CHAPTER01=5124095:00:00.000\n").is_err());
+    assert!(read_matroska_xml(
+        "<Chapters><ChapterAtom><ChapterTimeStart>bogus</ChapterTimeStart></ChapterAtom></Chapters>"
+    )
+    .is_err());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{write, TempDir};
+use mpls::classify::{Category, Thresholds};
+use mpls::types::PlaybackType;
+use mpls::{Disc, Mpls, TimeStamp};
+
+fn asset(bytes: &[u8]) -> Mpls {
+    Mpls::from(bytes).unwrap()
+}
+
+fn part(range: std::ops::Range<usize>) -> Mpls {
+    let mut mpls = asset(include_bytes!("../assets/large.mpls"));
+    mpls.play_list.play_items = mpls.play_list.play_items[range].to_vec();
+    mpls
+}
+
+#[test]
+fn feature() {
+    let mpls = asset(include_bytes!("../assets/simple.mpls"));
+    let c = mpls.classify(&Thresholds::default());
+    assert_eq!(c.category, Category::Feature);
+    assert!(c.confidence > 0.5);
+    assert_eq!(c.features.audio_streams, 5);
+    assert_eq!(c.features.subtitle_streams, 6);
+    assert_eq!(c.features.longest_ratio, None);
+    assert_eq!(c.scores.len(), 5);
+}
+
+#[test]
+fn menu_loop() {
+    let mut mpls = asset(include_bytes!("../assets/tiny.mpls"));
+    assert_eq!(
+        mpls.classify(&Thresholds::default()).category,
+        Category::MenuLoop
+    );
+
+    mpls.play_list.play_items[0].still_mode = 2;
+    mpls.app_info_play_list.playback_type = PlaybackType::Random;
+    let c = mpls.classify(&Thresholds::default());
+    assert_eq!(c.category, Category::MenuLoop);
+    assert_eq!(c.features.still_play_items, 1);
+    assert!(c.confidence > 0.9);
+}
+
+#[test]
+fn trailer() {
+    let mut mpls = asset(include_bytes!("../assets/simple.mpls"));
+    let item = &mut mpls.play_list.play_items[0];
+    item.out_time = TimeStamp(item.in_time.0 + 150 * 45_000);
+    item.random_access_flag = true;
+    mpls.play_list.play_items.truncate(1);
+    mpls.marks.clear();
+
+    let c = mpls.classify(&Thresholds::default());
+    assert_eq!(c.category, Category::Trailer);
+    assert!(c.features.random_access_restricted);
+}
+
+#[test]
+fn tuned_thresholds() {
+    let episode = part(0..40);
+    let defaults = Thresholds::default();
+    assert_eq!(episode.classify(&defaults).category, Category::Extra);
+
+    let thresholds = Thresholds {
+        feature_min_duration: TimeStamp(20 * 60 * 45_000),
+        ..defaults
+    };
+    assert_eq!(episode.classify(&thresholds).category, Category::Feature);
+}
+
+#[test]
+fn disc_context() {
+    let dir = TempDir::new();
+    let (one, two) = (part(0..40), part(40..90));
+    let mut all = one.clone();
+    all.play_list
+        .play_items
+        .extend(two.play_list.play_items.iter().cloned());
+    let simple = asset(include_bytes!("../assets/simple.mpls"));
+    for (number, mpls) in &[(1, &simple), (800, &all), (801, &one), (802, &two)] {
+        let path = format!("BDMV/PLAYLIST/{:05}.mpls", number);
+        write(dir.path(), &path, &mpls.to_bytes().unwrap());
+    }
+    let disc = Disc::open(dir.path()).unwrap();
+
+    let classes = disc.classify(&Thresholds::default());
+    assert_eq!(classes[&1].category, Category::Feature);
+    assert_eq!(classes[&1].features.longest_ratio, Some(1.0));
+    assert_eq!(classes[&800].category, Category::PlayAll);
+    assert_eq!(classes[&800].features.is_play_all, Some(true));
+    assert_eq!(classes[&801].category, Category::Extra);
+    assert_eq!(classes[&801].features.shared_clip_ratio, Some(1.0));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{write, TempDir};
+use mpls::clip_graph::ClipGraph;
+use mpls::{Disc, Mpls};
+use std::collections::BTreeMap;
+
+fn asset(bytes: &[u8]) -> Mpls {
+    Mpls::from(bytes).unwrap()
+}
+
+// the multi-angle playlist, and a "cut" of it that leaves out the first
+// three play items and plays the simple playlist's clips afterwards
+fn playlists() -> BTreeMap<u16, Mpls> {
+    let angles = asset(include_bytes!("../assets/multi-angle.mpls"));
+    let simple = asset(include_bytes!("../assets/simple.mpls"));
+    let mut cut = angles.clone();
+    cut.play_list.play_items = angles.play_list.play_items[3..]
+        .iter()
+        .chain(simple.play_list.play_items.iter())
+        .cloned()
+        .collect();
+
+    let mut playlists = BTreeMap::new();
+    playlists.insert(1, simple);
+    playlists.insert(800, angles);
+    playlists.insert(801, cut);
+    playlists
+}
+
+#[test]
+fn queries() {
+    let graph = ClipGraph::new(&playlists());
+
+    assert_eq!(graph.playlists(), &[1, 800, 801]);
+    assert_eq!(graph.playlists_using("00055"), &[1, 801]);
+    assert_eq!(graph.playlists_using("00081"), &[800]);
+    assert_eq!(graph.playlists_using("00091"), &[800, 801]);
+    assert!(graph.playlists_using("99999").is_empty());
+    assert_eq!(graph.clips_of(1), &["00055", "00059", "00061"]);
+    assert_eq!(graph.shared_clips(1, 801), &["00055", "00059", "00061"]);
+    assert!(graph.shared_clips(1, 800).is_empty());
+
+    let shared = graph.shared();
+    assert!(!shared.contains_key("00081"));
+    assert_eq!(shared["00091"], &[800, 801]);
+}
+
+#[test]
+fn angle_edges() {
+    let graph = ClipGraph::new(&playlists());
+
+    let uses: Vec<_> = graph.uses_of_clip("00083").collect();
+    assert_eq!(uses.len(), 1);
+    assert_eq!(uses[0].playlist, 800);
+    assert_eq!(uses[0].play_item, 1);
+    assert_eq!(uses[0].angle, 1);
+
+    let default: Vec<_> = graph.uses_of_clip("00082").collect();
+    assert_eq!(default[0].angle, 0);
+    assert_eq!(default[0].in_time, uses[0].in_time);
+    assert_eq!(default[0].out_time, uses[0].out_time);
+}
+
+#[test]
+fn dot() {
+    let mut playlists = BTreeMap::new();
+    playlists.insert(0, asset(include_bytes!("../assets/tiny.mpls")));
+    let dot = ClipGraph::new(&playlists).to_dot();
+
+    let item = &playlists[&0].play_list.play_items[0];
+    let expected = format!(
+        "digraph clips {{\n    rankdir=LR;\n    \
+         \"00000.mpls\" [shape=box, label=\"00000.mpls\"];\n    \
+         \"00000.m2ts\" [shape=ellipse, label=\"00000.m2ts\"];\n    \
+         \"00000.mpls\" -> \"00000.m2ts\" [label=\"#0 {:.3}-{:.3}\"];\n}}\n",
+        item.in_time.seconds(),
+        item.out_time.seconds()
+    );
+    assert_eq!(dot, expected);
+}
+
+#[test]
+fn disc_clip_graph() {
+    let dir = TempDir::new();
+    for (number, mpls) in playlists() {
+        let path = format!("BDMV/PLAYLIST/{:05}.mpls", number);
+        write(dir.path(), &path, &mpls.to_bytes().unwrap());
+    }
+    let disc = Disc::open(dir.path()).unwrap();
+
+    let graph = disc.clip_graph();
+    assert_eq!(graph.uses(), ClipGraph::new(&playlists()).uses());
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{clpi_bytes, ClpiSpec};
+use mpls::{Clpi, Mpls, MplsError, StreamType, TimeStamp};
+
+fn spec() -> ClpiSpec {
+    ClpiSpec {
+        presentation_start: 27_000_000,
+        presentation_end: 27_450_000,
+        source_packets: 200_000,
+        entry_points: vec![
+            (27_000_000, 0),
+            (27_022_522, 3_000),
+            (27_045_045, 140_000),
+            (27_200_000, 150_000),
+        ],
+    }
+}
+
+#[test]
+fn parse_clip_info() {
+    let clpi = Clpi::from(&clpi_bytes(&spec())[..]).unwrap();
+
+    assert_eq!(clpi.version, "0200");
+    assert_eq!(clpi.clip_info.clip_stream_type, 1);
+    assert_eq!(clpi.clip_info.application_type, 1);
+    assert_eq!(clpi.clip_info.ts_recording_rate, 6_000_000);
+    assert_eq!(clpi.clip_info.number_of_source_packets, 200_000);
+    assert!(clpi.clip_info.atc_deltas.is_empty());
+    assert!(clpi.ext.is_empty());
+}
+
+#[test]
+fn parse_sequence_info() {
+    let clpi = Clpi::from(&clpi_bytes(&spec())[..]).unwrap();
+
+    let stc = clpi.stc_sequences();
+    assert_eq!(stc.len(), 1);
+    assert_eq!(stc[0].pcr_pid, 0x1001);
+    assert_eq!(stc[0].presentation_start_time, TimeStamp(27_000_000));
+    assert_eq!(stc[0].presentation_end_time, TimeStamp(27_450_000));
+}
+
+#[test]
+fn parse_program_info() {
+    let clpi = Clpi::from(&clpi_bytes(&spec())[..]).unwrap();
+
+    let streams = clpi.streams();
+    assert_eq!(streams.len(), 2);
+    assert_eq!(streams[0].pid, 0x1011);
+    assert!(matches!(
+        streams[0].attrs.stream_type,
+        StreamType::SdrVideo(..)
+    ));
+    assert_eq!(streams[1].pid, 0x1100);
+    match &streams[1].attrs.stream_type {
+        StreamType::Audio(_, _, lang) => assert_eq!(lang, "eng"),
+        t => panic!("unexpected stream type {:?}", t),
+    }
+}
+
+#[test]
+fn parse_ep_map() {
+    let clpi = Clpi::from(&clpi_bytes(&spec())[..]).unwrap();
+
+    let ep_map = clpi.cpi.unwrap();
+    assert_eq!(ep_map.cpi_type, 1);
+    assert_eq!(ep_map.streams.len(), 1);
+    let stream = &ep_map.streams[0];
+    assert_eq!(stream.pid, 0x1011);
+    assert_eq!(stream.ep_stream_type, 1);
+    assert_eq!(stream.fine.len(), 4);
+    // a new coarse entry is needed once the SPN crosses 2^17
+    assert_eq!(stream.coarse.len(), 2);
+    assert_eq!(stream.coarse[1].ref_to_ep_fine_id, 2);
+    assert_eq!(stream.coarse[1].spn_ep, 140_000);
+    assert_eq!(stream.fine[1].spn_ep, 3_000);
+}
+
+#[test]
+fn reject_garbage() {
+    assert!(Clpi::from(&b"HDMV0200"[..]).is_err());
+    assert!(matches!(
+        Clpi::from(&include_bytes!("../assets/tiny.mpls")[..]),
+        Err(MplsError::ClpiParseError)
+    ));
+
+    let mut truncated = clpi_bytes(&spec());
+    truncated.truncate(300);
+    assert!(Clpi::from(&truncated[..]).is_err());
+}
+
+#[test]
+fn entry_points() {
+    let clpi = Clpi::from(&clpi_bytes(&spec())[..]).unwrap();
+
+    let points = clpi.ep_map_stream(0x1011).unwrap().entry_points();
+    assert_eq!(points.len(), 4);
+    // the EP map only stores time stamps with a precision of 256 ticks
+    for (point, (pts, spn)) in points.iter().zip(spec().entry_points) {
+        assert_eq!(point.pts, TimeStamp(pts & !0xFF));
+        assert_eq!(point.spn, spn);
+    }
+    assert!(clpi.ep_map_stream(0x1100).is_none());
+}
+
+#[test]
+fn seek_to_preceding_i_frame() {
+    let mpls = Mpls::from(&include_bytes!("../assets/tiny.mpls")[..]).unwrap();
+    let play_item = &mpls.play_list.play_items[0];
+    let clpi = Clpi::from(&clpi_bytes(&spec())[..]).unwrap();
+
+    let entry = clpi.seek(play_item, play_item.in_time).unwrap();
+    assert_eq!(entry.spn, 0);
+    assert_eq!(entry.byte_offset(), 0);
+
+    let entry = clpi.seek(play_item, TimeStamp(27_100_000)).unwrap();
+    assert_eq!(entry.spn, 140_000);
+    assert_eq!(entry.byte_offset(), 140_000 * 192);
+
+    let entry = clpi.seek(play_item, TimeStamp(27_400_000)).unwrap();
+    assert_eq!(entry.spn, 150_000);
+
+    assert!(clpi.seek(play_item, TimeStamp(26_000_000)).is_none());
+}
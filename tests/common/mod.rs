@@ -0,0 +1,506 @@
+#![allow(dead_code)]
+
+use mpls::movie_objects::{Command, Instruction};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A temporary directory that is removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> TempDir {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let path = std::env::temp_dir().join(format!("mpls-test-{}-{}", std::process::id(), n));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes a file, creating its parent directories as needed.
+pub fn write(root: &Path, path: &str, contents: &[u8]) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// A minimal description of a clip information file, see `clpi_bytes`.
+pub struct ClpiSpec {
+    pub presentation_start: u32,
+    pub presentation_end: u32,
+    pub source_packets: u32,
+    /// Entry points of the video stream as (45 kHz time stamp, source packet number).
+    pub entry_points: Vec<(u32, u32)>,
+}
+
+fn with_u32_len(body: Vec<u8>) -> Vec<u8> {
+    let mut buf = (body.len() as u32).to_be_bytes().to_vec();
+    buf.extend(body);
+    buf
+}
+
+/// Builds a clip information file with a single ATC and STC sequence, an
+/// H.264 video stream (PID 0x1011) and an AC-3 audio stream (PID 0x1100), and
+/// an EP map for the video stream.
+pub fn clpi_bytes(spec: &ClpiSpec) -> Vec<u8> {
+    let mut clip_info = vec![0, 0, 1, 1, 0, 0, 0, 0];
+    clip_info.extend_from_slice(&6_000_000u32.to_be_bytes());
+    clip_info.extend_from_slice(&spec.source_packets.to_be_bytes());
+    clip_info.extend_from_slice(&[0; 128]);
+    clip_info.extend_from_slice(&[0x00, 0x1E]);
+    clip_info.extend_from_slice(&[0; 30]);
+    let clip_info = with_u32_len(clip_info);
+
+    let mut sequence_info = vec![0, 1, 0, 0, 0, 0, 1, 0];
+    sequence_info.extend_from_slice(&0x1001u16.to_be_bytes());
+    sequence_info.extend_from_slice(&0u32.to_be_bytes());
+    sequence_info.extend_from_slice(&spec.presentation_start.to_be_bytes());
+    sequence_info.extend_from_slice(&spec.presentation_end.to_be_bytes());
+    let sequence_info = with_u32_len(sequence_info);
+
+    let mut program_info = vec![0, 1, 0, 0, 0, 0, 0x01, 0x00, 2, 0];
+    program_info.extend_from_slice(&[0x10, 0x11, 0x15, 0x1B, 0x61]);
+    program_info.extend_from_slice(&[0; 19]);
+    program_info.extend_from_slice(&[0x11, 0x00, 0x15, 0x81, 0x61, b'e', b'n', b'g']);
+    program_info.extend_from_slice(&[0; 17]);
+    let program_info = with_u32_len(program_info);
+
+    // split the entry points into coarse and fine entries
+    let mut coarse: Vec<(u32, u32, u32)> = Vec::new();
+    let mut fine: Vec<u32> = Vec::new();
+    for (i, (pts, spn)) in spec.entry_points.iter().enumerate() {
+        let pts90 = *pts as u64 * 2;
+        let new_coarse = match coarse.last() {
+            Some((_, c_pts, c_spn)) => {
+                (pts90 >> 19) as u32 & !1 != c_pts & !1 || spn >> 17 != c_spn >> 17
+            }
+            None => true,
+        };
+        if new_coarse {
+            coarse.push((i as u32, ((pts90 >> 19) & 0x3FFF) as u32, *spn));
+        }
+        fine.push(((((pts90 >> 9) & 0x7FF) as u32) << 17) | (spn & 0x1FFFF));
+    }
+    let mut ep_map = vec![0, 1];
+    ep_map.extend_from_slice(&0x1011u16.to_be_bytes());
+    let bits: u64 = (1 << 34) | ((coarse.len() as u64) << 18) | fine.len() as u64;
+    ep_map.extend_from_slice(&((bits >> 32) as u16).to_be_bytes());
+    ep_map.extend_from_slice(&(bits as u32).to_be_bytes());
+    // the stream's entries directly follow the EP map header
+    ep_map.extend_from_slice(&14u32.to_be_bytes());
+    let fine_start = 4 + 8 * coarse.len() as u32;
+    ep_map.extend_from_slice(&fine_start.to_be_bytes());
+    for (fine_id, pts, spn) in coarse.iter() {
+        ep_map.extend_from_slice(&((fine_id << 14) | pts).to_be_bytes());
+        ep_map.extend_from_slice(&spn.to_be_bytes());
+    }
+    for f in fine.iter() {
+        ep_map.extend_from_slice(&f.to_be_bytes());
+    }
+    let mut cpi = vec![0x00, 0x01];
+    cpi.extend(ep_map);
+    let cpi = with_u32_len(cpi);
+
+    let sequence_info_addr = 40 + clip_info.len() as u32;
+    let program_info_addr = sequence_info_addr + sequence_info.len() as u32;
+    let cpi_addr = program_info_addr + program_info.len() as u32;
+    let clip_mark_addr = cpi_addr + cpi.len() as u32;
+
+    let mut buf = b"HDMV0200".to_vec();
+    for addr in &[
+        sequence_info_addr,
+        program_info_addr,
+        cpi_addr,
+        clip_mark_addr,
+        0,
+    ] {
+        buf.extend_from_slice(&addr.to_be_bytes());
+    }
+    buf.extend_from_slice(&[0; 12]);
+    buf.extend(clip_info);
+    buf.extend(sequence_info);
+    buf.extend(program_info);
+    buf.extend(cpi);
+    buf.extend_from_slice(&[0; 4]);
+    buf
+}
+
+/// An object of the index table, see `index_bytes`.
+#[derive(Clone, Copy)]
+pub enum IndexObjectSpec {
+    None,
+    /// A movie object, by index.
+    Hdmv(u16),
+    /// A BD-J object, by name.
+    BdJ(&'static str),
+}
+
+fn index_object_bytes(object: IndexObjectSpec, access_type: u32) -> Vec<u8> {
+    let (object_type, playback_type, data) = match object {
+        IndexObjectSpec::None => (0, 0, vec![0; 6]),
+        IndexObjectSpec::Hdmv(id) => (1, 0, id.to_be_bytes().to_vec()),
+        IndexObjectSpec::BdJ(name) => (2, 2, name.as_bytes().to_vec()),
+    };
+    let mut buf = ((object_type << 30) | (access_type << 28))
+        .to_be_bytes()
+        .to_vec();
+    buf.extend_from_slice(&((playback_type as u16) << 14).to_be_bytes());
+    buf.extend(data);
+    buf.resize(12, 0);
+    buf
+}
+
+/// Builds an index table with the given first playback, top menu and titles,
+/// all of them with the "movie" playback type. Titles that are `Hdmv(n)` with
+/// an odd `n` are hidden from the user.
+pub fn index_bytes(
+    first_playback: IndexObjectSpec,
+    top_menu: IndexObjectSpec,
+    titles: &[IndexObjectSpec],
+) -> Vec<u8> {
+    let mut app_info = vec![0, 0x11];
+    app_info.extend_from_slice(&[0; 32]);
+    let app_info = with_u32_len(app_info);
+
+    let mut indexes = index_object_bytes(first_playback, 0);
+    indexes.extend(index_object_bytes(top_menu, 0));
+    indexes.extend_from_slice(&(titles.len() as u16).to_be_bytes());
+    for title in titles {
+        let access_type = match title {
+            IndexObjectSpec::Hdmv(n) if n % 2 == 1 => 0b10,
+            _ => 0,
+        };
+        indexes.extend(index_object_bytes(*title, access_type));
+    }
+    let indexes = with_u32_len(indexes);
+
+    let mut buf = b"INDX0200".to_vec();
+    buf.extend_from_slice(&(40 + app_info.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&[0; 28]);
+    buf.extend(app_info);
+    buf.extend(indexes);
+    buf
+}
+
+/// Builds a movie object file, with the resume intention flag set on all
+/// objects.
+pub fn movie_objects_bytes(objects: &[Vec<Instruction>]) -> Vec<u8> {
+    let mut body = vec![0; 4];
+    body.extend_from_slice(&(objects.len() as u16).to_be_bytes());
+    for instructions in objects {
+        body.extend_from_slice(&0x8000u16.to_be_bytes());
+        body.extend_from_slice(&(instructions.len() as u16).to_be_bytes());
+        for instruction in instructions {
+            let command = Command::from(*instruction);
+            body.extend_from_slice(&command.opcode.to_be_bytes());
+            body.extend_from_slice(&command.dst.to_be_bytes());
+            body.extend_from_slice(&command.src.to_be_bytes());
+        }
+    }
+
+    let mut buf = b"MOBJ0200".to_vec();
+    buf.extend_from_slice(&[0; 32]);
+    buf.extend(with_u32_len(body));
+    buf
+}
+
+/// Builds a BD-J object with the given accessible playlists.
+pub fn bdjo_bytes(access_to_all: bool, autostart: bool, playlists: &[&str]) -> Vec<u8> {
+    let mut terminal_info = b"00000".to_vec();
+    terminal_info.extend_from_slice(&[0x38, 0, 0, 0]);
+
+    let mut app_cache_info = vec![1, 0, 1];
+    app_cache_info.extend_from_slice(b"00000");
+    app_cache_info.extend_from_slice(b"*.*");
+    app_cache_info.extend_from_slice(&[0; 3]);
+
+    let flags = (access_to_all as u32) << 31 | (autostart as u32) << 30;
+    let mut accessible_playlists = flags.to_be_bytes().to_vec();
+    accessible_playlists.extend_from_slice(&((playlists.len() as u16) << 5).to_be_bytes());
+    for name in playlists {
+        accessible_playlists.extend_from_slice(name.as_bytes());
+        accessible_playlists.push(0);
+    }
+
+    let mut buf = b"BDJO0200".to_vec();
+    buf.extend_from_slice(&[0; 20]);
+    buf.extend(with_u32_len(terminal_info));
+    buf.extend(with_u32_len(app_cache_info));
+    buf.extend(with_u32_len(accessible_playlists));
+    buf.extend(with_u32_len(vec![1, 0, 1, 0x10, 0, 0, 0, 1, 0x40, 0]));
+    buf
+}
+
+const UDF_BLOCK: usize = 2048;
+// the sector the physical partition starts at
+const UDF_PARTITION_START: usize = 257;
+
+// a node of the directory tree of a UDF image
+enum UdfNode {
+    Dir(std::collections::BTreeMap<String, UdfNode>),
+    File(Vec<u8>),
+}
+
+// where a node is recorded: the metadata block of its file entry and the
+// metadata blocks of its directory data or the physical blocks of its file data
+struct UdfPlaced {
+    entry: u32,
+    data: Vec<u32>,
+    children: Vec<(String, UdfPlaced)>,
+    size: usize,
+}
+
+fn udf_tag(buf: &mut [u8], id: u16, location: u32) {
+    buf[0..2].copy_from_slice(&id.to_le_bytes());
+    buf[2] = 3;
+    buf[12..16].copy_from_slice(&location.to_le_bytes());
+    buf[4] = 0;
+    buf[4] = buf[..16].iter().fold(0u8, |s, b| s.wrapping_add(*b));
+}
+
+fn udf_short_ad(length: usize, block: u32) -> Vec<u8> {
+    let mut ad = (length as u32).to_le_bytes().to_vec();
+    ad.extend_from_slice(&block.to_le_bytes());
+    ad
+}
+
+fn udf_long_ad(length: usize, block: u32, partition: u16) -> Vec<u8> {
+    let mut ad = udf_short_ad(length, block);
+    ad.extend_from_slice(&partition.to_le_bytes());
+    ad.extend_from_slice(&[0; 6]);
+    ad
+}
+
+// a file entry, or an extended file entry for regular files
+fn udf_file_entry(file_type: u8, size: usize, ad_type: u16, ads: &[u8], location: u32) -> Vec<u8> {
+    let extended = file_type != 4;
+    let mut buf = vec![0; UDF_BLOCK];
+    buf[27] = file_type;
+    buf[34..36].copy_from_slice(&ad_type.to_le_bytes());
+    buf[56..64].copy_from_slice(&(size as u64).to_le_bytes());
+    let (l_ad, start) = if extended { (212, 216) } else { (172, 176) };
+    buf[l_ad..l_ad + 4].copy_from_slice(&(ads.len() as u32).to_le_bytes());
+    buf[start..start + ads.len()].copy_from_slice(ads);
+    udf_tag(&mut buf, if extended { 266 } else { 261 }, location);
+    buf
+}
+
+fn udf_file_identifier(name: Option<&str>, is_dir: bool, entry: u32) -> Vec<u8> {
+    let name: Vec<u8> = match name {
+        Some(n) => std::iter::once(8).chain(n.bytes()).collect(),
+        None => Vec::new(),
+    };
+    let mut buf = vec![0; 38];
+    buf[16] = 1;
+    buf[18] = if name.is_empty() {
+        0x0A
+    } else if is_dir {
+        0x02
+    } else {
+        0
+    };
+    buf[19] = name.len() as u8;
+    buf[20..36].copy_from_slice(&udf_long_ad(UDF_BLOCK, entry, 1));
+    buf.extend(name);
+    buf.resize(buf.len().div_ceil(4) * 4, 0);
+    udf_tag(&mut buf, 257, 0);
+    buf
+}
+
+fn udf_put(image: &mut [u8], sector: usize, bytes: &[u8]) {
+    let offset = sector * UDF_BLOCK;
+    image[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn udf_directory_size(children: &std::collections::BTreeMap<String, UdfNode>) -> usize {
+    let fid = |name_len: usize| (38 + name_len).div_ceil(4) * 4;
+    fid(0) + children.keys().map(|n| fid(n.len() + 1)).sum::<usize>()
+}
+
+fn udf_layout(node: &UdfNode, meta: &mut u32, physical: &mut u32) -> UdfPlaced {
+    let entry = *meta;
+    *meta += 1;
+    match node {
+        UdfNode::Dir(children) => {
+            let size = udf_directory_size(children);
+            let n = size.div_ceil(UDF_BLOCK);
+            let data = (*meta..*meta + n as u32).collect();
+            *meta += n as u32;
+            let children = children
+                .iter()
+                .map(|(name, child)| (name.clone(), udf_layout(child, meta, physical)))
+                .collect();
+            UdfPlaced {
+                entry,
+                data,
+                children,
+                size,
+            }
+        }
+        UdfNode::File(bytes) => {
+            // small files are embedded in their file entry, larger ones are
+            // recorded in one extent per block, with a gap after each
+            let n = if bytes.len() <= 64 {
+                0
+            } else {
+                bytes.len().div_ceil(UDF_BLOCK)
+            };
+            let data = (0..n as u32).map(|i| *physical + 2 * i).collect();
+            *physical += 2 * n as u32;
+            UdfPlaced {
+                entry,
+                data,
+                children: Vec::new(),
+                size: bytes.len(),
+            }
+        }
+    }
+}
+
+/// Builds a Blu-ray disc image with a UDF 2.50 file system that contains the
+/// given files, e.g. `("BDMV/PLAYLIST/00800.mpls", ...)`.
+///
+/// The file system has a physical partition and a metadata partition, whose
+/// metadata file is split into two extents. Directories are recorded with
+/// file entries in the metadata partition; regular files with extended file
+/// entries, whose data is either embedded (for up to 64 bytes) or recorded in
+/// one extent per block in the physical partition. Anchors are recorded at
+/// sector 256 and in the last sector.
+pub fn udf_image(files: &[(&str, &[u8])]) -> Vec<u8> {
+    use std::collections::BTreeMap;
+
+    let mut root = UdfNode::Dir(BTreeMap::new());
+    for (path, contents) in files {
+        let mut node = &mut root;
+        let components: Vec<&str> = path.split('/').collect();
+        for (i, name) in components.iter().enumerate() {
+            let children = match node {
+                UdfNode::Dir(children) => children,
+                UdfNode::File(_) => panic!("{} is a file", name),
+            };
+            node = children.entry(name.to_string()).or_insert_with(|| {
+                if i + 1 == components.len() {
+                    UdfNode::File(contents.to_vec())
+                } else {
+                    UdfNode::Dir(BTreeMap::new())
+                }
+            });
+        }
+    }
+
+    // physical block 0 is the metadata file's entry, blocks 1 and 2 are the
+    // first two metadata blocks, followed by the file data and the remaining
+    // metadata blocks
+    let (mut meta, mut physical) = (1, 3);
+    let placed = udf_layout(&root, &mut meta, &mut physical);
+    let meta_start = physical;
+    let n_physical = meta_start as usize + meta as usize - 2;
+    let n_sectors = UDF_PARTITION_START + n_physical + 1;
+    let mut image = vec![0; n_sectors * UDF_BLOCK];
+    let meta_sector = |block: u32| {
+        let physical = if block < 2 {
+            1 + block
+        } else {
+            meta_start + block - 2
+        };
+        UDF_PARTITION_START + physical as usize
+    };
+
+    // the anchors, the volume descriptor sequence at sector 32
+    let mut anchor = vec![0; UDF_BLOCK];
+    anchor[16..20].copy_from_slice(&(16 * UDF_BLOCK as u32).to_le_bytes());
+    anchor[20..24].copy_from_slice(&32u32.to_le_bytes());
+    udf_tag(&mut anchor, 2, 256);
+    udf_put(&mut image, 256, &anchor);
+    udf_put(&mut image, n_sectors - 1, &anchor);
+
+    let mut pd = vec![0; UDF_BLOCK];
+    pd[188..192].copy_from_slice(&(UDF_PARTITION_START as u32).to_le_bytes());
+    pd[192..196].copy_from_slice(&(n_physical as u32).to_le_bytes());
+    udf_tag(&mut pd, 5, 32);
+    udf_put(&mut image, 32, &pd);
+
+    let mut lvd = vec![0; UDF_BLOCK];
+    lvd[212..216].copy_from_slice(&(UDF_BLOCK as u32).to_le_bytes());
+    lvd[248..264].copy_from_slice(&udf_long_ad(UDF_BLOCK, 0, 1));
+    lvd[264..268].copy_from_slice(&70u32.to_le_bytes());
+    lvd[268..272].copy_from_slice(&2u32.to_le_bytes());
+    lvd[440..446].copy_from_slice(&[1, 6, 1, 0, 0, 0]);
+    let map = &mut lvd[446..510];
+    map[0] = 2;
+    map[1] = 64;
+    map[5..28].copy_from_slice(b"*UDF Metadata Partition");
+    map[36..38].copy_from_slice(&1u16.to_le_bytes());
+    map[48..52].copy_from_slice(&u32::MAX.to_le_bytes());
+    udf_tag(&mut lvd, 6, 33);
+    udf_put(&mut image, 33, &lvd);
+
+    let mut td = vec![0; UDF_BLOCK];
+    udf_tag(&mut td, 8, 34);
+    udf_put(&mut image, 34, &td);
+
+    // the metadata file
+    let mut ads = udf_short_ad(2 * UDF_BLOCK, 1);
+    ads.extend(udf_short_ad((meta as usize - 2) * UDF_BLOCK, meta_start));
+    let entry = udf_file_entry(250, meta as usize * UDF_BLOCK, 0, &ads, 0);
+    udf_put(&mut image, UDF_PARTITION_START, &entry);
+
+    // the file set descriptor in metadata block 0
+    let mut fsd = vec![0; UDF_BLOCK];
+    fsd[400..416].copy_from_slice(&udf_long_ad(UDF_BLOCK, placed.entry, 1));
+    udf_tag(&mut fsd, 256, 0);
+    udf_put(&mut image, meta_sector(0), &fsd);
+
+    fn write_node(
+        node: &UdfNode,
+        placed: &UdfPlaced,
+        parent: u32,
+        image: &mut [u8],
+        meta_sector: &dyn Fn(u32) -> usize,
+    ) {
+        match node {
+            UdfNode::Dir(children) => {
+                let mut data = udf_file_identifier(None, true, parent);
+                for ((name, child), (_, child_placed)) in children.iter().zip(&placed.children) {
+                    let is_dir = matches!(child, UdfNode::Dir(_));
+                    data.extend(udf_file_identifier(Some(name), is_dir, child_placed.entry));
+                    write_node(child, child_placed, placed.entry, image, meta_sector);
+                }
+                for (i, block) in placed.data.iter().enumerate() {
+                    let chunk = data.chunks(UDF_BLOCK).nth(i).unwrap();
+                    udf_put(image, meta_sector(*block), chunk);
+                }
+                let ads = udf_short_ad(placed.size, placed.data[0]);
+                let entry = udf_file_entry(4, placed.size, 0, &ads, placed.entry);
+                udf_put(image, meta_sector(placed.entry), &entry);
+            }
+            UdfNode::File(bytes) => {
+                let entry = if placed.data.is_empty() {
+                    udf_file_entry(5, bytes.len(), 3, bytes, placed.entry)
+                } else {
+                    let mut ads = Vec::new();
+                    for (chunk, block) in bytes.chunks(UDF_BLOCK).zip(&placed.data) {
+                        udf_put(image, UDF_PARTITION_START + *block as usize, chunk);
+                        ads.extend(udf_short_ad(chunk.len(), *block));
+                    }
+                    udf_file_entry(5, bytes.len(), 0, &ads, placed.entry)
+                };
+                udf_put(image, meta_sector(placed.entry), &entry);
+            }
+        }
+    }
+    write_node(&root, &placed, placed.entry, &mut image, &meta_sector);
+    image
+}
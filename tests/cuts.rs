@@ -0,0 +1,83 @@
+use mpls::cuts::SegmentKind;
+use mpls::{Mpls, TimeStamp};
+
+fn large() -> Mpls {
+    Mpls::from(&include_bytes!("../assets/large.mpls")[..]).unwrap()
+}
+
+// the extended cut of large.mpls: play items 10..15 are cut from the
+// theatrical version, and the extended cut replaces play item 50 with two
+// other play items
+fn cuts() -> (Mpls, Mpls) {
+    let extended = large();
+    let mut theatrical = extended.clone();
+    theatrical.play_list.play_items.drain(10..15);
+    let mut extended = extended;
+    let items = &mut extended.play_list.play_items;
+    let alternate = vec![items[100].clone(), items[101].clone()];
+    items.splice(50..51, alternate);
+    (theatrical, extended)
+}
+
+#[test]
+fn identical() {
+    let mpls = large();
+    let comparison = mpls.compare_cut(&mpls);
+    assert!(comparison.is_identical());
+    assert_eq!(comparison.segments.len(), 1);
+    assert_eq!(comparison.shared, mpls.duration());
+    assert_eq!(comparison.added, TimeStamp(0));
+    assert_eq!(comparison.removed, TimeStamp(0));
+}
+
+#[test]
+fn branches() {
+    let (theatrical, extended) = cuts();
+    let original = large();
+    let comparison = theatrical.compare_cut(&extended);
+
+    let kinds: Vec<SegmentKind> = comparison.segments.iter().map(|s| s.kind).collect();
+    use SegmentKind::*;
+    assert_eq!(kinds, &[Shared, Added, Shared, Removed, Added, Shared]);
+
+    let added = &comparison.segments[1];
+    assert_eq!(added.first.play_items, 10..10);
+    assert_eq!(added.second.play_items, 10..15);
+    let items = &original.play_list.play_items;
+    let scene: u32 = items[10..15].iter().map(|p| p.duration().0).sum();
+    assert_eq!(added.duration, TimeStamp(scene));
+    assert_eq!(added.second.range.start, comparison.segments[0].duration);
+    assert_eq!(added.first.range.start, added.first.range.end);
+
+    let removed = &comparison.segments[3];
+    assert_eq!(removed.first.play_items, 45..46);
+    assert_eq!(removed.duration, items[50].duration());
+    assert_eq!(comparison.segments[4].second.play_items, 50..52);
+
+    assert_eq!(
+        comparison.added.0,
+        scene + items[100].duration().0 + items[101].duration().0
+    );
+    assert_eq!(comparison.removed, items[50].duration());
+    assert_eq!(
+        comparison.shared.0 + comparison.removed.0,
+        theatrical.duration().0
+    );
+    assert_eq!(
+        comparison.shared.0 + comparison.added.0,
+        extended.duration().0
+    );
+    assert_eq!(comparison.differences().count(), 3);
+}
+
+#[test]
+fn report() {
+    let (theatrical, extended) = cuts();
+    let report = theatrical.compare_cut(&extended).to_string();
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines.len(), 7);
+    assert!(lines[0].starts_with("shared   00:00:00.000-"));
+    assert!(lines[1].starts_with("added    "));
+    assert!(lines[1].ends_with("play items 10..15)"));
+    assert!(lines[6].starts_with("shared "));
+}
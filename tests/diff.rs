@@ -0,0 +1,194 @@
+use mpls::diff::{Change, StreamTable};
+use mpls::types::ExtensionDataEntry;
+use mpls::{Mpls, TimeStamp};
+
+fn simple() -> Mpls {
+    Mpls::from(&include_bytes!("../assets/simple.mpls")[..]).unwrap()
+}
+
+#[test]
+fn identical() {
+    let mpls = simple();
+    let diff = mpls.diff(&mpls.clone());
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "--- a\n+++ b\n");
+}
+
+#[test]
+fn play_items() {
+    let old = simple();
+    let mut new = old.clone();
+    let mut extra = Mpls::from(&include_bytes!("../assets/tiny.mpls")[..])
+        .unwrap()
+        .play_list
+        .play_items
+        .remove(0);
+    extra.clip.file_name = "00099".into();
+    new.play_list.play_items.insert(1, extra);
+    new.play_list.play_items.remove(3);
+    new.play_list.play_items[0].out_time = TimeStamp(old.play_list.play_items[0].out_time.0 + 45);
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.changes.len(), 3);
+    match &diff.changes[0] {
+        Change::PlayItemModified {
+            old_index,
+            index,
+            clip,
+            fields,
+        } => {
+            assert_eq!((*old_index, *index), (0, 0));
+            assert_eq!(clip, "00055");
+            assert_eq!(fields.len(), 1);
+            assert_eq!(fields[0].field, "out_time");
+        }
+        c => panic!("unexpected change {:?}", c),
+    }
+    assert!(
+        matches!(&diff.changes[1], Change::PlayItemAdded { index: 1, clip } if clip.starts_with("00099 "))
+    );
+    assert!(
+        matches!(&diff.changes[2], Change::PlayItemRemoved { index: 2, clip } if clip.starts_with("00061 "))
+    );
+}
+
+#[test]
+fn streams() {
+    let old = simple();
+    let mut new = old.clone();
+    let table = &mut new.play_list.play_items[1].stream_number_table;
+    table.primary_audio_streams.pop();
+    table.primary_pgs_streams[1].attrs.coding_type = 0x92;
+
+    let changes = old.diff(&new).changes;
+    assert!(changes.contains(&Change::StreamRemoved {
+        play_item: 1,
+        table: StreamTable::PrimaryAudio,
+        index: 4,
+        stream: match &changes[0] {
+            Change::StreamRemoved { stream, .. } => stream.clone(),
+            c => panic!("unexpected change {:?}", c),
+        },
+    }));
+    assert!(changes.iter().all(|c| match c {
+        Change::StreamRemoved { table, .. } => *table == StreamTable::PrimaryAudio,
+        Change::StreamModified {
+            play_item, table, ..
+        } => *play_item == 1 && *table == StreamTable::PrimaryPgs,
+        _ => false,
+    }));
+}
+
+#[test]
+fn header_marks_and_extension_data() {
+    let old = simple();
+    let mut new = old.clone();
+    new.version = "0200".into();
+    new.app_info_play_list.user_opt_mask = 0;
+    new.marks[2].time_stamp = TimeStamp(new.marks[2].time_stamp.0 + 45_000);
+    new.marks.pop();
+    new.ext.push(ExtensionDataEntry {
+        data_type: 7,
+        data_version: 1,
+        data: vec![0; 12],
+    });
+    let last = old.marks.len() - 1;
+
+    let diff = old.diff(&new);
+    assert!(matches!(&diff.changes[0], Change::Header(c) if c.field == "version"));
+    assert!(matches!(&diff.changes[1], Change::Header(c) if c.field == "user_opt_mask"));
+    assert!(matches!(
+        &diff.changes[2],
+        Change::MarkMoved { index: 2, .. }
+    ));
+    assert!(matches!(&diff.changes[3], Change::MarkRemoved { index, .. } if *index == last));
+    assert_eq!(
+        diff.changes[4],
+        Change::Extension {
+            data_type: 7,
+            data_version: 1,
+            old_len: None,
+            new_len: Some(12),
+        }
+    );
+    assert_eq!(diff.changes.len(), 5);
+
+    let text = diff.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        &lines[..7],
+        &[
+            "--- a",
+            "+++ b",
+            "@@ header @@",
+            "-version: \"0300\"",
+            "-user_opt_mask: 1127064916721664",
+            "+version: \"0200\"",
+            "+user_opt_mask: 0",
+        ]
+    );
+    assert_eq!(lines[7], "@@ mark 2 @@");
+    assert!(lines[8].starts_with("-EntryPoint at play item "));
+    assert_eq!(
+        &lines[lines.len() - 2..],
+        &["@@ extension data 7:1 @@", "+12 bytes"]
+    );
+}
+
+#[test]
+fn insertions_keep_later_elements_aligned() {
+    let old = simple();
+    let mut new = old.clone();
+    let mut mark = new.marks[0];
+    mark.time_stamp = TimeStamp(mark.time_stamp.0 + 1);
+    new.marks.insert(0, mark);
+    let table = &mut new.play_list.play_items[0].stream_number_table;
+    let stream = table.primary_audio_streams[1].clone();
+    table.primary_audio_streams.insert(0, stream);
+
+    let changes = old.diff(&new).changes;
+    assert_eq!(changes.len(), 2);
+    assert!(matches!(
+        &changes[0],
+        Change::StreamAdded {
+            play_item: 0,
+            table: StreamTable::PrimaryAudio,
+            index: 0,
+            ..
+        }
+    ));
+    assert!(matches!(&changes[1], Change::MarkAdded { index: 0, .. }));
+}
+
+#[test]
+fn extension_entries_with_the_same_type() {
+    let entry = |len: usize| ExtensionDataEntry {
+        data_type: 7,
+        data_version: 1,
+        data: vec![0; len],
+    };
+    let mut old = simple();
+    old.ext = vec![entry(4), entry(8)];
+    let mut new = old.clone();
+    new.ext[1] = entry(12);
+    new.ext.push(entry(16));
+
+    let changes = old.diff(&new).changes;
+    assert_eq!(
+        changes,
+        &[
+            Change::Extension {
+                data_type: 7,
+                data_version: 1,
+                old_len: Some(8),
+                new_len: Some(12),
+            },
+            Change::Extension {
+                data_type: 7,
+                data_version: 1,
+                old_len: None,
+                new_len: Some(16),
+            },
+        ]
+    );
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use common::{write, TempDir};
+use mpls::Disc;
+
+fn disc_with_playlists() -> TempDir {
+    let dir = TempDir::new();
+    let root = dir.path();
+    write(
+        root,
+        "BDMV/PLAYLIST/00000.mpls",
+        include_bytes!("../assets/tiny.mpls"),
+    );
+    write(
+        root,
+        "BDMV/PLAYLIST/00800.mpls",
+        include_bytes!("../assets/simple.mpls"),
+    );
+    write(
+        root,
+        "BDMV/PLAYLIST/00801.MPLS",
+        include_bytes!("../assets/multi-angle.mpls"),
+    );
+    write(root, "BDMV/PLAYLIST/00900.mpls", b"MPLS0200 but not really");
+    write(root, "BDMV/PLAYLIST/notes.txt", b"ignored");
+    dir
+}
+
+#[test]
+fn open_parses_all_playlists() {
+    let dir = disc_with_playlists();
+    let disc = Disc::open(dir.path()).unwrap();
+
+    assert_eq!(disc.root, dir.path().join("BDMV"));
+    assert_eq!(
+        disc.playlists.keys().copied().collect::<Vec<_>>(),
+        &[0, 800, 801]
+    );
+    assert_eq!(disc.errors.len(), 1);
+    assert!(disc.errors[0].0.ends_with("00900.mpls"));
+
+    // opening the BDMV directory itself works as well
+    let disc = Disc::open(dir.path().join("BDMV")).unwrap();
+    assert!(disc.playlist(800).is_some());
+}
+
+#[test]
+fn open_without_bdmv_fails() {
+    let dir = TempDir::new();
+    assert!(Disc::open(dir.path()).is_err());
+}
+
+#[test]
+fn clip_files_and_missing_files() {
+    let dir = disc_with_playlists();
+    let root = dir.path();
+    write(root, "BDMV/STREAM/00055.m2ts", b"");
+    write(root, "BDMV/CLIPINF/00055.clpi", b"");
+    write(root, "BDMV/STREAM/SSIF/00059.ssif", b"");
+    write(root, "BDMV/CLIPINF/00059.clpi", b"");
+    fs_remove(root, "BDMV/PLAYLIST/00000.mpls");
+    fs_remove(root, "BDMV/PLAYLIST/00801.MPLS");
+
+    let disc = Disc::open(root).unwrap();
+    let mpls = disc.playlist(800).unwrap();
+    let clips: Vec<_> = mpls
+        .play_list
+        .play_items
+        .iter()
+        .map(|p| disc.clip_files(&p.clip))
+        .collect();
+
+    assert!(clips[0].is_complete());
+    assert!(clips[0].ssif.is_none());
+    assert!(clips[1].is_complete());
+    assert!(clips[1].stream.is_none());
+    assert!(!clips[2].is_complete());
+
+    let missing = disc.missing_files();
+    let bdmv = root.join("BDMV");
+    assert_eq!(
+        missing,
+        &[
+            bdmv.join("CLIPINF/00061.clpi"),
+            bdmv.join("STREAM/00061.m2ts")
+        ]
+    );
+}
+
+fn fs_remove(root: &std::path::Path, path: &str) {
+    std::fs::remove_file(root.join(path)).unwrap();
+}
+
+#[test]
+fn clip_info() {
+    let dir = disc_with_playlists();
+    let root = dir.path();
+    let spec = common::ClpiSpec {
+        presentation_start: 27_000_000,
+        presentation_end: 27_011_261,
+        source_packets: 1_000,
+        entry_points: vec![(27_000_000, 0)],
+    };
+    write(root, "BDMV/CLIPINF/00000.clpi", &common::clpi_bytes(&spec));
+
+    let disc = Disc::open(root).unwrap();
+    let clip = &disc.playlist(0).unwrap().play_list.play_items[0].clip;
+    let clpi = disc.clip_info(clip).unwrap();
+    assert_eq!(clpi.clip_info.number_of_source_packets, 1_000);
+
+    let clip = &disc.playlist(800).unwrap().play_list.play_items[0].clip;
+    assert!(disc.clip_info(clip).is_err());
+}
+
+#[test]
+fn index_table() {
+    let dir = disc_with_playlists();
+    let root = dir.path();
+    let disc = Disc::open(root).unwrap();
+    assert!(disc.index.is_none());
+
+    let index = common::index_bytes(
+        common::IndexObjectSpec::Hdmv(0),
+        common::IndexObjectSpec::None,
+        &[common::IndexObjectSpec::Hdmv(1)],
+    );
+    write(root, "BDMV/index.bdmv", b"INDX0200 but broken");
+    write(root, "BDMV/BACKUP/index.bdmv", &index);
+    let disc = Disc::open(root).unwrap();
+    assert_eq!(disc.index.unwrap().titles.len(), 1);
+    assert_eq!(disc.errors.len(), 1);
+
+    fs_remove(root, "BDMV/BACKUP/index.bdmv");
+    let disc = Disc::open(root).unwrap();
+    assert!(disc.index.is_none());
+    assert_eq!(disc.errors.len(), 2);
+    assert!(disc.errors[0].0.ends_with("index.bdmv"));
+}
+
+#[test]
+fn movie_objects_and_vm() {
+    use mpls::movie_objects::{Instruction::PlayPl, Operand::Immediate};
+    use mpls::vm::VmConfig;
+
+    let dir = disc_with_playlists();
+    let root = dir.path();
+    let disc = Disc::open(root).unwrap();
+    assert!(disc.movie_objects.is_none());
+    assert!(disc.vm(VmConfig::default()).is_none());
+
+    let index = common::index_bytes(
+        common::IndexObjectSpec::None,
+        common::IndexObjectSpec::None,
+        &[common::IndexObjectSpec::Hdmv(0)],
+    );
+    write(root, "BDMV/index.bdmv", &index);
+    let mobj = common::movie_objects_bytes(&[vec![PlayPl(Immediate(800))]]);
+    write(root, "BDMV/BACKUP/MOVIEOBJECT.BDMV", &mobj);
+    let disc = Disc::open(root).unwrap();
+    assert_eq!(disc.movie_objects.as_ref().unwrap().objects.len(), 1);
+    let mut vm = disc.vm(VmConfig::default()).unwrap();
+    assert_eq!(vm.run_title(1).playlists(), &[800]);
+}
+
+#[test]
+fn reachable_playlists() {
+    use common::IndexObjectSpec::{BdJ, Hdmv};
+    use mpls::movie_objects::{Instruction::PlayPl, Operand::Immediate};
+    use mpls::vm::VmConfig;
+
+    let dir = disc_with_playlists();
+    let root = dir.path();
+    let config = VmConfig::default();
+    let disc = Disc::open(root).unwrap();
+    assert!(disc.title_playlists(1, &config).is_none());
+    assert!(disc.reachable_playlists(&config).is_empty());
+
+    let index = common::index_bytes(
+        Hdmv(0),
+        BdJ("00001"),
+        &[Hdmv(1), BdJ("00002"), BdJ("00003")],
+    );
+    write(root, "BDMV/index.bdmv", &index);
+    let mobj =
+        common::movie_objects_bytes(&[vec![PlayPl(Immediate(10))], vec![PlayPl(Immediate(800))]]);
+    write(root, "BDMV/MovieObject.bdmv", &mobj);
+    let bdjo = common::bdjo_bytes(false, true, &["00801", "00011"]);
+    write(root, "BDMV/BDJO/00002.bdjo", &bdjo);
+    let bdjo = common::bdjo_bytes(true, false, &[]);
+    write(root, "BDMV/BDJO/00001.BDJO", &bdjo);
+    write(root, "BDMV/BDJO/00003.bdjo", b"BDJO but broken");
+
+    let disc = Disc::open(root).unwrap();
+    assert_eq!(disc.bdj_objects.len(), 2);
+    assert_eq!(disc.errors.len(), 2);
+    assert_eq!(disc.title_playlists(1, &config), Some(vec![800]));
+    assert_eq!(disc.title_playlists(2, &config), Some(vec![801, 11]));
+    assert_eq!(disc.title_playlists(3, &config), None);
+    assert_eq!(disc.title_playlists(4, &config), None);
+    assert_eq!(
+        disc.reachable_playlists(&config)
+            .into_iter()
+            .collect::<Vec<_>>(),
+        &[0, 10, 11, 800, 801]
+    );
+}
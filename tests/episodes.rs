@@ -0,0 +1,87 @@
+mod common;
+
+use common::{write, TempDir};
+use mpls::episodes::find_play_alls;
+use mpls::{Disc, Mpls, TimeStamp};
+use std::collections::BTreeMap;
+
+fn part(range: std::ops::Range<usize>) -> Mpls {
+    let mut mpls = Mpls::from(&include_bytes!("../assets/large.mpls")[..]).unwrap();
+    mpls.play_list.play_items = mpls.play_list.play_items[range].to_vec();
+    mpls
+}
+
+fn concat(parts: &[&Mpls]) -> Mpls {
+    let mut mpls = parts[0].clone();
+    mpls.play_list.play_items = parts
+        .iter()
+        .flat_map(|p| p.play_list.play_items.iter().cloned())
+        .collect();
+    mpls
+}
+
+// the 135 play items of large.mpls as three episodes, a "play all" of all
+// episodes, one of the first two, and an extra copy of the second episode
+fn playlists() -> BTreeMap<u16, Mpls> {
+    let (one, two, three) = (part(0..40), part(40..90), part(90..135));
+    let mut playlists = BTreeMap::new();
+    playlists.insert(
+        0,
+        Mpls::from(&include_bytes!("../assets/tiny.mpls")[..]).unwrap(),
+    );
+    playlists.insert(800, concat(&[&one, &two, &three]));
+    playlists.insert(801, one.clone());
+    playlists.insert(802, two.clone());
+    playlists.insert(803, three);
+    playlists.insert(804, two.clone());
+    playlists.insert(805, concat(&[&one, &two]));
+    playlists
+}
+
+#[test]
+fn split_play_all() {
+    let playlists = playlists();
+    let play_alls = find_play_alls(&playlists);
+
+    assert_eq!(play_alls.len(), 2);
+    let play_all = &play_alls[0];
+    assert_eq!(play_all.playlist, 800);
+    let episodes: Vec<u16> = play_all.episodes.iter().map(|e| e.playlist).collect();
+    assert_eq!(episodes, &[801, 802, 803]);
+    assert_eq!(play_all.episodes[0].play_items, 0..40);
+    assert_eq!(play_all.episodes[1].play_items, 40..90);
+    assert_eq!(play_all.episodes[2].play_items, 90..135);
+
+    let durations: Vec<TimeStamp> = play_all.episodes.iter().map(|e| e.duration).collect();
+    for (episode, duration) in episodes.iter().zip(durations.iter()) {
+        assert_eq!(playlists[episode].duration(), *duration);
+    }
+    assert_eq!(play_all.episodes[0].range.start, TimeStamp(0));
+    assert_eq!(play_all.episodes[1].range.start, durations[0]);
+    assert_eq!(play_all.episodes[2].range.end, playlists[&800].duration());
+
+    assert_eq!(play_alls[1].playlist, 805);
+    assert_eq!(play_alls[1].episodes.len(), 2);
+}
+
+#[test]
+fn no_play_alls() {
+    let mut playlists = BTreeMap::new();
+    playlists.insert(1, part(0..40));
+    playlists.insert(2, part(0..40));
+    playlists.insert(3, part(40..90));
+    assert!(find_play_alls(&playlists).is_empty());
+}
+
+#[test]
+fn disc_play_alls() {
+    let dir = TempDir::new();
+    for (number, mpls) in playlists() {
+        let path = format!("BDMV/PLAYLIST/{:05}.mpls", number);
+        write(dir.path(), &path, &mpls.to_bytes().unwrap());
+    }
+    let disc = Disc::open(dir.path()).unwrap();
+
+    let play_alls = disc.play_alls();
+    assert_eq!(play_alls, find_play_alls(&playlists()));
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{write, TempDir};
+use mpls::export::restrict_to_angle;
+use mpls::vm::VmConfig;
+use mpls::{Disc, Mpls};
+
+fn disc_with(number: u16, bytes: &[u8]) -> TempDir {
+    let dir = TempDir::new();
+    let root = dir.path();
+    write(root, &format!("BDMV/PLAYLIST/{:05}.mpls", number), bytes);
+    for clip in Mpls::from(bytes).unwrap().clips() {
+        let name = &clip.file_name;
+        write(root, &format!("BDMV/STREAM/{}.m2ts", name), name.as_bytes());
+        write(root, &format!("BDMV/CLIPINF/{}.clpi", name), b"HDMV");
+    }
+    dir
+}
+
+#[test]
+fn export_playlist() {
+    let bytes = include_bytes!("../assets/simple.mpls");
+    let source = disc_with(800, bytes);
+    let disc = Disc::open(source.path()).unwrap();
+    let dest = TempDir::new();
+
+    let written = disc.export_playlist(800, None, dest.path()).unwrap();
+    // 3 navigation files and 3 clip information files with backups, and 3
+    // streams
+    assert_eq!(written.len(), 15);
+
+    let export = Disc::open(dest.path()).unwrap();
+    assert!(export.errors.is_empty());
+    assert_eq!(export.playlists.keys().copied().collect::<Vec<_>>(), &[0]);
+    assert_eq!(&export.playlists[&0].to_bytes().unwrap()[..], &bytes[..]);
+    assert_eq!(
+        export.title_playlists(1, &VmConfig::default()),
+        Some(vec![0])
+    );
+    assert!(export.index.unwrap().first_playback.is_some());
+
+    let root = dest.path().join("BDMV");
+    for name in &["00055", "00059", "00061"] {
+        let stream = root.join(format!("STREAM/{}.m2ts", name));
+        assert_eq!(std::fs::read(stream).unwrap(), name.as_bytes());
+        assert!(root.join(format!("CLIPINF/{}.clpi", name)).is_file());
+        assert!(root.join(format!("BACKUP/CLIPINF/{}.clpi", name)).is_file());
+    }
+    // AppInfoBDMV is 34 bytes long
+    let index = std::fs::read(root.join("BACKUP/index.bdmv")).unwrap();
+    assert_eq!(&index[40..44], &34u32.to_be_bytes());
+    assert!(root.join("BACKUP/MovieObject.bdmv").is_file());
+    assert!(root.join("BACKUP/PLAYLIST/00000.mpls").is_file());
+
+    // never overwrite an existing export
+    assert!(disc.export_playlist(800, None, dest.path()).is_err());
+}
+
+#[test]
+fn export_angle() {
+    let bytes = include_bytes!("../assets/multi-angle.mpls");
+    let source = disc_with(801, bytes);
+    let disc = Disc::open(source.path()).unwrap();
+    let dest = TempDir::new();
+
+    disc.export_playlist(801, Some(1), dest.path()).unwrap();
+    let export = Disc::open(dest.path()).unwrap();
+    let mpls = &export.playlists[&0];
+    assert_eq!(mpls.angles().len(), 1);
+
+    let original = Mpls::from(&bytes[..]).unwrap();
+    let expected: Vec<&str> = original.angles()[1]
+        .segments()
+        .iter()
+        .map(|c| c.file_name.as_str())
+        .collect();
+    let segments: Vec<&str> = mpls.angles()[0]
+        .segments()
+        .iter()
+        .map(|c| c.file_name.as_str())
+        .collect();
+    assert_eq!(segments, expected);
+    assert!(export.missing_files().is_empty());
+    assert!(!dest.path().join("BDMV/STREAM/00082.m2ts").exists());
+}
+
+#[test]
+fn restrict_angle() {
+    let mpls = Mpls::from(&include_bytes!("../assets/multi-angle.mpls")[..]).unwrap();
+    let first = restrict_to_angle(&mpls, 0);
+    assert_eq!(first.angles().len(), 1);
+    assert_eq!(first.play_list.play_items[1].clip.file_name, "00082");
+    assert_eq!(first.duration(), mpls.duration());
+    let second = restrict_to_angle(&mpls, 1);
+    assert_eq!(second.play_list.play_items[1].clip.file_name, "00083");
+}
+
+#[test]
+fn export_missing_files() {
+    let bytes = include_bytes!("../assets/simple.mpls");
+    let source = disc_with(800, bytes);
+    std::fs::remove_file(source.path().join("BDMV/STREAM/00059.m2ts")).unwrap();
+    let disc = Disc::open(source.path()).unwrap();
+    let dest = TempDir::new();
+
+    assert!(disc.export_playlist(800, None, dest.path()).is_err());
+    assert!(disc.export_playlist(1, None, dest.path()).is_err());
+    assert!(!dest.path().join("BDMV").exists());
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{index_bytes, IndexObjectSpec};
+use mpls::index::{IndexObject, PlaybackType};
+use mpls::types::ExtensionDataEntry;
+use mpls::{Index, MplsError};
+
+#[test]
+fn parse_index() {
+    let bytes = index_bytes(
+        IndexObjectSpec::Hdmv(0),
+        IndexObjectSpec::BdJ("00000"),
+        &[IndexObjectSpec::Hdmv(2), IndexObjectSpec::Hdmv(3)],
+    );
+    let index = Index::from(&bytes[..]).unwrap();
+
+    assert_eq!(index.version, "0200");
+    assert_eq!(index.app_info.video_format, 1);
+    assert_eq!(index.app_info.frame_rate, 1);
+    assert_eq!(
+        index.first_playback,
+        Some(IndexObject::Hdmv {
+            playback_type: PlaybackType::Movie,
+            movie_object: 0,
+        })
+    );
+    let top_menu = index.top_menu.as_ref().unwrap();
+    assert!(top_menu.is_bdj());
+    assert_eq!(top_menu.playback_type(), PlaybackType::Movie);
+
+    assert_eq!(index.titles.len(), 2);
+    assert!(index.title(0).is_none());
+    assert!(!index.title(1).unwrap().access_type.hidden);
+    assert!(index.title(2).unwrap().access_type.hidden);
+    assert_eq!(
+        index.title(2).unwrap().object,
+        IndexObject::Hdmv {
+            playback_type: PlaybackType::Movie,
+            movie_object: 3,
+        }
+    );
+    assert!(index.title(3).is_none());
+}
+
+#[test]
+fn parse_app_info() {
+    let mut bytes = index_bytes(IndexObjectSpec::None, IndexObjectSpec::None, &[]);
+    // AppInfoBDMV directly follows the 40-byte header
+    assert_eq!(&bytes[40..44], &34u32.to_be_bytes());
+    bytes[45] = 0x63;
+    let index = Index::from(&bytes[..]).unwrap();
+    assert_eq!(index.app_info.video_format, 6);
+    assert_eq!(index.app_info.frame_rate, 3);
+}
+
+#[test]
+fn parse_index_without_first_playback() {
+    let bytes = index_bytes(IndexObjectSpec::None, IndexObjectSpec::None, &[]);
+    let index = Index::from(&bytes[..]).unwrap();
+
+    assert!(index.first_playback.is_none());
+    assert!(index.top_menu.is_none());
+    assert!(index.titles.is_empty());
+}
+
+#[test]
+fn reject_garbage() {
+    assert!(Index::from(&b"INDX0200"[..]).is_err());
+    assert!(matches!(
+        Index::from(&include_bytes!("../assets/tiny.mpls")[..]),
+        Err(MplsError::IndexParseError)
+    ));
+
+    // titles always need an object
+    let bytes = index_bytes(
+        IndexObjectSpec::None,
+        IndexObjectSpec::None,
+        &[IndexObjectSpec::None],
+    );
+    assert!(Index::from(&bytes[..]).is_err());
+}
+
+#[test]
+fn write_index() {
+    let bytes = index_bytes(
+        IndexObjectSpec::Hdmv(0),
+        IndexObjectSpec::BdJ("00000"),
+        &[IndexObjectSpec::Hdmv(2), IndexObjectSpec::Hdmv(3)],
+    );
+    let mut index = Index::from(&bytes[..]).unwrap();
+    assert_eq!(index.to_bytes(), bytes);
+
+    index.ext.push(ExtensionDataEntry {
+        data_type: 1,
+        data_version: 2,
+        data: vec![1, 2, 3],
+    });
+    let index = Index::from(&index.to_bytes()[..]).unwrap();
+    assert_eq!(index.ext.len(), 1);
+    assert_eq!(index.ext[0].data, &[1, 2, 3]);
+    assert_eq!(index.titles.len(), 2);
+}
@@ -1,4 +1,4 @@
-use mpls::Mpls;
+use mpls::{Clip, Mpls, MplsError, PlayItemRef, PlaybackType, SubPath, SubPlayItem, TimeStamp};
 
 #[test]
 fn complete_tiny() {
@@ -40,6 +40,63 @@ fn complete_multi_angle() {
     assert!(res.is_ok());
 }
 
+#[test]
+fn playback_count_depends_on_playback_type() {
+    // AppInfoPlayList: length, reserved, playback type, playback count
+    let mut data = include_bytes!("../assets/simple.mpls").to_vec();
+    data[46..48].copy_from_slice(&2u16.to_be_bytes());
+    let mpls = Mpls::from(&data[..]).unwrap();
+    assert_eq!(mpls.app_info_play_list.playback_count, None);
+
+    // random playback (2) with any count
+    data[45] = 2;
+    data[46..48].copy_from_slice(&5u16.to_be_bytes());
+    let mpls = Mpls::from(&data[..]).unwrap();
+    assert!(matches!(
+        mpls.app_info_play_list.playback_type,
+        PlaybackType::Random
+    ));
+    assert_eq!(mpls.app_info_play_list.playback_count, Some(5));
+}
+
+#[test]
+fn multi_clip_entries_include_the_main_clip() {
+    let mut mpls = Mpls::from(&include_bytes!("../assets/simple.mpls")[..]).unwrap();
+    let clip = |name: &str| Clip {
+        file_name: name.into(),
+        codec_id: "M2TS".into(),
+        stc_id: 0,
+    };
+    mpls.play_list.sub_paths.push(SubPath {
+        sub_path_type: 3,
+        is_repeat: false,
+        play_items: vec![SubPlayItem {
+            clip: clip("00100"),
+            connection_condition: 1,
+            in_time: TimeStamp(0),
+            out_time: TimeStamp(45_000),
+            sync_play_item_id: 0,
+            sync_start_pts: 0,
+            multi_clip_entries: vec![clip("00101")],
+        }],
+    });
+    let bytes = mpls.to_bytes().unwrap();
+
+    // the number of entries is written as 2, counting the main clip
+    let entry = bytes.windows(5).position(|w| w == b"00101").unwrap();
+    assert_eq!(bytes[entry - 2], 2);
+
+    let parsed = Mpls::from(&bytes[..]).unwrap();
+    let item = &parsed.play_list.sub_paths[0].play_items[0];
+    assert_eq!(item.clip.file_name, "00100");
+    let entries: Vec<&str> = item
+        .multi_clip_entries
+        .iter()
+        .map(|c| c.file_name.as_str())
+        .collect();
+    assert_eq!(entries, &["00101"]);
+}
+
 #[test]
 fn multi_angle_count() {
     let data = include_bytes!("../assets/multi-angle.mpls");
@@ -111,3 +168,187 @@ fn multi_angle_last_segments() {
         &["00081", "00085", "00086", "00090", "00091"]
     );
 }
+
+#[test]
+fn round_trip_all_assets() {
+    let assets: [&[u8]; 4] = [
+        include_bytes!("../assets/tiny.mpls"),
+        include_bytes!("../assets/simple.mpls"),
+        include_bytes!("../assets/large.mpls"),
+        include_bytes!("../assets/multi-angle.mpls"),
+    ];
+
+    for data in assets.iter() {
+        let mpls = Mpls::from(*data).unwrap();
+        assert_eq!(&mpls.to_bytes().unwrap()[..], *data);
+    }
+}
+
+#[test]
+fn writing_rejects_lists_too_long_for_their_count_field() {
+    use std::io::ErrorKind;
+
+    let data = include_bytes!("../assets/multi-angle.mpls");
+    let mpls = Mpls::from(&data[..]).unwrap();
+    let is_invalid_input = |m: &Mpls| match m.to_bytes() {
+        Err(MplsError::IoError(e)) => e.kind() == ErrorKind::InvalidInput,
+        _ => false,
+    };
+
+    // the angle count includes the main clip
+    let i = mpls
+        .play_list
+        .play_items
+        .iter()
+        .position(|p| p.angle_info.is_some())
+        .unwrap();
+    let mut angles = mpls.clone();
+    let clip = angles.play_list.play_items[i].clip.clone();
+    angles.play_list.play_items[i]
+        .angles
+        .resize(254, clip.clone());
+    assert!(angles.to_bytes().is_ok());
+    angles.play_list.play_items[i].angles.push(clip);
+    assert!(is_invalid_input(&angles));
+
+    let mut streams = mpls.clone();
+    let stn = &mut streams.play_list.play_items[0].stream_number_table;
+    let audio = stn.primary_audio_streams[0].clone();
+    stn.primary_audio_streams.resize(256, audio);
+    assert!(is_invalid_input(&streams));
+
+    let mut marks = mpls.clone();
+    let mark = marks.marks[0];
+    marks.marks.resize(0x10000, mark);
+    assert!(is_invalid_input(&marks));
+}
+
+#[test]
+fn frame_conversions() {
+    use mpls::{RoundingMode, TimeStamp};
+
+    let data = include_bytes!("../assets/large.mpls");
+    let mpls = Mpls::from(&data[..]).unwrap();
+    let play_item = &mpls.play_list.play_items[1];
+
+    // play item relative frame indices, starting at the in time
+    assert_eq!(
+        play_item.frame_index(play_item.in_time, RoundingMode::Floor),
+        Some(0)
+    );
+    let last = play_item.frame_count(RoundingMode::Floor).unwrap();
+    let t = play_item.frame_time(last, RoundingMode::Ceil).unwrap();
+    assert!(t <= play_item.out_time);
+    assert_eq!(play_item.frame_index(t, RoundingMode::Floor), Some(last));
+
+    // frame -> time -> frame is lossless with matching rounding
+    for frame in &[0u64, 1, 23, 24, 1_000, 123_456] {
+        let t = mpls.frame_time(*frame, RoundingMode::Ceil).unwrap();
+        assert_eq!(mpls.frame_index(t, RoundingMode::Floor), Some(*frame));
+    }
+    assert_eq!(
+        mpls.frame_index(TimeStamp(45_045), RoundingMode::Nearest),
+        Some(24)
+    );
+}
+
+#[test]
+fn sub_path_timeline() {
+    use mpls::{Clip, SubPath, SubPlayItem, TimeStamp};
+
+    let data = include_bytes!("../assets/simple.mpls");
+    let mut mpls = Mpls::from(&data[..]).unwrap();
+    let sub_item = |sync_play_item_id, sync_start_pts| SubPlayItem {
+        clip: Clip {
+            file_name: "00300".into(),
+            codec_id: "M2TS".into(),
+            stc_id: 0,
+        },
+        connection_condition: 1,
+        in_time: TimeStamp(1_000),
+        out_time: TimeStamp(46_000),
+        sync_play_item_id,
+        sync_start_pts,
+        multi_clip_entries: Vec::new(),
+    };
+    // text subtitles, synced to 10 seconds into the second play item
+    mpls.play_list.sub_paths.push(SubPath {
+        sub_path_type: 4,
+        is_repeat: false,
+        play_items: vec![sub_item(1, 448_475_625 + 450_000)],
+    });
+    // async PiP, not placed
+    mpls.play_list.sub_paths.push(SubPath {
+        sub_path_type: 6,
+        is_repeat: false,
+        play_items: vec![sub_item(0, 188_925_000)],
+    });
+
+    // the sub paths survive serialization
+    let mpls = Mpls::from(&mpls.to_bytes().unwrap()[..]).unwrap();
+    assert_eq!(mpls.play_list.sub_paths.len(), 2);
+
+    let timeline = mpls.sub_path_timeline();
+    assert_eq!(timeline.len(), 1);
+    assert_eq!(timeline[0].sub_path, 0);
+    let first_item = mpls.play_list.play_items[0].duration().0;
+    assert_eq!(
+        timeline[0].range,
+        TimeStamp(first_item + 450_000)..TimeStamp(first_item + 495_000)
+    );
+}
+
+#[test]
+fn angle_change_points() {
+    let data = include_bytes!("../assets/multi-angle.mpls");
+    let mpls = Mpls::from(&data[..]).unwrap();
+    let changes = mpls.angle_change_points();
+
+    // every other play item is an alternate-angle scene
+    assert_eq!(changes.len(), 18);
+    let items = &mpls.play_list.play_items;
+    for change in changes.iter() {
+        assert_eq!(change.play_items.len(), 1);
+        let i = change.play_items.start;
+        assert_eq!(items[i].angles.len(), 3);
+        assert!(!change.angle_info.is_seamless_angle_change);
+        let start = mpls.playlist_time(mpls::PlayItemRef(i as u16), items[i].in_time);
+        assert_eq!(Some(change.range.start), start);
+        assert_eq!(
+            change.range.end.0 - change.range.start.0,
+            items[i].duration().0
+        );
+    }
+
+    // the time ranges saturate at the largest time stamp
+    let mut long = mpls.clone();
+    for item in long.play_list.play_items.iter_mut() {
+        item.in_time = TimeStamp(0);
+        item.out_time = TimeStamp(u32::MAX / 8);
+    }
+    let changes = long.angle_change_points();
+    assert_eq!(changes.len(), 18);
+    assert_eq!(changes.last().unwrap().range.end, TimeStamp(u32::MAX));
+
+    let data = include_bytes!("../assets/large.mpls");
+    let mpls = Mpls::from(&data[..]).unwrap();
+    assert!(mpls.angle_change_points().is_empty());
+}
+
+#[test]
+fn durations_saturate() {
+    let mut mpls = Mpls::from(&include_bytes!("../assets/simple.mpls")[..]).unwrap();
+    for item in mpls.play_list.play_items.iter_mut() {
+        item.in_time = TimeStamp(0);
+        item.out_time = TimeStamp(u32::MAX / 2);
+    }
+    assert_eq!(mpls.duration(), TimeStamp(u32::MAX));
+    assert_eq!(
+        mpls.playlist_time(PlayItemRef(2), TimeStamp(10)),
+        Some(TimeStamp(u32::MAX))
+    );
+    assert_eq!(mpls.chapters().len(), mpls.marks.len());
+    let (item, time) = mpls.clip_time(TimeStamp(u32::MAX - 1)).unwrap();
+    assert_eq!((item.0, time), (2, TimeStamp(0)));
+    assert!(mpls.clip_time(TimeStamp(u32::MAX)).is_some());
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::{index_bytes, movie_objects_bytes, write, IndexObjectSpec, TempDir};
+use mpls::main_feature::Criterion;
+use mpls::movie_objects::{Instruction::PlayPl, Operand::Immediate};
+use mpls::{Disc, Mpls};
+
+// a disc with a feature (00800), a slightly shorter cut (00801), a copy of
+// the feature (00803) and three decoys playing the feature's clips out of
+// order (00810 to 00812)
+fn disc() -> TempDir {
+    let dir = TempDir::new();
+    let root = dir.path();
+    let simple = include_bytes!("../assets/simple.mpls");
+    write(
+        root,
+        "BDMV/PLAYLIST/00000.mpls",
+        include_bytes!("../assets/tiny.mpls"),
+    );
+    write(root, "BDMV/PLAYLIST/00800.mpls", simple);
+    write(
+        root,
+        "BDMV/PLAYLIST/00801.mpls",
+        include_bytes!("../assets/large.mpls"),
+    );
+    write(root, "BDMV/PLAYLIST/00803.mpls", simple);
+    for (i, order) in [[1, 0, 2], [2, 1, 0], [0, 2, 1]].iter().enumerate() {
+        let mut mpls = Mpls::from(&simple[..]).unwrap();
+        let items = mpls.play_list.play_items.clone();
+        mpls.play_list.play_items = order.iter().map(|i| items[*i].clone()).collect();
+        let path = format!("BDMV/PLAYLIST/{:05}.mpls", 810 + i);
+        write(root, &path, &mpls.to_bytes().unwrap());
+    }
+    dir
+}
+
+#[test]
+fn rank_without_index() {
+    let dir = disc();
+    let disc = Disc::open(dir.path()).unwrap();
+
+    let ranking = disc.main_feature();
+    let order: Vec<u16> = ranking.iter().map(|c| c.playlist).collect();
+    assert_eq!(order.len(), 7);
+    assert_eq!(order[0], 800);
+    assert_eq!(order[1], 801);
+    assert_eq!(*order.last().unwrap(), 0);
+
+    let feature = &ranking[0];
+    let sum: f64 = feature.breakdown.iter().map(|c| c.points).sum();
+    assert!((feature.score - sum).abs() < 1e-9);
+    assert!(feature
+        .breakdown
+        .iter()
+        .all(|c| c.criterion != Criterion::Reachability));
+    let duplicates = feature
+        .breakdown
+        .iter()
+        .find(|c| c.criterion == Criterion::Duplicates)
+        .unwrap();
+    assert_eq!(duplicates.points, 0.0);
+    assert_eq!(
+        duplicates.reason,
+        "likely original among 5 playlists with the same clips in 4 different orders"
+    );
+    let decoy = ranking.iter().find(|c| c.playlist == 810).unwrap();
+    assert!(decoy.score < feature.score);
+    assert!(decoy.breakdown.iter().any(|c| c.points == -10.0
+        && c.reason == "one of 5 playlists with the same clips in 4 different orders"));
+
+    let copy = ranking.iter().find(|c| c.playlist == 803).unwrap();
+    assert!(copy
+        .breakdown
+        .iter()
+        .any(|c| c.reason.starts_with("identical to 00800.mpls, ")));
+}
+
+#[test]
+fn rank_with_index() {
+    let dir = disc();
+    let root = dir.path();
+    let index = index_bytes(
+        IndexObjectSpec::None,
+        IndexObjectSpec::None,
+        &[IndexObjectSpec::Hdmv(0)],
+    );
+    write(root, "BDMV/index.bdmv", &index);
+    let mobj = movie_objects_bytes(&[vec![PlayPl(Immediate(800))]]);
+    write(root, "BDMV/MovieObject.bdmv", &mobj);
+    let disc = Disc::open(root).unwrap();
+
+    let ranking = disc.main_feature();
+    assert_eq!(ranking[0].playlist, 800);
+    let reachability = ranking[0]
+        .breakdown
+        .iter()
+        .find(|c| c.criterion == Criterion::Reachability)
+        .unwrap();
+    assert_eq!(reachability.reason, "played by title 1");
+
+    let text = ranking[0].to_string();
+    assert!(text.starts_with("00800.mpls: "));
+    assert!(text.contains("  +25.0 Reachability: played by title 1\n"));
+}
+
+#[test]
+fn feature_wins_within_decoy_group() {
+    // the decoys have lower numbers than the feature, so they'd win any tie
+    let dir = TempDir::new();
+    let root = dir.path();
+    let simple = include_bytes!("../assets/simple.mpls");
+    write(
+        root,
+        "BDMV/PLAYLIST/00000.mpls",
+        include_bytes!("../assets/tiny.mpls"),
+    );
+    for (i, order) in [[1, 0, 2], [2, 1, 0], [0, 2, 1], [0, 1, 2]]
+        .iter()
+        .enumerate()
+    {
+        let mut mpls = Mpls::from(&simple[..]).unwrap();
+        let items = mpls.play_list.play_items.clone();
+        mpls.play_list.play_items = order.iter().map(|i| items[*i].clone()).collect();
+        let path = format!("BDMV/PLAYLIST/{:05}.mpls", 800 + i);
+        write(root, &path, &mpls.to_bytes().unwrap());
+    }
+    let disc = Disc::open(root).unwrap();
+
+    let ranking = disc.main_feature();
+    assert_eq!(ranking[0].playlist, 803);
+    assert!(ranking[0].score > ranking[1].score);
+    assert_eq!(ranking.last().unwrap().playlist, 0);
+}
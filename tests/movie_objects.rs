@@ -0,0 +1,128 @@
+mod common;
+
+use common::movie_objects_bytes;
+use mpls::movie_objects::{
+    Command, CompareOp, Instruction, Instruction::*, Operand::*, SetOp, SetSystemOp,
+};
+use mpls::MovieObjects;
+
+fn objects() -> Vec<Vec<Instruction>> {
+    vec![
+        vec![
+            Set(SetOp::Move, Gpr(0), Immediate(801)),
+            Compare(CompareOp::Eq, Psr(16), Immediate(0x656E67)),
+            Set(SetOp::Move, Gpr(0), Immediate(800)),
+            PlayPl(Gpr(0)),
+            JumpTitle(Immediate(2)),
+        ],
+        vec![PlayPlAtMark(Immediate(5), Immediate(3)), Break],
+        vec![Set(SetOp::Move, Gpr(1), Psr(4)), PlayPl(Gpr(1)), Resume],
+    ]
+}
+
+#[test]
+fn parse_movie_objects() {
+    let mobj = MovieObjects::from(&movie_objects_bytes(&objects())[..]).unwrap();
+
+    assert_eq!(mobj.version, "0200");
+    assert_eq!(mobj.objects.len(), 3);
+    assert!(mobj.objects[0].resume_intention_flag);
+    assert!(!mobj.objects[0].menu_call_mask);
+    for (object, instructions) in mobj.objects.iter().zip(objects()) {
+        let decoded: Vec<Instruction> = object.commands.iter().map(|c| c.instruction()).collect();
+        assert_eq!(decoded, instructions);
+    }
+}
+
+#[test]
+fn write_movie_objects() {
+    let bytes = movie_objects_bytes(&objects());
+    let mut mobj = MovieObjects::from(&bytes[..]).unwrap();
+    assert_eq!(mobj.to_bytes(), bytes);
+
+    mobj.objects[1].menu_call_mask = true;
+    mobj.objects[1].resume_intention_flag = false;
+    let mobj = MovieObjects::from(&mobj.to_bytes()[..]).unwrap();
+    assert!(mobj.objects[1].menu_call_mask);
+    assert!(!mobj.objects[1].resume_intention_flag);
+    assert_eq!(mobj.objects[2].commands.len(), 3);
+}
+
+#[test]
+fn disassembly() {
+    let mobj = MovieObjects::from(&movie_objects_bytes(&objects())[..]).unwrap();
+
+    let lines: Vec<String> = mobj.objects[0]
+        .commands
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        &[
+            "Move GPR[0], 801",
+            "Eq PSR[16], 6647399",
+            "Move GPR[0], 800",
+            "PlayPL GPR[0]",
+            "JumpTitle 2",
+        ]
+    );
+    assert_eq!(mobj.objects[1].commands[0].to_string(), "PlayPLatMK 5, 3");
+    assert_eq!(
+        SetSystem(SetSystemOp::SetStream, Immediate(0x8001_0000), Immediate(0)).to_string(),
+        "SetStream 0x80010000, 0x00000000"
+    );
+}
+
+#[test]
+fn encode_decode() {
+    let instructions = [
+        Nop,
+        Goto(Immediate(3)),
+        JumpObject(Gpr(7)),
+        CallTitle(Immediate(1)),
+        PlayPlAtPlayItem(Gpr(1), Psr(5)),
+        TerminatePl,
+        LinkMark(Immediate(2)),
+        Compare(CompareOp::Bc, Gpr(4095), Immediate(1)),
+        Set(SetOp::ShiftRight, Gpr(2), Immediate(4)),
+        SetSystem(SetSystemOp::StillOff, Immediate(0), Immediate(0)),
+    ];
+    for instruction in instructions.iter() {
+        assert_eq!(Command::from(*instruction).instruction(), *instruction);
+    }
+
+    // PlayPL 800
+    let command = Command {
+        opcode: 0x2280_0000,
+        dst: 800,
+        src: 0,
+    };
+    assert_eq!(command.instruction(), PlayPl(Immediate(800)));
+    assert_eq!(Command::from(PlayPl(Immediate(800))), command);
+
+    let garbage = Command {
+        opcode: 0xFFFF_FFFF,
+        dst: 0,
+        src: 0,
+    };
+    assert_eq!(garbage.instruction(), Unknown(garbage));
+    assert_eq!(garbage.to_string(), "Unknown FFFFFFFF 00000000 00000000");
+}
+
+#[test]
+fn playlists() {
+    let mobj = MovieObjects::from(&movie_objects_bytes(&objects())[..]).unwrap();
+
+    assert_eq!(mobj.playlists(), vec![vec![800, 801], vec![5], vec![]]);
+}
+
+#[test]
+fn reject_garbage() {
+    assert!(MovieObjects::from(&b"MOBJ0200"[..]).is_err());
+    assert!(MovieObjects::from(&include_bytes!("../assets/tiny.mpls")[..]).is_err());
+
+    let mut truncated = movie_objects_bytes(&objects());
+    truncated.truncate(60);
+    assert!(MovieObjects::from(&truncated[..]).is_err());
+}
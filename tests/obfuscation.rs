@@ -0,0 +1,122 @@
+mod common;
+
+use common::{clpi_bytes, write, ClpiSpec, TempDir};
+use mpls::obfuscation::find_decoy_groups;
+use mpls::{Disc, Mpls, TimeStamp};
+use std::collections::BTreeMap;
+
+fn permuted(order: &[usize]) -> Mpls {
+    let mut mpls = Mpls::from(&include_bytes!("../assets/simple.mpls")[..]).unwrap();
+    let items = mpls.play_list.play_items.clone();
+    mpls.play_list.play_items = order.iter().map(|i| items[*i].clone()).collect();
+    mpls
+}
+
+// the original order is 00055, 00059, 00061; 00801 plays 00059 first, 00802
+// plays the clips in the original order, but with descending clip numbers
+fn playlists() -> BTreeMap<u16, Mpls> {
+    let mut playlists = BTreeMap::new();
+    playlists.insert(
+        0,
+        Mpls::from(&include_bytes!("../assets/tiny.mpls")[..]).unwrap(),
+    );
+    playlists.insert(800, permuted(&[0, 1, 2]));
+    playlists.insert(801, permuted(&[1, 0, 2]));
+    playlists.insert(802, permuted(&[2, 1, 0]));
+    playlists
+}
+
+#[test]
+fn group_by_clips() {
+    let groups = find_decoy_groups(&playlists(), &BTreeMap::new());
+
+    assert_eq!(groups.len(), 1);
+    let group = &groups[0];
+    assert_eq!(group.clips, &["00055", "00059", "00061"]);
+    let numbers: Vec<u16> = group.playlists.iter().map(|p| p.playlist).collect();
+    assert_eq!(numbers, &[800, 801, 802]);
+
+    assert!(group.playlists[0].is_monotonic());
+    assert_eq!(group.playlists[1].order_breaks, &[1]);
+    assert_eq!(group.playlists[2].order_breaks, &[1, 2]);
+    assert!(group.playlists.iter().all(|p| p.is_consistent().is_none()));
+    assert_eq!(group.likely_originals(), &[800]);
+}
+
+// sets the in and out times of the play items of the given clips
+fn with_times(mut mpls: Mpls, times: &[(&str, u32, u32)]) -> Mpls {
+    for item in mpls.play_list.play_items.iter_mut() {
+        let (_, in_time, out_time) = times
+            .iter()
+            .find(|(name, _, _)| *name == item.clip.file_name)
+            .unwrap();
+        item.in_time = TimeStamp(*in_time);
+        item.out_time = TimeStamp(*out_time);
+    }
+    mpls
+}
+
+#[test]
+fn check_clip_timelines() {
+    let dir = TempDir::new();
+    let root = dir.path();
+    // the clips' whole timelines follow each other in the order of their
+    // numbers
+    let clips = [
+        ("00055", 188_925_000, 448_735_175),
+        ("00059", 448_735_175, 457_199_340),
+        ("00061", 457_199_340, 478_125_288),
+    ];
+    for (name, start, end) in clips.iter() {
+        let spec = ClpiSpec {
+            presentation_start: *start,
+            presentation_end: *end,
+            source_packets: 1_000,
+            entry_points: vec![(*start, 0)],
+        };
+        write(
+            root,
+            &format!("BDMV/CLIPINF/{}.clpi", name),
+            &clpi_bytes(&spec),
+        );
+    }
+    // but the play items continue each other in the order 00061, 00059,
+    // 00055, which is what counts
+    let times = [
+        ("00061", 300_000_000, 310_000_000),
+        ("00059", 310_000_000, 320_000_000),
+        ("00055", 320_020_000, 400_000_000),
+    ];
+    for (number, mpls) in playlists() {
+        let mpls = if number == 0 {
+            mpls
+        } else {
+            with_times(mpls, &times)
+        };
+        write(
+            root,
+            &format!("BDMV/PLAYLIST/{:05}.mpls", number),
+            &mpls.to_bytes().unwrap(),
+        );
+    }
+    let disc = Disc::open(root).unwrap();
+
+    let groups = disc.decoy_groups();
+    assert_eq!(groups.len(), 1);
+    let group = &groups[0];
+    assert_eq!(group.playlists[0].discontinuities, Some(vec![1, 2]));
+    assert_eq!(group.playlists[1].discontinuities, Some(vec![2]));
+    assert_eq!(group.playlists[2].discontinuities, Some(vec![]));
+    assert_eq!(group.playlists[2].is_consistent(), Some(true));
+    assert_eq!(group.likely_originals(), &[802]);
+
+    // without all of the clip information, the timelines can't be checked
+    std::fs::remove_file(root.join("BDMV/CLIPINF/00059.clpi")).unwrap();
+    let disc = Disc::open(root).unwrap();
+    let groups = disc.decoy_groups();
+    assert!(groups[0]
+        .playlists
+        .iter()
+        .all(|p| p.discontinuities.is_none()));
+    assert_eq!(groups[0].likely_originals(), &[800]);
+}
@@ -0,0 +1,118 @@
+use mpls::summary::MplsSummary;
+use mpls::{Mpls, TimeStamp};
+use std::time::Instant;
+
+const ASSETS: [&[u8]; 4] = [
+    include_bytes!("../assets/tiny.mpls"),
+    include_bytes!("../assets/simple.mpls"),
+    include_bytes!("../assets/multi-angle.mpls"),
+    include_bytes!("../assets/large.mpls"),
+];
+
+#[test]
+fn matches_full_parse() {
+    for bytes in ASSETS.iter() {
+        let mpls = Mpls::from(*bytes).unwrap();
+        let summary = MplsSummary::from(*bytes).unwrap();
+
+        assert_eq!(summary.version, mpls.version);
+        assert_eq!(summary.duration, mpls.duration());
+        assert_eq!(summary.play_items, mpls.play_list.play_items.len());
+        assert_eq!(summary.sub_paths, mpls.play_list.sub_paths.len());
+        assert_eq!(summary.angles, mpls.angles().len());
+        let clips: Vec<&str> = mpls
+            .play_list
+            .play_items
+            .iter()
+            .map(|p| p.clip.file_name.as_str())
+            .collect();
+        assert_eq!(summary.clips, clips);
+
+        let table = &mpls.play_list.play_items[0].stream_number_table;
+        let streams = summary.streams;
+        assert_eq!(
+            streams.primary_video as usize,
+            table.primary_video_streams.len()
+        );
+        assert_eq!(
+            streams.primary_audio as usize,
+            table.primary_audio_streams.len()
+        );
+        assert_eq!(
+            streams.primary_pgs as usize,
+            table.primary_pgs_streams.len()
+        );
+        assert_eq!(
+            streams.primary_igs as usize,
+            table.primary_igs_streams.len()
+        );
+        assert_eq!(
+            streams.secondary_audio as usize,
+            table.secondary_audio_streams.len()
+        );
+        assert_eq!(
+            streams.secondary_video as usize,
+            table.secondary_video_streams.len()
+        );
+    }
+}
+
+#[test]
+fn duration_saturates_like_full_parse() {
+    let mut mpls = Mpls::from(&include_bytes!("../assets/simple.mpls")[..]).unwrap();
+    for item in mpls.play_list.play_items.iter_mut() {
+        item.in_time = TimeStamp(0);
+        item.out_time = TimeStamp(u32::MAX / 2);
+    }
+    let summary = MplsSummary::from(&mpls.to_bytes().unwrap()[..]).unwrap();
+    assert_eq!(summary.duration, TimeStamp(u32::MAX));
+    assert_eq!(summary.duration, mpls.duration());
+}
+
+#[test]
+fn follows_the_play_list_address() {
+    // move the play list section back by 16 bytes of padding
+    let bytes = include_bytes!("../assets/simple.mpls");
+    let start = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+    let mut padded = bytes[..start].to_vec();
+    padded.extend_from_slice(&[0; 16]);
+    padded.extend_from_slice(&bytes[start..]);
+    padded[8..12].copy_from_slice(&(start as u32 + 16).to_be_bytes());
+
+    let summary = MplsSummary::from(&padded[..]).unwrap();
+    assert_eq!(summary, MplsSummary::from(&bytes[..]).unwrap());
+}
+
+#[test]
+fn rejects_invalid_playlists() {
+    assert!(MplsSummary::from(&b"MPLS0200"[..]).is_err());
+    assert!(MplsSummary::from(&b"HDMV0200\0\0\0\x28"[..]).is_err());
+
+    let bytes = include_bytes!("../assets/simple.mpls");
+    assert!(MplsSummary::from(&bytes[..bytes.len() / 2]).is_err());
+    let mut broken = bytes.to_vec();
+    broken[8..12].copy_from_slice(&(bytes.len() as u32).to_be_bytes());
+    assert!(MplsSummary::from(&broken[..]).is_err());
+}
+
+// timing-dependent, run with `cargo test --release -- --ignored`
+#[test]
+#[ignore]
+fn faster_than_full_parse() {
+    let bytes = include_bytes!("../assets/large.mpls");
+    let runs = 200;
+
+    let start = Instant::now();
+    for _ in 0..runs {
+        Mpls::from(&bytes[..]).unwrap();
+    }
+    let full = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..runs {
+        MplsSummary::from(&bytes[..]).unwrap();
+    }
+    let summary = start.elapsed();
+
+    assert!(summary * 2 < full, "{:?} vs. {:?}", summary, full);
+}
@@ -0,0 +1,220 @@
+mod common;
+
+use common::{udf_image, write, TempDir};
+use mpls::udf::UdfImage;
+use mpls::{Disc, MplsError};
+use std::io::Cursor;
+
+// a byte pattern that differs from block to block
+fn stream(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i / 7 % 251) as u8).collect()
+}
+
+fn disc_image() -> Vec<u8> {
+    let spec = common::ClpiSpec {
+        presentation_start: 27_000_000,
+        presentation_end: 27_011_261,
+        source_packets: 30,
+        entry_points: vec![(27_000_000, 0)],
+    };
+    let index = common::index_bytes(
+        common::IndexObjectSpec::None,
+        common::IndexObjectSpec::None,
+        &[common::IndexObjectSpec::Hdmv(0)],
+    );
+    udf_image(&[
+        ("BDMV/index.bdmv", &index),
+        (
+            "BDMV/PLAYLIST/00800.mpls",
+            include_bytes!("../assets/simple.mpls"),
+        ),
+        (
+            "BDMV/PLAYLIST/00000.mpls",
+            include_bytes!("../assets/tiny.mpls"),
+        ),
+        ("BDMV/PLAYLIST/00900.mpls", b"MPLS0200 but not really"),
+        ("BDMV/CLIPINF/00055.clpi", &common::clpi_bytes(&spec)),
+        ("BDMV/STREAM/00055.m2ts", &stream(192 * 30)),
+        ("BDMV/CLIPINF/00059.clpi", &[0; 80]),
+        ("BDMV/STREAM/SSIF/00059.ssif", &stream(192 * 8)),
+        ("BDMV/CLIPINF/00061.clpi", &[0; 60]),
+        ("BDMV/STREAM/00061.m2ts", &stream(100)),
+        ("CERTIFICATE/id.bdmv", b"cert"),
+    ])
+}
+
+#[test]
+fn read_directories_and_files() {
+    let mut image = UdfImage::open(Cursor::new(disc_image())).unwrap();
+
+    let root: Vec<(String, bool)> = image
+        .read_dir("")
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.name, e.is_dir))
+        .collect();
+    assert_eq!(
+        root,
+        &[
+            ("BDMV".to_string(), true),
+            ("CERTIFICATE".to_string(), true)
+        ]
+    );
+
+    let streams = image.read_dir("/BDMV/STREAM").unwrap();
+    let names: Vec<&str> = streams.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, &["00055.m2ts", "00061.m2ts", "SSIF"]);
+    assert_eq!(streams[0].size, 192 * 30);
+
+    let bytes = image.read("BDMV/PLAYLIST/00800.mpls").unwrap();
+    assert_eq!(&bytes[..], &include_bytes!("../assets/simple.mpls")[..]);
+    assert_eq!(image.read("CERTIFICATE/id.bdmv").unwrap(), b"cert");
+    assert_eq!(
+        image.read("BDMV/STREAM/00055.m2ts").unwrap(),
+        stream(192 * 30)
+    );
+
+    // ranges may span several extents and are clamped to the file size
+    let expected = stream(192 * 30);
+    let range = image
+        .read_range("BDMV/STREAM/00055.m2ts", 2000..4200)
+        .unwrap();
+    assert_eq!(range, &expected[2000..4200]);
+    let range = image
+        .read_range("BDMV/STREAM/00055.m2ts", 5000..9000)
+        .unwrap();
+    assert_eq!(range, &expected[5000..]);
+    assert!(image
+        .read_range("BDMV/STREAM/00055.m2ts", 9000..9100)
+        .unwrap()
+        .is_empty());
+
+    let mut copy = Vec::new();
+    let n = image.copy_to("BDMV/STREAM/00061.m2ts", &mut copy).unwrap();
+    assert_eq!(n, 100);
+    assert_eq!(copy, stream(100));
+
+    assert!(image.read("BDMV/PLAYLIST/00801.mpls").is_err());
+    assert!(image.read("BDMV/index.bdmv/x").is_err());
+    assert!(image.read_dir("BDMV/index.bdmv").is_err());
+    assert!(image.file("BDMV").unwrap().is_dir());
+}
+
+#[test]
+fn open_falls_back_to_the_last_anchor() {
+    let mut bytes = disc_image();
+    for b in bytes[256 * 2048..257 * 2048].iter_mut() {
+        *b = 0;
+    }
+    let mut image = UdfImage::open(Cursor::new(bytes)).unwrap();
+    assert_eq!(image.read("CERTIFICATE/id.bdmv").unwrap(), b"cert");
+}
+
+#[test]
+fn open_rejects_other_images() {
+    assert!(UdfImage::open(Cursor::new(vec![0; 2048 * 300])).is_err());
+    assert!(UdfImage::open(Cursor::new(vec![0; 100])).is_err());
+
+    // a broken descriptor checksum
+    let mut bytes = disc_image();
+    bytes[256 * 2048 + 4] ^= 1;
+    let last = bytes.len() - 2048;
+    bytes[last + 4] ^= 1;
+    assert!(UdfImage::open(Cursor::new(bytes)).is_err());
+}
+
+#[test]
+fn open_rejects_other_block_sizes() {
+    // the logical block size of the logical volume descriptor in sector 33
+    for size in [512u32, 4096, u32::MAX].iter() {
+        let mut bytes = disc_image();
+        bytes[33 * 2048 + 212..33 * 2048 + 216].copy_from_slice(&size.to_le_bytes());
+        assert!(matches!(
+            UdfImage::open(Cursor::new(bytes)),
+            Err(MplsError::UdfParseError)
+        ));
+    }
+}
+
+#[test]
+fn open_iso() {
+    let dir = TempDir::new();
+    write(dir.path(), "movie.iso", &disc_image());
+    let iso = dir.path().join("movie.iso");
+    let disc = Disc::open_iso(&iso).unwrap();
+
+    assert_eq!(disc.root, iso.join("BDMV"));
+    assert_eq!(
+        disc.playlists.keys().copied().collect::<Vec<_>>(),
+        &[0, 800]
+    );
+    assert_eq!(disc.errors.len(), 1);
+    assert!(disc.errors[0].0.ends_with("00900.mpls"));
+    assert_eq!(disc.index.as_ref().unwrap().titles.len(), 1);
+
+    let mpls = disc.playlist(800).unwrap();
+    let files = disc.clip_files(&mpls.play_list.play_items[0].clip);
+    let stream_path = iso.join("BDMV/STREAM/00055.m2ts");
+    assert_eq!(files.stream.as_ref(), Some(&stream_path));
+    assert!(files.is_complete());
+    let files = disc.clip_files(&mpls.play_list.play_items[1].clip);
+    assert!(files.stream.is_none());
+    assert!(files.ssif.is_some());
+    // only the clip of 00000.mpls is missing
+    let clip = &disc.playlist(0).unwrap().play_list.play_items[0].clip;
+    assert_eq!(
+        disc.missing_files(),
+        &[
+            iso.join(format!("BDMV/CLIPINF/{}.clpi", clip.file_name)),
+            iso.join(format!("BDMV/STREAM/{}.m2ts", clip.file_name)),
+        ]
+    );
+
+    let expected = stream(192 * 30);
+    assert_eq!(
+        disc.read_range(&stream_path, 192..384).unwrap(),
+        &expected[192..384]
+    );
+    assert_eq!(disc.read_file(&stream_path).unwrap(), expected);
+    assert!(disc.read_file(dir.path().join("other.iso")).is_err());
+
+    let clpi = disc.clip_info(&mpls.play_list.play_items[0].clip).unwrap();
+    assert_eq!(clpi.clip_info.number_of_source_packets, 30);
+    assert!(disc.clip_info(clip).is_err());
+
+    let plan = disc.backup_plan(800, None).unwrap();
+    assert!(plan.is_complete());
+    assert_eq!(
+        plan.total_size(),
+        include_bytes!("../assets/simple.mpls").len() as u64
+            + disc
+                .read_file(iso.join("BDMV/CLIPINF/00055.clpi"))
+                .unwrap()
+                .len() as u64
+            + 192 * 30
+            + 80
+            + 192 * 8
+            + 60
+            + 100
+    );
+
+    let dest = TempDir::new();
+    disc.export_playlist(800, None, dest.path()).unwrap();
+    let exported = dest.path().join("BDMV/STREAM/00055.m2ts");
+    assert_eq!(std::fs::read(exported).unwrap(), expected);
+    let exported = dest.path().join("BDMV/STREAM/SSIF/00059.ssif");
+    assert_eq!(std::fs::read(exported).unwrap(), stream(192 * 8));
+    assert!(Disc::open(dest.path()).unwrap().errors.is_empty());
+}
+
+#[test]
+fn open_iso_without_bdmv_fails() {
+    let dir = TempDir::new();
+    write(
+        dir.path(),
+        "data.iso",
+        &udf_image(&[("VIDEO_TS/VIDEO_TS.IFO", b"DVDVIDEO-VMG")]),
+    );
+    assert!(Disc::open_iso(dir.path().join("data.iso")).is_err());
+    assert!(Disc::open_iso(dir.path().join("missing.iso")).is_err());
+}
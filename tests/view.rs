@@ -0,0 +1,152 @@
+use mpls::view::MplsView;
+use mpls::{Mpls, TimeStamp};
+
+const ASSETS: [&[u8]; 4] = [
+    include_bytes!("../assets/tiny.mpls"),
+    include_bytes!("../assets/simple.mpls"),
+    include_bytes!("../assets/multi-angle.mpls"),
+    include_bytes!("../assets/large.mpls"),
+];
+
+fn address(bytes: &[u8], offset: usize) -> usize {
+    u32::from_be_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ]) as usize
+}
+
+// inserts padding in front of a section and moves the following sections'
+// addresses accordingly
+fn pad_before(bytes: &[u8], position: usize, len: usize) -> Vec<u8> {
+    let mut padded = bytes[..position].to_vec();
+    padded.extend(vec![0; len]);
+    padded.extend_from_slice(&bytes[position..]);
+    for offset in [8, 12, 16].iter() {
+        let a = address(bytes, *offset);
+        if a != 0 && a >= position {
+            padded[*offset..*offset + 4].copy_from_slice(&((a + len) as u32).to_be_bytes());
+        }
+    }
+    padded
+}
+
+#[test]
+fn matches_full_parse() {
+    for bytes in ASSETS.iter() {
+        let mpls = Mpls::from(*bytes).unwrap();
+        let view = MplsView::new(bytes).unwrap();
+        assert_eq!(view.version(), mpls.version);
+        assert_eq!(
+            view.play_item_count().unwrap(),
+            mpls.play_list.play_items.len()
+        );
+        assert_eq!(
+            view.sub_paths().unwrap().len(),
+            mpls.play_list.sub_paths.len()
+        );
+        assert_eq!(view.marks().unwrap().len(), mpls.marks.len());
+        assert_eq!(view.ext().unwrap().len(), mpls.ext.len());
+        assert_eq!(view.duration().unwrap(), mpls.duration());
+        assert_eq!(
+            view.to_mpls().unwrap().to_bytes().unwrap(),
+            mpls.to_bytes().unwrap()
+        );
+    }
+}
+
+#[test]
+fn duration_saturates_like_full_parse() {
+    let mut mpls = Mpls::from(&include_bytes!("../assets/simple.mpls")[..]).unwrap();
+    for item in mpls.play_list.play_items.iter_mut() {
+        item.in_time = TimeStamp(0);
+        item.out_time = TimeStamp(u32::MAX / 2);
+    }
+    let bytes = mpls.to_bytes().unwrap();
+    let view = MplsView::new(&bytes).unwrap();
+    assert_eq!(view.duration().unwrap(), TimeStamp(u32::MAX));
+    assert_eq!(view.duration().unwrap(), mpls.duration());
+}
+
+#[test]
+fn caches_parsed_sections() {
+    let bytes = include_bytes!("../assets/large.mpls");
+    let view = MplsView::new(&bytes[..]).unwrap();
+    let first = view.play_item(3).unwrap().unwrap();
+    let second = view.play_item(3).unwrap().unwrap();
+    assert!(std::ptr::eq(first, second));
+    assert!(std::ptr::eq(view.marks().unwrap(), view.marks().unwrap()));
+    assert!(view.play_item(135).unwrap().is_none());
+}
+
+#[test]
+fn parses_play_items_on_demand() {
+    let bytes = include_bytes!("../assets/simple.mpls");
+    let mpls = Mpls::from(&bytes[..]).unwrap();
+
+    // break the stream number table of the last play item
+    let mut broken = bytes.to_vec();
+    let start = address(bytes, 8) + 10;
+    let mut offset = start;
+    for _ in 0..2 {
+        offset += 2 + u16::from_be_bytes([broken[offset], broken[offset + 1]]) as usize;
+    }
+    let stn = offset + 2 + 32;
+    // an invalid stream type
+    broken[stn + 17] = 0x09;
+    assert!(Mpls::from(&broken[..]).is_err());
+
+    let view = MplsView::new(&broken[..]).unwrap();
+    assert_eq!(view.play_item_count().unwrap(), 3);
+    let first = view.play_item(0).unwrap().unwrap();
+    assert_eq!(
+        first.clip.file_name,
+        mpls.play_list.play_items[0].clip.file_name
+    );
+    assert!(view.play_item(2).is_err());
+    assert!(view.play_items().is_err());
+    assert_eq!(view.marks().unwrap().len(), mpls.marks.len());
+}
+
+#[test]
+fn ignores_padding_between_sections() {
+    for bytes in ASSETS.iter() {
+        let mpls = Mpls::from(*bytes).unwrap();
+        let mut padded = pad_before(bytes, address(bytes, 8), 12);
+        padded = pad_before(&padded, address(&padded, 12), 6);
+        let ext = address(&padded, 16);
+        if ext != 0 {
+            padded = pad_before(&padded, ext, 30);
+        }
+
+        let view = MplsView::new(&padded).unwrap();
+        let (play_list, marks, _) = view.sections();
+        assert_eq!(play_list.start, address(bytes, 8) + 12);
+        assert_eq!(marks.start, address(bytes, 12) + 18);
+        assert_eq!(
+            view.to_mpls().unwrap().to_bytes().unwrap(),
+            mpls.to_bytes().unwrap()
+        );
+    }
+}
+
+#[test]
+fn validates_the_header() {
+    assert!(MplsView::new(b"MPLS0200").is_err());
+    assert!(MplsView::new(b"HDMV0200").is_err());
+
+    let bytes = include_bytes!("../assets/simple.mpls");
+    // out of bounds
+    let mut broken = bytes.to_vec();
+    broken[12..16].copy_from_slice(&(bytes.len() as u32 + 1).to_be_bytes());
+    assert!(MplsView::new(&broken[..]).is_err());
+    // out of order
+    let mut broken = bytes.to_vec();
+    broken[8..12].copy_from_slice(&(address(bytes, 12) as u32 + 1).to_be_bytes());
+    assert!(MplsView::new(&broken[..]).is_err());
+    // overlapping the header
+    let mut broken = bytes.to_vec();
+    broken[8..12].copy_from_slice(&20u32.to_be_bytes());
+    assert!(MplsView::new(&broken[..]).is_err());
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use common::{index_bytes, movie_objects_bytes, IndexObjectSpec};
+use mpls::movie_objects::{CompareOp, Instruction::*, Operand::*, SetOp};
+use mpls::vm::{PlayStart, Vm, VmConfig, VmStop};
+use mpls::{Index, MovieObjects};
+
+const DEU: u32 = 0x64_65_75;
+
+fn disc() -> (Index, MovieObjects) {
+    let index = index_bytes(
+        IndexObjectSpec::Hdmv(3),
+        IndexObjectSpec::Hdmv(4),
+        &[
+            IndexObjectSpec::Hdmv(0),
+            IndexObjectSpec::Hdmv(1),
+            IndexObjectSpec::BdJ("00001"),
+            IndexObjectSpec::Hdmv(2),
+        ],
+    );
+    let objects = movie_objects_bytes(&[
+        // 0: picks a playlist by audio language, then goes to the top menu
+        vec![
+            Set(SetOp::Move, Gpr(0), Immediate(801)),
+            Compare(CompareOp::Eq, Psr(16), Immediate(DEU)),
+            Set(SetOp::Move, Gpr(0), Immediate(800)),
+            PlayPl(Gpr(0)),
+            JumpTitle(Immediate(0)),
+        ],
+        // 1: calls object 5 for a warning, then plays a chapter
+        vec![
+            CallObject(Immediate(5)),
+            PlayPlAtMark(Immediate(802), Immediate(3)),
+            JumpTitle(Immediate(4)),
+        ],
+        // 2: loops forever
+        vec![Nop, Goto(Immediate(0))],
+        // 3: first playback
+        vec![PlayPl(Immediate(10)), JumpObject(Immediate(4))],
+        // 4: top menu, selects the region specific menu
+        vec![
+            Compare(CompareOp::Bc, Psr(20), Immediate(2)),
+            JumpObject(Immediate(6)),
+            PlayPl(Immediate(20)),
+            Break,
+        ],
+        // 5: warning
+        vec![PlayPl(Immediate(1)), Resume],
+        // 6: region B menu
+        vec![PlayPl(Immediate(21))],
+    ]);
+    (
+        Index::from(&index[..]).unwrap(),
+        MovieObjects::from(&objects[..]).unwrap(),
+    )
+}
+
+#[test]
+fn language_dependent_playlist() {
+    let (index, mobj) = disc();
+
+    let mut vm = Vm::new(&index, &mobj, VmConfig::default());
+    let result = vm.run_title(1);
+    assert_eq!(result.playlists(), &[801]);
+    assert_eq!(result.stop, VmStop::Title(0));
+    // the skipped command doesn't count
+    assert_eq!(result.steps, 4);
+    assert_eq!(vm.psr[4], 1);
+    assert_eq!(vm.psr[6], 801);
+    assert_eq!(vm.gpr[0], 801);
+
+    let config = VmConfig {
+        audio_language: "deu".into(),
+        ..VmConfig::default()
+    };
+    let mut vm = Vm::new(&index, &mobj, config);
+    assert_eq!(vm.run_title(1).playlists(), &[800]);
+}
+
+#[test]
+fn overflowing_arithmetic_saturates() {
+    let index = index_bytes(
+        IndexObjectSpec::None,
+        IndexObjectSpec::None,
+        &[IndexObjectSpec::Hdmv(0)],
+    );
+    let objects = movie_objects_bytes(&[vec![
+        Set(SetOp::Move, Gpr(0), Immediate(0x10000)),
+        Set(SetOp::Mul, Gpr(0), Immediate(0x10000)),
+        Set(SetOp::Move, Gpr(1), Immediate(0x10000)),
+        Set(SetOp::Add, Gpr(1), Gpr(0)),
+        Compare(CompareOp::Eq, Gpr(0), Immediate(0)),
+        PlayPl(Immediate(800)),
+        PlayPl(Immediate(801)),
+    ]]);
+    let index = Index::from(&index[..]).unwrap();
+    let mobj = MovieObjects::from(&objects[..]).unwrap();
+
+    let mut vm = Vm::new(&index, &mobj, VmConfig::default());
+    let result = vm.run_title(1);
+    assert_eq!(vm.gpr[0], u32::MAX);
+    assert_eq!(vm.gpr[1], u32::MAX);
+    // a wrapping product would have been 0
+    assert_eq!(result.playlists(), &[801]);
+}
+
+#[test]
+fn call_and_resume() {
+    let (index, mobj) = disc();
+
+    let mut vm = Vm::new(&index, &mobj, VmConfig::default());
+    let result = vm.run_title(2);
+    assert_eq!(result.playlists(), &[1, 802]);
+    assert_eq!(result.played[0].object, 5);
+    assert_eq!(result.played[1].start, PlayStart::Mark(3));
+    assert_eq!(result.stop, VmStop::Title(4));
+}
+
+#[test]
+fn follow_title_jumps() {
+    let (index, mobj) = disc();
+
+    let config = VmConfig {
+        follow_title_jumps: true,
+        ..VmConfig::default()
+    };
+    let mut vm = Vm::new(&index, &mobj, config.clone());
+    let result = vm.run_title(2);
+    assert_eq!(result.stop, VmStop::StepLimit);
+    assert_eq!(result.steps, config.step_limit);
+    assert_eq!(vm.psr[4], 4);
+
+    // title 1 jumps to title 0, which isn't a numbered title
+    let mut vm = Vm::new(&index, &mobj, config);
+    assert_eq!(vm.run_title(1).stop, VmStop::InvalidTitle(0));
+}
+
+#[test]
+fn region_dependent_menu() {
+    let (index, mobj) = disc();
+
+    let mut vm = Vm::new(&index, &mobj, VmConfig::default());
+    let result = vm.run_first_playback();
+    assert_eq!(result.playlists(), &[10, 20]);
+    assert_eq!(result.stop, VmStop::Finished);
+
+    let config = VmConfig {
+        region: 2,
+        ..VmConfig::default()
+    };
+    let mut vm = Vm::new(&index, &mobj, config);
+    assert_eq!(vm.run_top_menu().playlists(), &[21]);
+    assert_eq!(vm.psr[4], 0);
+}
+
+#[test]
+fn unsupported_titles() {
+    let (index, mobj) = disc();
+
+    let mut vm = Vm::new(&index, &mobj, VmConfig::default());
+    assert_eq!(vm.run_title(3).stop, VmStop::BdJTitle(3));
+    assert_eq!(vm.run_title(5).stop, VmStop::InvalidTitle(5));
+    assert_eq!(vm.run_object(9).stop, VmStop::InvalidObject(9));
+}
+
+#[test]
+fn trace() {
+    let (index, mobj) = disc();
+
+    let config = VmConfig {
+        step_limit: 5,
+        trace: true,
+        ..VmConfig::default()
+    };
+    let mut vm = Vm::new(&index, &mobj, config);
+    let result = vm.run_title(4);
+    assert_eq!(result.stop, VmStop::StepLimit);
+    let trace: Vec<String> = result.trace.iter().map(|s| s.to_string()).collect();
+    assert_eq!(
+        trace,
+        &[
+            "    2:0    Nop",
+            "    2:1    GoTo 0",
+            "    2:0    Nop",
+            "    2:1    GoTo 0",
+            "    2:0    Nop",
+        ]
+    );
+}